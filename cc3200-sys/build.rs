@@ -3,7 +3,17 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 extern crate common_build;
+
+use std::env;
+
 fn main() {
+    // The C code only builds for the board. Host builds, used to run the
+    // tests, only get the Rust side.
+    let target = env::var("TARGET").unwrap_or(String::new());
+    if !target.starts_with("thumb") && !target.starts_with("arm") {
+        return;
+    }
+
     common_build::gcc_config()
         .include(".")
         .include("sdk")
@@ -23,11 +33,13 @@ fn main() {
         .file("StrPrintf.c")
         .file("freertos_rs.c")
         .file("sdk/driverlib/cpu.c")
+        .file("sdk/driverlib/crc.c")
         .file("sdk/driverlib/gpio.c")
         .file("sdk/driverlib/interrupt.c")
         .file("sdk/driverlib/i2c.c")
         .file("sdk/driverlib/pin.c")
         .file("sdk/driverlib/prcm.c")
+        .file("sdk/driverlib/shamd5.c")
         .file("sdk/driverlib/spi.c")
         .file("sdk/driverlib/uart.c")
         .file("sdk/driverlib/udma.c")
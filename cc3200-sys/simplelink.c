@@ -24,12 +24,37 @@ unsigned long  g_ulDestinationIP; // IP address of destination server
 unsigned long  g_ulGatewayIP = 0; // Network Gateway IP address
 unsigned char  g_ucConnectionSSID[SSID_LEN_MAX+1]; //Connection SSID
 unsigned char  g_ucConnectionBSSID[BSSID_LEN_MAX]; //Connection BSSID
+SlIpV6AcquiredAsync_t g_Ipv6Info; // Last IPv6 addressing reported by the NWP
+bool g_bIpv6Acquired = false;
+
+// Implemented in Rust (src/events.rs). These forward the asynchronous
+// SimpleLink events to the Rust subscribers.
+extern void simplelink_on_wlan_connect(const uint8_t *ssid, uint8_t ssid_len,
+                                       const uint8_t *bssid);
+extern void simplelink_on_wlan_disconnect(uint8_t reason_code);
+extern void simplelink_on_wlan_sta_connect(const uint8_t *mac);
+extern void simplelink_on_wlan_sta_disconnect(const uint8_t *mac);
+extern void simplelink_on_p2p_dev_found(const uint8_t *name, uint8_t name_len,
+                                        const uint8_t *mac, uint8_t wps_dev_password_id);
+extern void simplelink_on_p2p_neg_req(const uint8_t *name, uint8_t name_len,
+                                      const uint8_t *mac, uint8_t wps_dev_password_id);
+extern void simplelink_on_wlan_connection_failed(uint16_t status);
+extern void simplelink_on_ipv4_acquired(uint32_t ip, uint32_t gateway, uint32_t dns);
+extern void simplelink_on_ipv6_acquired(const uint32_t *ip, const uint32_t *gateway,
+                                        const uint32_t *dns);
+extern void simplelink_on_ip_leased(uint32_t ip, uint32_t lease_time, const uint8_t *mac);
+extern void simplelink_on_ip_released(uint32_t ip, const uint8_t *mac, uint16_t reason);
+extern void simplelink_on_sock_tx_failed(uint8_t sd, int16_t status);
+extern void simplelink_on_sock_async(uint8_t sd, uint8_t type, int16_t val);
+extern void simplelink_on_device_error(int8_t status, uint32_t sender);
 
 void simplelink_init_app_variables(void) {
     g_ulStatus = 0;
     g_ulGatewayIP = 0;
     memset(g_ucConnectionSSID,0,sizeof(g_ucConnectionSSID));
     memset(g_ucConnectionBSSID,0,sizeof(g_ucConnectionBSSID));
+    memset(&g_Ipv6Info,0,sizeof(g_Ipv6Info));
+    g_bIpv6Acquired = false;
 }
 
 bool simplelink_get_status_bit(uint32_t bit) {
@@ -44,12 +69,6 @@ void simplelink_set_status_bit(uint32_t bit) {
     SET_STATUS_BIT(g_ulStatus, bit);
 }
 
-void simplelink_get_version(SlVersionFull *ver) {
-    uint8_t ucConfigOpt = SL_DEVICE_GENERAL_VERSION;
-    uint8_t ucConfigLen = sizeof(*ver);
-    sl_DevGet(SL_DEVICE_GENERAL_CONFIGURATION, &ucConfigOpt, &ucConfigLen, (uint8_t *)(ver));
-}
-
 const char *simplelink_get_driver_version(uint32_t *len) {
     *len = sizeof(SL_DRIVER_VERSION) - 1; // sizeof includes the terminating nul
     return SL_DRIVER_VERSION;
@@ -59,6 +78,48 @@ uint32_t simplelink_gateway_ip(void) {
     return g_ulGatewayIP;
 }
 
+// Copies the last IPv6 addressing reported by the NWP, as 4 words each.
+// Returns false if no IPv6 address was acquired yet.
+bool simplelink_get_ipv6_info(uint32_t *ip, uint32_t *gateway, uint32_t *dns) {
+    if (!g_bIpv6Acquired) {
+        return false;
+    }
+    memcpy(ip, g_Ipv6Info.ip, sizeof(g_Ipv6Info.ip));
+    memcpy(gateway, g_Ipv6Info.gateway, sizeof(g_Ipv6Info.gateway));
+    memcpy(dns, g_Ipv6Info.dns, sizeof(g_Ipv6Info.dns));
+    return true;
+}
+
+// Reads up to len bytes from the NWP true random number generator and returns
+// the number of bytes read, or a negative error code. The 1.x SDKs don't have
+// the net utilities, in which case no byte is returned.
+int32_t simplelink_get_true_random(uint8_t *buf, uint16_t len) {
+#ifdef SL_NETUTIL_TRUE_RANDOM
+    _u16 out_len = len;
+    _i32 rc = sl_NetUtilGet(SL_NETUTIL_TRUE_RANDOM, 0, buf, &out_len);
+    return rc < 0 ? rc : out_len;
+#else
+    (void)buf;
+    (void)len;
+    return 0;
+#endif
+}
+
+// Copies the SSID and BSSID of the current connection, and returns the SSID
+// length (0 when not connected). ssid must hold SSID_LEN_MAX bytes.
+uint8_t simplelink_get_connection_info(uint8_t *ssid, uint8_t *bssid) {
+    if (!GET_STATUS_BIT(g_ulStatus, STATUS_BIT_CONNECTION)) {
+        return 0;
+    }
+    uint8_t len = 0;
+    while (len < SSID_LEN_MAX && g_ucConnectionSSID[len] != 0) {
+        len++;
+    }
+    memcpy(ssid, g_ucConnectionSSID, SSID_LEN_MAX);
+    memcpy(bssid, g_ucConnectionBSSID, BSSID_LEN_MAX);
+    return len;
+}
+
 uint32_t simplelink_ping_packets_received(void) {
     return g_ulPingPacketsRecv;
 }
@@ -94,6 +155,11 @@ void SimpleLinkWlanEventHandler(SlWlanEvent_t *pWlanEvent)
                       g_ucConnectionBSSID[1],g_ucConnectionBSSID[2],
                       g_ucConnectionBSSID[3],g_ucConnectionBSSID[4],
                       g_ucConnectionBSSID[5]);
+
+            simplelink_on_wlan_connect(
+                pWlanEvent->EventData.STAandP2PModeWlanConnected.ssid_name,
+                pWlanEvent->EventData.STAandP2PModeWlanConnected.ssid_len,
+                pWlanEvent->EventData.STAandP2PModeWlanConnected.bssid);
         }
         break;
 
@@ -128,6 +194,60 @@ void SimpleLinkWlanEventHandler(SlWlanEvent_t *pWlanEvent)
             }
             memset(g_ucConnectionSSID,0,sizeof(g_ucConnectionSSID));
             memset(g_ucConnectionBSSID,0,sizeof(g_ucConnectionBSSID));
+
+            simplelink_on_wlan_disconnect(pEventData->reason_code);
+        }
+        break;
+
+        case SL_WLAN_STA_CONNECTED_EVENT:
+        {
+            // A station connected to us (AP mode)
+            SET_STATUS_BIT(g_ulStatus, STATUS_BIT_CONNECTION);
+            simplelink_on_wlan_sta_connect(pWlanEvent->EventData.APModeStaConnected.mac);
+        }
+        break;
+
+        case SL_WLAN_STA_DISCONNECTED_EVENT:
+        {
+            // A station disconnected from us (AP mode)
+            CLR_STATUS_BIT(g_ulStatus, STATUS_BIT_CONNECTION);
+            CLR_STATUS_BIT(g_ulStatus, STATUS_BIT_IP_LEASED);
+            simplelink_on_wlan_sta_disconnect(pWlanEvent->EventData.APModestaDisconnected.mac);
+        }
+        break;
+
+        case SL_WLAN_P2P_DEV_FOUND_EVENT:
+        {
+            slPeerInfoAsyncResponse_t *pEventData =
+                &pWlanEvent->EventData.P2PModeDevFound;
+
+            SET_STATUS_BIT(g_ulStatus, STATUS_BIT_P2P_DEV_FOUND);
+            simplelink_on_p2p_dev_found(pEventData->go_peer_device_name,
+                                        pEventData->go_peer_device_name_len,
+                                        pEventData->mac,
+                                        pEventData->wps_dev_password_id);
+        }
+        break;
+
+        case SL_WLAN_P2P_NEG_REQ_RECEIVED_EVENT:
+        {
+            slPeerInfoAsyncResponse_t *pEventData =
+                &pWlanEvent->EventData.P2PModeNegReqReceived;
+
+            SET_STATUS_BIT(g_ulStatus, STATUS_BIT_P2P_REQ_RECEIVED);
+            simplelink_on_p2p_neg_req(pEventData->go_peer_device_name,
+                                      pEventData->go_peer_device_name_len,
+                                      pEventData->mac,
+                                      pEventData->wps_dev_password_id);
+        }
+        break;
+
+        case SL_WLAN_CONNECTION_FAILED_EVENT:
+        {
+            // P2P negotiation or connection failure
+            SET_STATUS_BIT(g_ulStatus, STATUS_BIT_CONNECTION_FAILED);
+            simplelink_on_wlan_connection_failed(
+                pWlanEvent->EventData.P2PModewlanConnectionFailure.status);
         }
         break;
 
@@ -149,6 +269,9 @@ void SimpleLinkGeneralEventHandler(SlDeviceEvent_t *pDevEvent)
     UART_PRINT("[GENERAL EVENT] - ID=[%d] Sender=[%d]\n\n",
                pDevEvent->EventData.deviceEvent.status,
                pDevEvent->EventData.deviceEvent.sender);
+
+    simplelink_on_device_error(pDevEvent->EventData.deviceEvent.status,
+                               pDevEvent->EventData.deviceEvent.sender);
 }
 
 void SimpleLinkNetAppEventHandler(SlNetAppEvent_t *pNetAppEvent)
@@ -177,6 +300,43 @@ void SimpleLinkNetAppEventHandler(SlNetAppEvent_t *pNetAppEvent)
             SL_IPV4_BYTE(pNetAppEvent->EventData.ipAcquiredV4.gateway,2),
             SL_IPV4_BYTE(pNetAppEvent->EventData.ipAcquiredV4.gateway,1),
             SL_IPV4_BYTE(pNetAppEvent->EventData.ipAcquiredV4.gateway,0));
+
+            simplelink_on_ipv4_acquired(pEventData->ip, pEventData->gateway, pEventData->dns);
+        }
+        break;
+
+        case SL_NETAPP_IPV6_IPACQUIRED_EVENT:
+        {
+            SlIpV6AcquiredAsync_t *pEventData = &pNetAppEvent->EventData.ipAcquiredV6;
+
+            g_Ipv6Info = *pEventData;
+            g_bIpv6Acquired = true;
+
+            UART_PRINT("[NETAPP EVENT] IPv6 Acquired: IP=%08x:%08x:%08x:%08x\n\r",
+                       pEventData->ip[0], pEventData->ip[1],
+                       pEventData->ip[2], pEventData->ip[3]);
+
+            simplelink_on_ipv6_acquired(pEventData->ip, pEventData->gateway, pEventData->dns);
+        }
+        break;
+
+        case SL_NETAPP_IP_LEASED_EVENT:
+        {
+            SET_STATUS_BIT(g_ulStatus, STATUS_BIT_IP_LEASED);
+
+            simplelink_on_ip_leased(pNetAppEvent->EventData.ipLeased.ip_address,
+                                    pNetAppEvent->EventData.ipLeased.lease_time,
+                                    pNetAppEvent->EventData.ipLeased.mac);
+        }
+        break;
+
+        case SL_NETAPP_IP_RELEASED_EVENT:
+        {
+            CLR_STATUS_BIT(g_ulStatus, STATUS_BIT_IP_LEASED);
+
+            simplelink_on_ip_released(pNetAppEvent->EventData.ipReleased.ip_address,
+                                      pNetAppEvent->EventData.ipReleased.mac,
+                                      pNetAppEvent->EventData.ipReleased.reason);
         }
         break;
 
@@ -191,9 +351,6 @@ void SimpleLinkNetAppEventHandler(SlNetAppEvent_t *pNetAppEvent)
 
 void SimpleLinkSockEventHandler(SlSockEvent_t *pSock)
 {
-    //
-    // This application doesn't work w/ socket - Events are not expected
-    //
        switch( pSock->Event )
     {
         case SL_SOCKET_TX_FAILED_EVENT:
@@ -210,6 +367,14 @@ void SimpleLinkSockEventHandler(SlSockEvent_t *pSock)
                         pSock->socketAsyncEvent.SockAsyncData.sd,
                         pSock->socketAsyncEvent.SockTxFailData.status);
             }
+            simplelink_on_sock_tx_failed(pSock->socketAsyncEvent.SockTxFailData.sd,
+                                         pSock->socketAsyncEvent.SockTxFailData.status);
+            break;
+
+        case SL_SOCKET_ASYNC_EVENT:
+            simplelink_on_sock_async(pSock->socketAsyncEvent.SockAsyncData.sd,
+                                     pSock->socketAsyncEvent.SockAsyncData.type,
+                                     pSock->socketAsyncEvent.SockAsyncData.val);
             break;
 
         default:
@@ -229,13 +394,19 @@ void SimpleLinkPingReport(SlPingReport_t *pPingReport)
     g_ulPingPacketsRecv = pPingReport->PacketsReceived;
 }
 
-_u32 sl_FsMode(bool write, bool create, bool failsafe, _u32 maxCreateSize)
+// flags are SlFileOpenFlags_e values, only used when creating a file.
+_u32 sl_FsModeWithFlags(bool write, bool create, _u32 flags, _u32 maxCreateSize)
 {
     if (create) {
-        _u32 flags = failsafe ? _FS_FILE_OPEN_FLAG_COMMIT : 0;
         return FS_MODE_OPEN_CREATE(maxCreateSize, flags);
     } else if (write) {
         return FS_MODE_OPEN_WRITE;
     }
     return FS_MODE_OPEN_READ;
 }
+
+_u32 sl_FsMode(bool write, bool create, bool failsafe, _u32 maxCreateSize)
+{
+    return sl_FsModeWithFlags(write, create, failsafe ? _FS_FILE_OPEN_FLAG_COMMIT : 0,
+                              maxCreateSize);
+}
@@ -0,0 +1,569 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Host side implementation of the SimpleLink file system, used to run the
+// tests on Linux. It is only built with the host-sim feature, as it exports
+// the sl_Fs* and PRCMRTC* symbols.
+//
+// Every SimpleLink file is stored in a file of the directory passed to
+// `fs_mount`, prefixed by a header holding its allocated length, its open
+// flags and its token. The behaviour of the NWP is mimicked:
+//
+// - files have the fixed maximum size given when they are created, and
+//   creating a file that exists just opens it for writing;
+// - opening a file for writing discards its content;
+// - failsafe (commit) files keep their previous content until they are
+//   closed, and closing them with the "A" signature aborts the changes;
+// - secure files must be failsafe, and need their token to be opened or
+//   deleted unless they are public; the token is returned when they are
+//   created;
+// - secure files created without the no signature test flag must be closed
+//   with a signature and an existing certificate file. The signature itself
+//   is not verified.
+//
+// The FLC functions used by the image updates are implemented on top of the
+// file system; the test and commit calls never request a reset.
+//
+// The RTC only keeps the seconds it was set to, so that the tests control
+// the time.
+
+use std::boxed::Box;
+use std::cmp;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::slice;
+use std::str;
+use std::sync::{Mutex, MutexGuard, Once, ONCE_INIT};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::vec::Vec;
+
+use numeric_utils::{read_le, write_le};
+
+use simplelink::{FileSystemError, SlFsFileInfo, FS_ABORT_SIGNATURE, FS_FILE_OPEN_FLAG_COMMIT,
+                 FS_FILE_OPEN_FLAG_NO_SIGNATURE_TEST, FS_FILE_OPEN_FLAG_SECURE,
+                 FS_FILE_PUBLIC_READ, FS_FILE_PUBLIC_WRITE};
+
+const HEADER_MAGIC: &'static [u8; 4] = b"SLFS";
+const HEADER_LEN: usize = 16;
+
+// From fs.h
+const FS_MODE_OPEN_READ: u32 = 0;
+const FS_MODE_OPEN_WRITE: u32 = 1;
+const FS_MODE_OPEN_CREATE: u32 = 2;
+const FS_MODE_OPEN_WRITE_CREATE_IF_NOT_EXIST: u32 = 3;
+const FS_MODE_SIZE_GRANULARITIES: [u32; 5] = [256, 1024, 4096, 16384, 65536];
+
+// Stored in front of the content of every file.
+#[derive(Clone, Copy)]
+struct Header {
+    allocated_length: u32,
+    flags: u16,
+    token: u32,
+}
+
+impl Header {
+    fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut buf = [0; HEADER_LEN];
+        buf[..4].copy_from_slice(HEADER_MAGIC);
+        write_le(&mut buf[4..8], self.allocated_length as u64);
+        write_le(&mut buf[8..10], self.flags as u64);
+        write_le(&mut buf[12..16], self.token as u64);
+        buf
+    }
+
+    fn decode(buf: &[u8]) -> Option<Header> {
+        if buf.len() < HEADER_LEN || &buf[..4] != HEADER_MAGIC {
+            return None;
+        }
+        Some(Header {
+            allocated_length: read_le(&buf[4..8]) as u32,
+            flags: read_le(&buf[8..10]) as u16,
+            token: read_le(&buf[12..16]) as u32,
+        })
+    }
+
+    fn is_secure(&self) -> bool {
+        (self.flags as u32 & FS_FILE_OPEN_FLAG_SECURE) != 0
+    }
+
+    fn is_failsafe(&self) -> bool {
+        (self.flags as u32 & FS_FILE_OPEN_FLAG_COMMIT) != 0
+    }
+
+    fn needs_signature(&self) -> bool {
+        self.is_secure() && (self.flags as u32 & FS_FILE_OPEN_FLAG_NO_SIGNATURE_TEST) == 0
+    }
+}
+
+struct OpenFile {
+    path: PathBuf,
+    header: Header,
+    write: bool,
+    data: Vec<u8>,
+}
+
+struct State {
+    root: Option<PathBuf>,
+    next_handle: i32,
+    next_token: u32,
+    files: HashMap<i32, OpenFile>,
+}
+
+impl State {
+    // Files can't be reached outside of the mounted directory.
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        let name = name.trim_left_matches('/');
+        if name.is_empty() {
+            return None;
+        }
+        if name.split('/').any(|part| part == "." || part == "..") {
+            return None;
+        }
+        self.root.as_ref().map(|root| root.join(name))
+    }
+}
+
+static INIT: Once = ONCE_INIT;
+static mut STATE: *const Mutex<State> = 0 as *const Mutex<State>;
+static mut MOUNT_LOCK: *const Mutex<()> = 0 as *const Mutex<()>;
+
+fn init() {
+    INIT.call_once(|| unsafe {
+        STATE = Box::into_raw(Box::new(Mutex::new(State {
+            root: None,
+            next_handle: 1,
+            next_token: 0x5a5a0001,
+            files: HashMap::new(),
+        })));
+        MOUNT_LOCK = Box::into_raw(Box::new(Mutex::new(())));
+    });
+}
+
+fn state() -> MutexGuard<'static, State> {
+    init();
+    let state = unsafe { &*STATE };
+    state.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Keeps the file system mounted. Only one file system can be mounted at a
+/// time, so the tests using it run one after the other.
+pub struct FsMount {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Drop for FsMount {
+    fn drop(&mut self) {
+        let mut state = state();
+        state.root = None;
+        state.files.clear();
+    }
+}
+
+/// Mounts the directory `root` as the SimpleLink file system, creating it
+/// if needed. The files left open by a previous mount are forgotten.
+pub fn fs_mount(root: &Path) -> FsMount {
+    init();
+    let lock = unsafe { &*MOUNT_LOCK }.lock().unwrap_or_else(|err| err.into_inner());
+    fs::create_dir_all(root).unwrap();
+    let mut state = state();
+    state.root = Some(root.to_path_buf());
+    state.files.clear();
+    FsMount { _lock: lock }
+}
+
+fn read_file(path: &Path) -> Option<(Header, Vec<u8>)> {
+    let mut content = Vec::new();
+    match fs::File::open(path) {
+        Ok(mut file) => {
+            if file.read_to_end(&mut content).is_err() {
+                return None;
+            }
+        }
+        Err(_) => return None,
+    }
+    Header::decode(&content).map(|header| (header, content.split_off(HEADER_LEN)))
+}
+
+// Writes through a temporary file so that the update is atomic.
+fn write_file(path: &Path, header: &Header, data: &[u8]) -> Result<(), FileSystemError> {
+    if let Some(parent) = path.parent() {
+        if fs::create_dir_all(parent).is_err() {
+            return Err(FileSystemError::FAILED_TO_WRITE);
+        }
+    }
+    let tmp_path = path.with_extension("slfs-tmp");
+    let result = fs::File::create(&tmp_path).and_then(|mut file| {
+        try!(file.write_all(&header.encode()));
+        file.write_all(data)
+    });
+    if result.and_then(|_| fs::rename(&tmp_path, path)).is_err() {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(FileSystemError::FAILED_TO_WRITE);
+    }
+    Ok(())
+}
+
+unsafe fn file_name<'a>(name: *const u8) -> Option<&'a str> {
+    if name.is_null() {
+        return None;
+    }
+    let mut len = 0;
+    while *name.offset(len as isize) != 0 {
+        len += 1;
+    }
+    str::from_utf8(slice::from_raw_parts(name, len)).ok()
+}
+
+// Checks the token given to open or delete a secure file.
+unsafe fn check_token(header: &Header, token: *const u32, public_flag: u32) -> bool {
+    if !header.is_secure() || (header.flags as u32 & public_flag) != 0 {
+        return true;
+    }
+    !token.is_null() && *token == header.token
+}
+
+#[no_mangle]
+pub extern "C" fn sl_FsMode(write: bool, create: bool, failsafe: bool, max_size: u32) -> u32 {
+    sl_FsModeWithFlags(write,
+                       create,
+                       if failsafe { FS_FILE_OPEN_FLAG_COMMIT } else { 0 },
+                       max_size)
+}
+
+#[no_mangle]
+pub extern "C" fn sl_FsModeWithFlags(write: bool, create: bool, flags: u32, max_size: u32) -> u32 {
+    if create {
+        create_mode(max_size, flags)
+    } else if write {
+        fs_mode(FS_MODE_OPEN_WRITE, 0, 0, 0)
+    } else {
+        fs_mode(FS_MODE_OPEN_READ, 0, 0, 0)
+    }
+}
+
+// Same as _sl_GetCreateFsMode: picks the smallest granularity that can
+// describe `max_size`, and rounds the size up to it.
+fn create_mode(max_size: u32, flags: u32) -> u32 {
+    let mut gran = 0;
+    while gran < FS_MODE_SIZE_GRANULARITIES.len() - 1 &&
+          FS_MODE_SIZE_GRANULARITIES[gran] * 255 < max_size {
+        gran += 1;
+    }
+    let unit = FS_MODE_SIZE_GRANULARITIES[gran];
+    let count = (max_size + unit - 1) / unit;
+    fs_mode(FS_MODE_OPEN_WRITE_CREATE_IF_NOT_EXIST, gran as u32, count, flags)
+}
+
+/// Builds an open mode like the _FS_MODE macro of fs.h.
+pub fn fs_mode(access: u32, size_gran: u32, size: u32, flags: u32) -> u32 {
+    ((access & 0xf) << 12) | ((size_gran & 0xf) << 8) | (size & 0xff) | ((flags & 0xff) << 16)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_FsOpen(name: *const u8,
+                                   mode: u32,
+                                   token: *mut u32,
+                                   file_handle: *mut i32)
+                                   -> i32 {
+    let mut state = state();
+    if state.root.is_none() {
+        return FileSystemError::DEVICE_NOT_LOADED as i32;
+    }
+    let path = match file_name(name).and_then(|name| state.path(name)) {
+        Some(path) => path,
+        None => return FileSystemError::WRONG_FILE_NAME as i32,
+    };
+
+    let access = (mode >> 12) & 0xf;
+    let existing = read_file(&path);
+    let (header, data, write) = match (access, existing) {
+        (FS_MODE_OPEN_READ, Some((header, data))) => {
+            if !check_token(&header, token, FS_FILE_PUBLIC_READ) {
+                return FileSystemError::TOKEN_IS_NOT_VALID as i32;
+            }
+            (header, data, false)
+        }
+        (FS_MODE_OPEN_CREATE, Some(_)) => {
+            return FileSystemError::FILE_ALREADY_EXISTS as i32;
+        }
+        (FS_MODE_OPEN_WRITE, Some((header, _))) |
+        (FS_MODE_OPEN_WRITE_CREATE_IF_NOT_EXIST, Some((header, _))) => {
+            if !check_token(&header, token, FS_FILE_PUBLIC_WRITE) {
+                return FileSystemError::TOKEN_IS_NOT_VALID as i32;
+            }
+            (header, Vec::new(), true)
+        }
+        (FS_MODE_OPEN_READ, None) |
+        (FS_MODE_OPEN_WRITE, None) => {
+            return FileSystemError::FILE_NOT_EXISTS as i32;
+        }
+        (FS_MODE_OPEN_CREATE, None) |
+        (FS_MODE_OPEN_WRITE_CREATE_IF_NOT_EXIST, None) => {
+            let gran = ((mode >> 8) & 0xf) as usize;
+            let count = mode & 0xff;
+            if gran >= FS_MODE_SIZE_GRANULARITIES.len() || count == 0 {
+                return FileSystemError::FILE_UNVALID_FILE_SIZE as i32;
+            }
+            let header = Header {
+                allocated_length: FS_MODE_SIZE_GRANULARITIES[gran] * count,
+                flags: ((mode >> 16) & 0xff) as u16,
+                token: state.next_token,
+            };
+            if header.is_secure() && !header.is_failsafe() {
+                return FileSystemError::SECURE_FILE_MUST_BE_COMMIT as i32;
+            }
+            state.next_token = state.next_token.wrapping_add(0x01010101);
+            if header.is_secure() && !token.is_null() {
+                *token = header.token;
+            }
+            (header, Vec::new(), true)
+        }
+        _ => return FileSystemError::BAD_FILE_MODE as i32,
+    };
+
+    // Only failsafe files keep their content until they are closed.
+    if write && !header.is_failsafe() {
+        if let Err(err) = write_file(&path, &header, &data) {
+            return err as i32;
+        }
+    } else if write && read_file(&path).is_none() {
+        if let Err(err) = write_file(&path, &header, &[]) {
+            return err as i32;
+        }
+    }
+
+    let handle = state.next_handle;
+    state.next_handle += 1;
+    state.files.insert(handle,
+                       OpenFile {
+                           path: path,
+                           header: header,
+                           write: write,
+                           data: data,
+                       });
+    if !file_handle.is_null() {
+        *file_handle = handle;
+    }
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_FsClose(file_handle: i32,
+                                    certificate_file_name: *const u8,
+                                    signature: *const u8,
+                                    signature_length: u32)
+                                    -> i16 {
+    let mut state = state();
+    let file = match state.files.remove(&file_handle) {
+        Some(file) => file,
+        None => return FileSystemError::INVALID_HANDLE as i16,
+    };
+    let abort = !signature.is_null() &&
+                slice::from_raw_parts(signature, signature_length as usize) == FS_ABORT_SIGNATURE;
+    if file.write && file.header.needs_signature() && !abort {
+        // The changes are dropped, as the NWP does when the signature check
+        // fails.
+        if signature.is_null() || signature_length == 0 {
+            return FileSystemError::WRONG_SIGNATURE_OR_CERTIFIC_NAME_LENGTH as i16;
+        }
+        let certificate = match file_name(certificate_file_name).and_then(|name| state.path(name)) {
+            Some(path) => path,
+            None => return FileSystemError::WRONG_SIGNATURE_OR_CERTIFIC_NAME_LENGTH as i16,
+        };
+        if read_file(&certificate).is_none() {
+            return FileSystemError::CERT_CHAIN_ERROR as i16;
+        }
+    }
+    if file.write && file.header.is_failsafe() && !abort {
+        if let Err(err) = write_file(&file.path, &file.header, &file.data) {
+            return err as i16;
+        }
+    }
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_FsRead(file_handle: i32, offset: u32, data: *mut u8, len: u32) -> i32 {
+    let state = state();
+    let file = match state.files.get(&file_handle) {
+        Some(file) => file,
+        None => return FileSystemError::INVALID_HANDLE as i32,
+    };
+    if file.write {
+        return FileSystemError::INVALID_ACCESS_TYPE as i32;
+    }
+    let offset = offset as usize;
+    if offset > file.data.len() {
+        return FileSystemError::OFFSET_OUT_OF_RANGE as i32;
+    }
+    let len = cmp::min(len as usize, file.data.len() - offset);
+    ptr::copy_nonoverlapping(file.data[offset..].as_ptr(), data, len);
+    len as i32
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_FsWrite(file_handle: i32,
+                                    offset: u32,
+                                    data: *const u8,
+                                    len: u32)
+                                    -> i32 {
+    let mut state = state();
+    let file = match state.files.get_mut(&file_handle) {
+        Some(file) => file,
+        None => return FileSystemError::INVALID_HANDLE as i32,
+    };
+    if !file.write {
+        return FileSystemError::INVALID_ACCESS_TYPE as i32;
+    }
+    let offset = offset as usize;
+    let end = offset + len as usize;
+    if end > file.header.allocated_length as usize {
+        return FileSystemError::FILE_MAX_SIZE_EXCEEDED as i32;
+    }
+    if end > file.data.len() {
+        file.data.resize(end, 0);
+    }
+    file.data[offset..end].copy_from_slice(slice::from_raw_parts(data, len as usize));
+    if !file.header.is_failsafe() {
+        if let Err(err) = write_file(&file.path, &file.header, &file.data) {
+            return err as i32;
+        }
+    }
+    len as i32
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_FsGetInfo(name: *const u8,
+                                      _token: u32,
+                                      file_info: *mut SlFsFileInfo)
+                                      -> i16 {
+    let state = state();
+    if state.root.is_none() {
+        return FileSystemError::DEVICE_NOT_LOADED as i16;
+    }
+    let path = match file_name(name).and_then(|name| state.path(name)) {
+        Some(path) => path,
+        None => return FileSystemError::WRONG_FILE_NAME as i16,
+    };
+    let (header, data) = match read_file(&path) {
+        Some(file) => file,
+        None => return FileSystemError::FILE_NOT_EXISTS as i16,
+    };
+    let info = &mut *file_info;
+    info.flags = header.flags;
+    info.file_length = data.len() as u32;
+    info.allocated_length = header.allocated_length;
+    info.token = [0; 4];
+    if header.is_secure() {
+        info.token[0] = header.token;
+    }
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_FsDel(name: *const u8, token: u32) -> i16 {
+    let state = state();
+    if state.root.is_none() {
+        return FileSystemError::DEVICE_NOT_LOADED as i16;
+    }
+    let path = match file_name(name).and_then(|name| state.path(name)) {
+        Some(path) => path,
+        None => return FileSystemError::WRONG_FILE_NAME as i16,
+    };
+    let header = match read_file(&path) {
+        Some((header, _)) => header,
+        None => return FileSystemError::FILE_NOT_EXISTS as i16,
+    };
+    if !check_token(&header, &token, 0) {
+        return FileSystemError::TOKEN_IS_NOT_VALID as i16;
+    }
+    if fs::remove_file(&path).is_err() {
+        return FileSystemError::FAILED_TO_WRITE as i16;
+    }
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_extlib_FlcOpenFile(name: *const u8,
+                                               file_size: i32,
+                                               token: *const u32,
+                                               file_handle: *mut i32,
+                                               flags: i32)
+                                               -> i32 {
+    let mode = if flags == FS_MODE_OPEN_READ as i32 {
+        fs_mode(FS_MODE_OPEN_READ, 0, 0, 0)
+    } else {
+        create_mode(file_size as u32, flags as u32)
+    };
+    sl_FsOpen(name, mode, token as *mut u32, file_handle)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_extlib_FlcCloseFile(file_handle: i32,
+                                                certificate_file_name: *const u8,
+                                                signature: *const u8,
+                                                signature_length: u32)
+                                                -> i16 {
+    sl_FsClose(file_handle, certificate_file_name, signature, signature_length)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_extlib_FlcReadFile(file_handle: i32,
+                                               offset: i32,
+                                               data: *mut u8,
+                                               len: i32)
+                                               -> i32 {
+    sl_FsRead(file_handle, offset as u32, data, len as u32)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_extlib_FlcWriteFile(file_handle: i32,
+                                                offset: i32,
+                                                data: *const u8,
+                                                len: i32)
+                                                -> i32 {
+    sl_FsWrite(file_handle, offset as u32, data, len as u32)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sl_extlib_FlcAbortFile(file_handle: i32) -> i32 {
+    sl_FsClose(file_handle,
+               ptr::null(),
+               FS_ABORT_SIGNATURE.as_ptr(),
+               FS_ABORT_SIGNATURE.len() as u32) as i32
+}
+
+#[no_mangle]
+pub extern "C" fn sl_extlib_FlcCommit(_flags: i32) -> i32 {
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn sl_extlib_FlcTest(_flags: i32) -> i32 {
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn sl_extlib_FlcIsPendingCommit() -> i32 {
+    0
+}
+
+static RTC_SECONDS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+#[no_mangle]
+pub extern "C" fn PRCMRTCInUseSet() {}
+
+#[no_mangle]
+pub extern "C" fn PRCMRTCSet(secs: u32, _msecs: u16) {
+    RTC_SECONDS.store(secs as usize, Ordering::SeqCst);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn PRCMRTCGet(secs: *mut u32, msecs: *mut u16) {
+    *secs = RTC_SECONDS.load(Ordering::SeqCst) as u32;
+    *msecs = 0;
+}
@@ -12,11 +12,22 @@
 #[macro_use]
 extern crate log;
 
+// The host build of the tests simulates the NWP file system.
+#[cfg(all(feature = "host-sim", not(target_arch = "arm")))]
+extern crate std;
+#[cfg(all(feature = "host-sim", not(target_arch = "arm")))]
+extern crate numeric_utils;
+
 pub use self::UtilsDelay as MAP_UtilsDelay;
 
+// `socket` uses the enum macros of `simplelink`.
+#[macro_use]
 pub mod simplelink;
 pub mod socket;
 
+#[cfg(all(feature = "host-sim", not(target_arch = "arm")))]
+pub mod host;
+
 extern "C" {
     // From board.c
     pub fn board_init();
@@ -37,6 +48,25 @@ extern "C" {
     pub fn PRCMRTCInUseSet();
     pub fn PRCMRTCSet(secs: u32, msecs: u16);
     pub fn PRCMRTCGet(secs: *mut u32, msecs: *mut u16);
+    pub fn PRCMPeripheralClkEnable(ulPeripheral: u32, ulClkFlags: u32);
+    pub fn PRCMPeripheralReset(ulPeripheral: u32);
+
+    // From sdk/driverlib/shamd5.c
+    pub fn SHAMD5ConfigSet(ui32Base: u32, ui32Mode: u32);
+    pub fn SHAMD5DataLengthSet(ui32Base: u32, ui32Length: u32);
+    pub fn SHAMD5DataWrite(ui32Base: u32, pui8Src: *mut u8);
+    pub fn SHAMD5HMACKeySet(ui32Base: u32, pui8Src: *mut u8);
+    pub fn SHAMD5IntStatus(ui32Base: u32, bMasked: bool) -> u32;
+    pub fn SHAMD5ResultRead(ui32Base: u32, pui8Dest: *mut u8);
+
+    // From sdk/driverlib/crc.c
+    pub fn CRCConfigSet(ui32Base: u32, ui32CRCConfig: u32);
+    pub fn CRCDataProcess(ui32Base: u32,
+                          puiDataIn: *mut u8,
+                          ui32DataLength: u32,
+                          ui32Config: u32)
+                          -> u32;
+    pub fn CRCSeedSet(ui32Base: u32, ui32Seed: u32);
 
     // From sdk/driverlib/utils.c
     pub fn UtilsDelay(loops: u32);
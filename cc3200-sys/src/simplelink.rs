@@ -4,13 +4,27 @@
 
 use core::convert::TryFrom;
 use core::fmt;
+use core::ops::{BitAnd, BitOr};
+
+use socket::SocketError;
+
+/// The code of `SimpleLinkError::NotStarted`, outside of the ranges used by
+/// the SDK.
+pub const NOT_STARTED_CODE: i32 = -2000;
 
 #[derive(Debug)]
 pub enum SimpleLinkError {
     Wlan(self::WlanError),
     Osi(self::OsiError),
     FileSystem(self::FileSystemError),
+    RxFilter(self::RxFilterError),
+    /// Errors of the host driver, common to all the calls.
+    Driver(self::DriverError),
+    NetApp(self::NetAppError),
+    Socket(SocketError),
     ValueError(&'static str, i32),
+    /// The NWP isn't started.
+    NotStarted,
 }
 
 impl From<self::WlanError> for SimpleLinkError {
@@ -31,18 +45,131 @@ impl From<self::FileSystemError> for SimpleLinkError {
     }
 }
 
+impl From<self::RxFilterError> for SimpleLinkError {
+    fn from(err: self::RxFilterError) -> SimpleLinkError {
+        SimpleLinkError::RxFilter(err)
+    }
+}
+
+impl From<self::DriverError> for SimpleLinkError {
+    fn from(err: self::DriverError) -> SimpleLinkError {
+        SimpleLinkError::Driver(err)
+    }
+}
+
+impl From<self::NetAppError> for SimpleLinkError {
+    fn from(err: self::NetAppError) -> SimpleLinkError {
+        SimpleLinkError::NetApp(err)
+    }
+}
+
+impl From<SocketError> for SimpleLinkError {
+    fn from(err: SocketError) -> SimpleLinkError {
+        SimpleLinkError::Socket(err)
+    }
+}
+
+impl SimpleLinkError {
+    /// Decodes the negative return code of a WLAN or device call.
+    pub fn from_wlan_code(code: i32) -> SimpleLinkError {
+        match WlanError::try_from(code) {
+            Ok(e) => SimpleLinkError::Wlan(e),
+            Err(e) => SimpleLinkError::from_driver_code(code).unwrap_or(e),
+        }
+    }
+
+    /// Decodes the negative return code of a sl_Dev* or sl_NetCfg* call,
+    /// which only fail with the driver errors.
+    pub fn from_device_code(code: i32) -> SimpleLinkError {
+        SimpleLinkError::from_driver_code(code)
+            .unwrap_or(SimpleLinkError::ValueError("DriverError", code))
+    }
+
+    /// Decodes the negative return code of a sl_NetApp* call.
+    pub fn from_netapp_code(code: i32) -> SimpleLinkError {
+        match NetAppError::try_from(code) {
+            Ok(e) => SimpleLinkError::NetApp(e),
+            Err(e) => SimpleLinkError::from_driver_code(code).unwrap_or(e),
+        }
+    }
+
+    /// Decodes the negative return code of a socket call.
+    pub fn from_socket_code(code: i32) -> SimpleLinkError {
+        match SocketError::try_from(code) {
+            Ok(e) => SimpleLinkError::Socket(e),
+            Err(e) => SimpleLinkError::from_driver_code(code).unwrap_or(e),
+        }
+    }
+
+    /// Decodes the negative return code of a sl_Fs* call.
+    pub fn from_fs_code(code: i32) -> SimpleLinkError {
+        match FileSystemError::try_from(code) {
+            Ok(e) => SimpleLinkError::FileSystem(e),
+            Err(e) => e,
+        }
+    }
+
+    fn from_driver_code(code: i32) -> Option<SimpleLinkError> {
+        DriverError::try_from(code).ok().map(SimpleLinkError::Driver)
+    }
+
+    /// The SDK code of the error. It is only unique within a class of
+    /// errors: a FileSystemError and a WlanError may have the same code.
+    /// `NotStarted` isn't an SDK error and reports `NOT_STARTED_CODE`.
+    pub fn code(&self) -> i32 {
+        match *self {
+            SimpleLinkError::Wlan(e) => e as i32,
+            SimpleLinkError::Osi(e) => e as i32,
+            SimpleLinkError::FileSystem(e) => e as i32,
+            SimpleLinkError::RxFilter(e) => e as i32,
+            SimpleLinkError::Driver(e) => e as i32,
+            SimpleLinkError::NetApp(e) => e as i32,
+            SimpleLinkError::Socket(e) => e as i32,
+            SimpleLinkError::ValueError(_, n) => n,
+            SimpleLinkError::NotStarted => NOT_STARTED_CODE,
+        }
+    }
+
+    /// The name of the class of errors, as used by `Display`.
+    pub fn class(&self) -> &'static str {
+        match *self {
+            SimpleLinkError::Wlan(_) => "WLAN",
+            SimpleLinkError::Osi(_) => "OSI",
+            SimpleLinkError::FileSystem(_) => "File system",
+            SimpleLinkError::RxFilter(_) => "RX filter",
+            SimpleLinkError::Driver(_) => "Driver",
+            SimpleLinkError::NetApp(_) => "NetApp",
+            SimpleLinkError::Socket(_) => "Socket",
+            SimpleLinkError::ValueError(..) => "Value",
+            SimpleLinkError::NotStarted => "Device",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match *self {
+            SimpleLinkError::Wlan(e) => e.description(),
+            SimpleLinkError::Osi(e) => e.description(),
+            SimpleLinkError::FileSystem(e) => e.description(),
+            SimpleLinkError::RxFilter(e) => e.description(),
+            SimpleLinkError::Driver(e) => e.description(),
+            SimpleLinkError::NetApp(e) => e.description(),
+            SimpleLinkError::Socket(e) => e.description(),
+            SimpleLinkError::ValueError(..) => "unknown enum value",
+            SimpleLinkError::NotStarted => "the NWP isn't started",
+        }
+    }
+}
+
 impl fmt::Display for SimpleLinkError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            SimpleLinkError::Wlan(e) => write!(formatter, "WlanError: {:?}", e),
-            SimpleLinkError::Osi(e) => write!(formatter, "OsiError: {:?}", e),
-            SimpleLinkError::FileSystem(e) => write!(formatter, "FileSystemError: {:?}", e),
             SimpleLinkError::ValueError(ref enum_name, n) => {
                 write!(formatter,
                        "ValueError: Unknown enum value: {} for {}",
                        n,
                        enum_name)
             }
+            ref e => write!(formatter, "{} error {}: {}", e.class(), e.code(), e.description()),
         }
     }
 }
@@ -91,8 +218,9 @@ macro_rules! c_like_enum_neg {
         impl TryFrom<i32> for $name {
             type Err = SimpleLinkError;
             fn try_from(value: i32) -> Result<Self, Self::Err> {
+                // `$value` lost its minus sign, compare with the discriminants.
                 match value {
-                    $($value => Ok($name::$variant),)+
+                    $(n if n == $name::$variant as i32 => Ok($name::$variant),)+
                     n => Err(SimpleLinkError::ValueError(stringify!($name), n as i32))
                 }
             }
@@ -133,6 +261,150 @@ c_like_enum_neg! {
     }
 }
 
+impl WlanError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            WlanError::ROLE_STA_ERR => "failed to switch to the station role",
+            WlanError::ROLE_AP_ERR => "failed to switch to the AP role",
+            WlanError::KEY_ERROR => "invalid key",
+            WlanError::INVALID_ROLE => "invalid role for the operation",
+            WlanError::INVALID_SECURITY_TYPE => "invalid security type",
+            WlanError::PASSPHRASE_TOO_LONG => "passphrase too long",
+            WlanError::WPS_NO_PIN_OR_WRONG_PIN_LEN => "WPS PIN missing or of the wrong length",
+            WlanError::EAP_WRONG_METHOD => "wrong EAP method",
+            WlanError::PASSWORD_ERROR => "invalid password",
+            WlanError::EAP_ANONYMOUS_LEN_ERROR => "EAP anonymous identity too long",
+            WlanError::SSID_LEN_ERROR => "invalid SSID length",
+            WlanError::USER_ID_LEN_ERROR => "invalid user ID length",
+            WlanError::ILLEGAL_WEP_KEY_INDEX => "illegal WEP key index",
+            WlanError::INVALID_DWELL_TIME_VALUES => "invalid scan dwell time values",
+            WlanError::INVALID_POLICY_TYPE => "invalid policy type",
+            WlanError::PM_POLICY_INVALID_OPTION => "invalid power management policy option",
+            WlanError::PM_POLICY_INVALID_PARAMS => "invalid power management policy parameters",
+            WlanError::WIFI_ALREADY_DISCONNECTED => "already disconnected",
+            WlanError::WIFI_NOT_CONNECTED => "not connected",
+        }
+    }
+}
+
+// From simplelink/include/simplelink.h and device.h
+c_like_enum_neg! {
+    DriverError {
+        INVALID_INPUT = -2,
+        SELF_ERROR = -3,
+        NWP_IF_ERROR = -4,
+        MALLOC_ERROR = -5,
+        ABORT = -6,
+        PROTOCOL_ERROR = -7,
+        API_ABORTED = -100
+    }
+}
+
+impl DriverError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            DriverError::INVALID_INPUT => "invalid input",
+            DriverError::SELF_ERROR => "host driver internal error",
+            DriverError::NWP_IF_ERROR => "failed to communicate with the NWP",
+            DriverError::MALLOC_ERROR => "memory allocation failed",
+            DriverError::ABORT => "aborted",
+            DriverError::PROTOCOL_ERROR => "host driver protocol error",
+            DriverError::API_ABORTED => "aborted, the NWP has to be restarted",
+        }
+    }
+}
+
+// From simplelink/include/netapp.h
+c_like_enum_neg! {
+    NetAppError {
+        DEVICE_NAME_LEN_ERR = -117,
+        DEVICE_NAME_INVALID = -118,
+        DOMAIN_NAME_LEN_ERR = -119,
+        DOMAIN_NAME_INVALID = -120,
+        DNS_QUERY_NO_RESPONSE = -159,
+        DNS_NO_SERVER = -161,
+        DNS_PARAM_ERROR = -162,
+        DNS_QUERY_FAILED = -163,
+        DNS_INTERNAL_1 = -164,
+        DNS_INTERNAL_2 = -165,
+        DNS_MALFORMED_PACKET = -166,
+        DNS_INTERNAL_3 = -167,
+        DNS_INTERNAL_4 = -168,
+        DNS_INTERNAL_5 = -169,
+        DNS_INTERNAL_6 = -170,
+        DNS_INTERNAL_7 = -171,
+        DNS_INTERNAL_8 = -172,
+        DNS_INTERNAL_9 = -173,
+        DNS_MISMATCHED_RESPONSE = -174,
+        DNS_INTERNAL_10 = -175,
+        DNS_INTERNAL_11 = -176,
+        DNS_NO_ANSWER = -177,
+        DNS_NO_KNOWN_ANSWER = -178,
+        DNS_NAME_MISMATCH = -179,
+        DNS_NOT_STARTED = -180,
+        DNS_HOST_NAME_ERROR = -181,
+        DNS_NO_MORE_ENTRIES = -182,
+        DNS_MAX_SERVICES_ERROR = -200,
+        DNS_IDENTICAL_SERVICES_ERROR = -201,
+        DNS_NOT_EXISTED_SERVICE_ERROR = -203,
+        DNS_ERROR_SERVICE_NAME_ERROR = -204,
+        DNS_RX_PACKET_ALLOCATION_ERROR = -205,
+        DNS_BUFFER_SIZE_ERROR = -206,
+        DNS_NET_APP_SET_ERROR = -207,
+        DNS_GET_SERVICE_LIST_FLAG_ERROR = -208,
+        DNS_NO_CONFIGURATION_ERROR = -209,
+        RX_BUFFER_LENGTH_ERROR = -230
+    }
+}
+
+impl NetAppError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            NetAppError::DEVICE_NAME_LEN_ERR => "invalid device name length",
+            NetAppError::DEVICE_NAME_INVALID => "invalid device name",
+            NetAppError::DOMAIN_NAME_LEN_ERR => "invalid domain name length",
+            NetAppError::DOMAIN_NAME_INVALID => "invalid domain name",
+            NetAppError::DNS_QUERY_NO_RESPONSE => "DNS query failed, no response",
+            NetAppError::DNS_NO_SERVER => "no DNS server was specified",
+            NetAppError::DNS_PARAM_ERROR => "mDNS parameters error",
+            NetAppError::DNS_QUERY_FAILED => "DNS query failed, no DNS server sent an answer",
+            NetAppError::DNS_INTERNAL_1 |
+            NetAppError::DNS_INTERNAL_2 |
+            NetAppError::DNS_INTERNAL_3 |
+            NetAppError::DNS_INTERNAL_4 |
+            NetAppError::DNS_INTERNAL_5 |
+            NetAppError::DNS_INTERNAL_6 |
+            NetAppError::DNS_INTERNAL_7 |
+            NetAppError::DNS_INTERNAL_8 |
+            NetAppError::DNS_INTERNAL_9 |
+            NetAppError::DNS_INTERNAL_10 |
+            NetAppError::DNS_INTERNAL_11 => "DNS internal error",
+            NetAppError::DNS_MALFORMED_PACKET => "improperly formed or corrupted DNS packet",
+            NetAppError::DNS_MISMATCHED_RESPONSE => {
+                "server response type does not match the query request"
+            }
+            NetAppError::DNS_NO_ANSWER => "no response for one-shot query",
+            NetAppError::DNS_NO_KNOWN_ANSWER => "no known answer for query",
+            NetAppError::DNS_NAME_MISMATCH => "illegal service name according to the RFC",
+            NetAppError::DNS_NOT_STARTED => "mDNS is not running",
+            NetAppError::DNS_HOST_NAME_ERROR => "host name format not allowed by the RFCs",
+            NetAppError::DNS_NO_MORE_ENTRIES => "no more entries",
+            NetAppError::DNS_MAX_SERVICES_ERROR => "maximum advertised services already configured",
+            NetAppError::DNS_IDENTICAL_SERVICES_ERROR => "service already registered",
+            NetAppError::DNS_NOT_EXISTED_SERVICE_ERROR => "service not registered",
+            NetAppError::DNS_ERROR_SERVICE_NAME_ERROR => {
+                "illegal service name according to the RFC"
+            }
+            NetAppError::DNS_RX_PACKET_ALLOCATION_ERROR => "packet allocation failed, retry",
+            NetAppError::DNS_BUFFER_SIZE_ERROR => "list size bigger than allowed by the NWP",
+            NetAppError::DNS_NET_APP_SET_ERROR => "illegal length for an mDNS set function",
+            NetAppError::DNS_GET_SERVICE_LIST_FLAG_ERROR => "invalid service list flags",
+            NetAppError::DNS_NO_CONFIGURATION_ERROR => "mDNS not configured",
+            NetAppError::RX_BUFFER_LENGTH_ERROR => "receive buffer too small",
+        }
+    }
+}
+
 pub const SPAWN_TASK_PRIORITY: u32 = 9;
 pub const SL_STOP_TIMEOUT: u16 = 200;
 
@@ -149,6 +421,21 @@ c_like_enum_neg! {
     }
 }
 
+impl OsiError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            OsiError::OSI_FAILURE => "failure",
+            OsiError::OSI_OPERATION_FAILED => "operation failed",
+            OsiError::OSI_ABORTED => "aborted",
+            OsiError::OSI_INVALID_PARAMS => "invalid parameters",
+            OsiError::OSI_MEMORY_ALLOCATION_FAILURE => "memory allocation failed",
+            OsiError::OSI_TIMEOUT => "timed out",
+            OsiError::OSI_EVENTS_IN_USE => "events in use",
+            OsiError::OSI_EVENT_OPEARTION_FAILURE => "event operation failed",
+        }
+    }
+}
+
 c_like_enum! {
     SocketFamily {
         AF_INET = 2,
@@ -192,26 +479,222 @@ c_like_enum! {
     }
 }
 
-// Encode the PolicyType and Policy into a single value. The policy type is
-// in bits 8-15, and the policy in bits 0-7.
 c_like_enum! {
-    Policy {
-        // Need to figure out a better way to encode SL_CONNECTION_POLICY from wlan.h
-        // 0x01 - Auto
-        // 0x02 - Fast
-        // 0x04 - Open
-        // 0x08 - anyP2P
-        // 0x10 - autoSmartConfig
-        ConnectionDefault = 0x1011,
+    PolicyType {
+        Connection = 0x10,
+        Scan = 0x20,
+        Power = 0x30,
+        P2p = 0x40
+    }
+}
+
+/// SL_CONNECTION_POLICY: a combination of the CONNECTION_POLICY_* flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionPolicy {
+    bits: u8,
+}
+
+/// Connect automatically to the stored profiles.
+pub const CONNECTION_POLICY_AUTO: ConnectionPolicy = ConnectionPolicy { bits: 0x01 };
+/// Reconnect quickly to the last access point after a reset.
+pub const CONNECTION_POLICY_FAST: ConnectionPolicy = ConnectionPolicy { bits: 0x02 };
+/// Connect automatically to any open access point.
+pub const CONNECTION_POLICY_OPEN: ConnectionPolicy = ConnectionPolicy { bits: 0x04 };
+/// Connect automatically to the first P2P device found (P2P mode).
+pub const CONNECTION_POLICY_ANY_P2P: ConnectionPolicy = ConnectionPolicy { bits: 0x08 };
+/// Start SmartConfig when there's no profile to connect to.
+pub const CONNECTION_POLICY_AUTO_SMART_CONFIG: ConnectionPolicy = ConnectionPolicy { bits: 0x10 };
+
+const CONNECTION_POLICY_NAMES: [(ConnectionPolicy, &'static str); 5] =
+    [(CONNECTION_POLICY_AUTO, "auto"),
+     (CONNECTION_POLICY_FAST, "fast"),
+     (CONNECTION_POLICY_OPEN, "open"),
+     (CONNECTION_POLICY_ANY_P2P, "anyP2P"),
+     (CONNECTION_POLICY_AUTO_SMART_CONFIG, "autoSmartConfig")];
+
+impl ConnectionPolicy {
+    pub fn empty() -> Self {
+        ConnectionPolicy { bits: 0 }
+    }
+
+    pub fn from_bits_truncate(bits: u8) -> Self {
+        ConnectionPolicy { bits: bits & 0x1f }
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn contains(&self, other: ConnectionPolicy) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    pub fn insert(&mut self, other: ConnectionPolicy) {
+        self.bits |= other.bits;
+    }
+
+    pub fn remove(&mut self, other: ConnectionPolicy) {
+        self.bits &= !other.bits;
+    }
+}
+
+/// The device default: auto + autoSmartConfig.
+impl Default for ConnectionPolicy {
+    fn default() -> Self {
+        CONNECTION_POLICY_AUTO | CONNECTION_POLICY_AUTO_SMART_CONFIG
+    }
+}
+
+impl BitOr for ConnectionPolicy {
+    type Output = ConnectionPolicy;
+    fn bitor(self, other: ConnectionPolicy) -> ConnectionPolicy {
+        ConnectionPolicy { bits: self.bits | other.bits }
+    }
+}
+
+impl BitAnd for ConnectionPolicy {
+    type Output = ConnectionPolicy;
+    fn bitand(self, other: ConnectionPolicy) -> ConnectionPolicy {
+        ConnectionPolicy { bits: self.bits & other.bits }
+    }
+}
+
+impl fmt::Display for ConnectionPolicy {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(formatter, "none");
+        }
+        let mut first = true;
+        for &(flag, name) in CONNECTION_POLICY_NAMES.iter() {
+            if self.contains(flag) {
+                try!(write!(formatter, "{}{}", if first { "" } else { "|" }, name));
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerPolicy {
+    Normal,
+    LowLatency,
+    LowPower,
+    AlwaysOn,
+    /// Sleep up to `max_sleep_ms` between wake ups while connected.
+    LongSleepInterval { max_sleep_ms: u16 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanPolicy {
+    Disabled,
+    /// Scan every `interval_secs` seconds (10 minimum, the NWP default is
+    /// 600).
+    Enabled { interval_secs: u32 },
+}
+
+c_like_enum! {
+    P2pNegInitiator {
+        Active = 0,
+        Passive = 1,
+        RandBackoff = 2
+    }
+}
 
-        ScanDisable = 0x2000,
-        ScanEnable = 0x2001,
+// Group owner intents commonly used with P2pPolicy
+pub const P2P_GO_INTENT_CLIENT: u8 = 0;
+pub const P2P_GO_INTENT_NEGOTIATE: u8 = 3;
+pub const P2P_GO_INTENT_GROUP_OWNER: u8 = 15;
+
+/// SL_P2P_POLICY: the P2P negotiation parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct P2pPolicy {
+    /// 0 (client) to 15 (group owner).
+    pub go_intent: u8,
+    pub initiator: P2pNegInitiator,
+}
+
+// The largest policy options buffer (the long sleep interval one).
+pub const POLICY_OPTIONS_LEN_MAX: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WlanPolicy {
+    Connection(ConnectionPolicy),
+    Scan(ScanPolicy),
+    Power(PowerPolicy),
+    P2p(P2pPolicy),
+}
+
+impl WlanPolicy {
+    pub fn policy_type(&self) -> PolicyType {
+        match *self {
+            WlanPolicy::Connection(_) => PolicyType::Connection,
+            WlanPolicy::Scan(_) => PolicyType::Scan,
+            WlanPolicy::Power(_) => PolicyType::Power,
+            WlanPolicy::P2p(_) => PolicyType::P2p,
+        }
+    }
+
+    /// Returns the policy value passed to sl_WlanPolicySet, and the length
+    /// of the options written to `options`.
+    pub fn encode(&self, options: &mut [u8; POLICY_OPTIONS_LEN_MAX]) -> (u8, usize) {
+        match *self {
+            WlanPolicy::Connection(policy) => (policy.bits(), 0),
+            WlanPolicy::Scan(ScanPolicy::Disabled) => (0, 0),
+            WlanPolicy::Scan(ScanPolicy::Enabled { interval_secs }) => {
+                options[0] = interval_secs as u8;
+                options[1] = (interval_secs >> 8) as u8;
+                options[2] = (interval_secs >> 16) as u8;
+                options[3] = (interval_secs >> 24) as u8;
+                (1, 4)
+            }
+            WlanPolicy::Power(PowerPolicy::Normal) => (0, 0),
+            WlanPolicy::Power(PowerPolicy::LowLatency) => (1, 0),
+            WlanPolicy::Power(PowerPolicy::LowPower) => (2, 0),
+            WlanPolicy::Power(PowerPolicy::AlwaysOn) => (3, 0),
+            WlanPolicy::Power(PowerPolicy::LongSleepInterval { max_sleep_ms }) => {
+                // _u16 PolicyBuff[4], the maximum sleep time is PolicyBuff[2]
+                *options = [0; POLICY_OPTIONS_LEN_MAX];
+                options[4] = max_sleep_ms as u8;
+                options[5] = (max_sleep_ms >> 8) as u8;
+                (4, 8)
+            }
+            WlanPolicy::P2p(policy) => {
+                let intent = if policy.go_intent > P2P_GO_INTENT_GROUP_OWNER {
+                    P2P_GO_INTENT_GROUP_OWNER
+                } else {
+                    policy.go_intent
+                };
+                (intent | ((policy.initiator as u8) << 5), 0)
+            }
+        }
+    }
+}
 
-        PowerNormal = 0x3000,
-        PowerLowLatency = 0x3001,
-        PowerLowPower = 0x3002,
-        PowerAlwaysOn = 0x3003,
-        PowerLongSleepInterval = 0x3004
+impl PowerPolicy {
+    /// Decodes the value returned by sl_WlanPolicyGet.
+    pub fn decode(val: &[u8]) -> Result<Self, SimpleLinkError> {
+        match val.len() {
+            1 => {
+                match val[0] {
+                    0 => Ok(PowerPolicy::Normal),
+                    1 => Ok(PowerPolicy::LowLatency),
+                    2 => Ok(PowerPolicy::LowPower),
+                    3 => Ok(PowerPolicy::AlwaysOn),
+                    n => Err(SimpleLinkError::ValueError("PowerPolicy", n as i32)),
+                }
+            }
+            POLICY_OPTIONS_LEN_MAX => {
+                Ok(PowerPolicy::LongSleepInterval {
+                    max_sleep_ms: (val[4] as u16) | ((val[5] as u16) << 8),
+                })
+            }
+            n => Err(SimpleLinkError::ValueError("PowerPolicy length", n as i32)),
+        }
     }
 }
 
@@ -231,6 +714,17 @@ c_like_enum! {
 
 pub const SL_MAC_ADDR_LEN: usize = 6;
 
+// IPv4 addressing, as used by the Ipv4*StaticEnable and Ipv4*GetInfo
+// configurations. All the addresses are in host byte order.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SlNetCfgIpV4Args {
+    pub ip: u32,
+    pub mask: u32,
+    pub gateway: u32,
+    pub dns_server: u32,
+}
+
 // NetConfigGet only has an Id.
 c_like_enum! {
     NetConfigGet {
@@ -242,6 +736,89 @@ c_like_enum! {
     }
 }
 
+pub const MAXIMAL_SSID_LENGTH: usize = 32;
+
+/// Sl_WlanNetworkEntry_t: an access point found by the scan.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SlWlanNetworkEntry {
+    pub ssid: [u8; MAXIMAL_SSID_LENGTH],
+    pub ssid_len: u8,
+    /// One of the SCAN_SEC_TYPE_* values.
+    pub sec_type: u8,
+    pub bssid: [u8; SL_MAC_ADDR_LEN],
+    pub rssi: i8,
+    reserved: [i8; 3],
+}
+
+// Security types of the scan results.
+pub const SCAN_SEC_TYPE_OPEN: u8 = 0;
+pub const SCAN_SEC_TYPE_WEP: u8 = 1;
+pub const SCAN_SEC_TYPE_WPA: u8 = 2;
+pub const SCAN_SEC_TYPE_WPA2: u8 = 3;
+
+impl SlWlanNetworkEntry {
+    pub fn ssid(&self) -> &str {
+        let len = ::core::cmp::min(self.ssid_len as usize, MAXIMAL_SSID_LENGTH);
+        ::core::str::from_utf8(&self.ssid[..len]).unwrap_or("")
+    }
+}
+
+pub const NUM_OF_RATE_INDEXES: usize = 20;
+pub const SIZE_OF_RSSI_HISTOGRAM: usize = 6;
+
+/// SlGetRxStatResponse_t
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SlGetRxStatResponse {
+    /// Packets received OK, including the filtered ones.
+    pub received_valid_packets: u32,
+    /// Packets dropped because of an FCS error.
+    pub received_fcs_error_packets: u32,
+    /// Packets filtered out by one of the hardware filters.
+    pub received_address_mismatch_packets: u32,
+    pub avg_data_ctrl_rssi: i16,
+    pub avg_mgmt_rssi: i16,
+    pub rate_histogram: [u16; NUM_OF_RATE_INDEXES],
+    /// RSSI histogram from -40 to -87 dBm, the first and last cells also
+    /// count the values out of this range.
+    pub rssi_histogram: [u16; SIZE_OF_RSSI_HISTOGRAM],
+    /// When the collection started, in microseconds.
+    pub start_timestamp: u32,
+    /// When the statistics were read, in microseconds.
+    pub get_timestamp: u32,
+}
+
+// Encodes the DeviceGetId/DeviceSetId and the Option into a single value,
+// the same way as WlanConfig. The status options select the event class.
+c_like_enum! {
+    DeviceConfig {
+        GeneralDateTime = 0x010b,
+        GeneralVersion = 0x010c,
+        StatusDevice = 0x0201,
+        StatusWlan = 0x0202,
+        StatusBsd = 0x0203,
+        StatusNetApp = 0x0204,
+        StatusNetCfg = 0x0205,
+        StatusFs = 0x0206
+    }
+}
+
+// Bits of the device status read with DeviceConfig::Status*. The low bits
+// flag the events of the class dropped by the host driver.
+pub const STATUS_DEVICE_SMART_CONFIG_ACTIVE: u32 = 0x80000000;
+pub const STATUS_WLAN_STA_CONNECTED: u32 = 0x80000000;
+pub const EVENT_DROPPED_DEVICE_ASYNC_GENERAL_ERROR: u32 = 0x00000001;
+pub const EVENT_DROPPED_WLAN_WLANASYNCONNECTEDRESPONSE: u32 = 0x00000001;
+pub const EVENT_DROPPED_WLAN_WLANASYNCDISCONNECTEDRESPONSE: u32 = 0x00000002;
+pub const EVENT_DROPPED_WLAN_STA_CONNECTED: u32 = 0x00000004;
+pub const EVENT_DROPPED_WLAN_STA_DISCONNECTED: u32 = 0x00000008;
+pub const EVENT_DROPPED_NETAPP_IPACQUIRED: u32 = 0x00000001;
+pub const EVENT_DROPPED_NETAPP_IPACQUIRED_V6: u32 = 0x00000002;
+pub const EVENT_DROPPED_NETAPP_IP_LEASED: u32 = 0x00000004;
+pub const EVENT_DROPPED_NETAPP_IP_RELEASED: u32 = 0x00000008;
+pub const EVENT_DROPPED_SOCKET_TXFAILEDASYNCRESPONSE: u32 = 0x00000001;
+
 // Encodes the ConfigId and ConfigOpt into a single value.
 // The ConfigId is in  in bits 8-15 and ConfigOpt is in bits 0-7
 c_like_enum! {
@@ -283,6 +860,304 @@ impl WlanRxFilterOpBuf {
             padding: [0; 4],
         }
     }
+
+    pub fn no_filters() -> Self {
+        WlanRxFilterOpBuf {
+            mask: [0; 16],
+            padding: [0; 4],
+        }
+    }
+
+    pub fn from_filters(ids: &[RxFilterId]) -> Result<Self, SimpleLinkError> {
+        let mut buf = WlanRxFilterOpBuf::no_filters();
+        for id in ids {
+            try!(buf.add_filter(*id));
+        }
+        Ok(buf)
+    }
+
+    /// Fails for IDs outside of 0..128, which the NWP never returns.
+    pub fn add_filter(&mut self, id: RxFilterId) -> Result<(), SimpleLinkError> {
+        if id.0 < 0 {
+            return Err(SimpleLinkError::Osi(OsiError::OSI_INVALID_PARAMS));
+        }
+        let i = id.0 as usize;
+        self.mask[i >> 3] |= 0x80 >> (i & 7);
+        Ok(())
+    }
+}
+
+/// Identifier returned by sl_WlanRxFilterAdd.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RxFilterId(pub i8);
+
+// The filter ID used as parent by filters which don't depend on another one.
+pub const RX_FILTER_NO_PARENT: i8 = 0;
+
+c_like_enum! {
+    RxFilterRuleType {
+        Header = 0,
+        Combination = 1,
+        AlwaysTrue = 4
+    }
+}
+
+// Flags passed to sl_WlanRxFilterAdd
+pub const RX_FILTER_BINARY: u8 = 0x1;
+pub const RX_FILTER_PERSISTENT: u8 = 0x8;
+pub const RX_FILTER_ENABLE: u8 = 0x10;
+
+c_like_enum! {
+    RxFilterHeaderField {
+        FrameType = 1,
+        FrameSubtype = 2,
+        Bssid = 3,
+        MacSrcAddr = 4,
+        MacDstAddr = 5,
+        FrameLength = 6,
+        ProtocolType = 7,
+        IpVersion = 8,
+        IpProtocol = 9,
+        Ipv4SrcAddr = 10,
+        Ipv4DstAddr = 11,
+        Ipv6SrcAddr = 12,
+        Ipv6DstAddr = 13,
+        SrcPort = 14,
+        DstPort = 15
+    }
+}
+
+c_like_enum! {
+    RxFilterCompareFunc {
+        InBetween = 0,
+        Equal = 1,
+        NotEqual = 2,
+        NotInBetween = 3
+    }
+}
+
+c_like_enum! {
+    RxFilterCombinationOp {
+        Not = 0,
+        And = 1,
+        Or = 2
+    }
+}
+
+// Trigger connection states (bitmask)
+pub const RX_FILTER_CONNECTION_STATE_STA_CONNECTED: u8 = 0x1;
+pub const RX_FILTER_CONNECTION_STATE_STA_NOT_CONNECTED: u8 = 0x2;
+pub const RX_FILTER_CONNECTION_STATE_STA_HAS_IP: u8 = 0x4;
+pub const RX_FILTER_CONNECTION_STATE_STA_HAS_NO_IP: u8 = 0x8;
+
+// Trigger roles (bitmask)
+pub const RX_FILTER_ROLE_AP: u8 = 0x1;
+pub const RX_FILTER_ROLE_STA: u8 = 0x2;
+pub const RX_FILTER_ROLE_PROMISCUOUS: u8 = 0x4;
+
+// Action types (bitmask). RX_FILTER_ACTION_NULL lets the frame through.
+pub const RX_FILTER_ACTION_NULL: u8 = 0x0;
+pub const RX_FILTER_ACTION_DROP: u8 = 0x1;
+pub const RX_FILTER_ACTION_GPIO: u8 = 0x2;
+pub const RX_FILTER_ACTION_ON_REG_INCREASE: u8 = 0x4;
+pub const RX_FILTER_ACTION_ON_REG_DECREASE: u8 = 0x8;
+pub const RX_FILTER_ACTION_ON_REG_RESET: u8 = 0x10;
+pub const RX_FILTER_ACTION_SEND_TEMPLATE: u8 = 0x20;
+pub const RX_FILTER_ACTION_EVENT_TO_HOST: u8 = 0x40;
+
+pub const RX_FILTER_RULE_SIZE: usize = 56;
+pub const RX_FILTER_ARGS_SIZE: usize = 36;
+pub const RX_FILTER_ARGS_MASK_SIZE: usize = 16;
+
+// Offsets in the header rule (SlrxFilterHeaderType_t)
+const RX_FILTER_FIELD_OFFSET: usize = RX_FILTER_ARGS_SIZE + RX_FILTER_ARGS_MASK_SIZE;
+const RX_FILTER_COMPARE_FUNC_OFFSET: usize = RX_FILTER_FIELD_OFFSET + 1;
+
+/// SlrxFilterRule_t: a union of the header and the combination rules.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SlRxFilterRule {
+    pub data: [u8; RX_FILTER_RULE_SIZE],
+    _align: [u32; 0],
+}
+
+impl SlRxFilterRule {
+    /// A header rule. `args` holds the two arguments of the compare function
+    /// back to back (the second one is only used by the in between
+    /// functions), `args_mask` selects the bytes of each argument which are
+    /// compared.
+    pub fn header(field: RxFilterHeaderField,
+                  compare_func: RxFilterCompareFunc,
+                  args: &[u8],
+                  args_mask: &[u8])
+                  -> Self {
+        let mut rule = SlRxFilterRule::default();
+        rule.data[..args.len()].copy_from_slice(args);
+        rule.data[RX_FILTER_ARGS_SIZE..RX_FILTER_ARGS_SIZE + args_mask.len()]
+            .copy_from_slice(args_mask);
+        rule.data[RX_FILTER_FIELD_OFFSET] = field as u8;
+        rule.data[RX_FILTER_COMPARE_FUNC_OFFSET] = compare_func as u8;
+        rule
+    }
+
+    /// A combination rule. `Not` only uses the first filter.
+    pub fn combination(op: RxFilterCombinationOp, ids: [RxFilterId; 2]) -> Self {
+        let mut rule = SlRxFilterRule::default();
+        rule.data[0] = op as u8;
+        rule.data[1] = ids[0].0 as u8;
+        rule.data[2] = ids[1].0 as u8;
+        rule
+    }
+}
+
+impl Default for SlRxFilterRule {
+    fn default() -> Self {
+        SlRxFilterRule {
+            data: [0; RX_FILTER_RULE_SIZE],
+            _align: [],
+        }
+    }
+}
+
+/// SlrxFilterTrigger_t
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SlRxFilterTrigger {
+    pub parent_filter_id: i8,
+    pub counter: u8,
+    pub connection_state: u8,
+    pub roles: u8,
+    pub counter_arg: u32,
+    pub counter_compare_func: u8,
+    padding: [u8; 3],
+}
+
+/// SlrxFilterAction_t
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SlRxFilterAction {
+    pub action_type: u8,
+    pub action_arg: [u8; 5],
+    padding: [u8; 2],
+}
+
+// Positive error codes returned by sl_WlanRxFilterAdd and sl_WlanRxFilterSet
+c_like_enum! {
+    RxFilterError {
+        NUMBER_OF_FILTER_EXCEEDED = 23,
+        NO_FILTERS_ARE_DEFINED = 24,
+        UPDATE_NOT_SUPPORTED = 31,
+        RULE_HEADER_FIELD_ID_OUT_OF_RANGE = 32,
+        RULE_HEADER_COMBINATION_OPERATOR_OUT_OF_RANGE = 33,
+        RULE_HEADER_OUT_OF_RANGE = 34,
+        RULE_HEADER_NOT_SUPPORTED = 35,
+        RULE_HEADER_FIELD_ID_ASCII_NOT_SUPPORTED = 36,
+        RULE_FIELD_ID_NOT_SUPPORTED = 37,
+        FRAME_TYPE_NOT_SUPPORTED = 38,
+        RULE_HEADER_COMPARE_FUNC_OUT_OF_RANGE = 39,
+        RULE_HEADER_TRIGGER_OUT_OF_RANGE = 40,
+        RULE_HEADER_TRIGGER_COMPARE_FUNC_OUT_OF_RANGE = 41,
+        RULE_HEADER_ACTION_TYPE_NOT_SUPPORTED = 42,
+        DEPENDENT_FILTER_DO_NOT_EXIST_1 = 43,
+        DEPENDENT_FILTER_DO_NOT_EXIST_2 = 44,
+        DEPENDENT_FILTER_SYSTEM_STATE_DO_NOT_FIT = 45,
+        DEPENDENT_FILTER_LAYER_DO_NOT_FIT = 46,
+        ACTION_NO_REG_NUMBER = 47,
+        NUMBER_OF_ARGS_EXCEEDED = 48,
+        DEPEDENCY_NOT_ON_THE_SAME_LAYER = 49,
+        FILTER_DO_NOT_EXISTS = 50,
+        DEPENDENT_FILTER_DEPENDENCY_ACTION_IS_DROP = 51,
+        NUMBER_OF_CONNECTION_POINTS_EXCEEDED = 52,
+        DEPENDENCY_IS_DISABLED = 58,
+        CHILD_IS_ENABLED = 59,
+        FILTER_HAS_CHILDS = 60,
+        DEPENDENT_FILTER_IS_NOT_ENABLED = 61,
+        DEPENDENT_FILTER_IS_NOT_PERSISTENT = 62,
+        WRONG_MULTICAST_ADDRESS = 63,
+        WRONG_COMPARE_FUNC_FOR_BROADCAST_ADDRESS = 64,
+        THE_FILTER_IS_NOT_OF_HEADER_TYPE = 65,
+        WRONG_MULTICAST_BROADCAST_ADDRESS = 66,
+        FIELD_SUPPORT_ONLY_EQUAL_AND_NOTEQUAL = 67,
+        ACTION_USE_REG1_TO_REG4 = 68,
+        ACTION_USE_REG5_TO_REG8 = 69,
+        TRIGGER_USE_REG1_TO_REG4 = 70,
+        TRIGGER_USE_REG5_TO_REG8 = 71,
+        SYSTEM_STATE_NOT_SUPPORTED_FOR_THIS_FILTER = 72,
+        DEPENDENCY_IS_NOT_PERSISTENT = 74,
+        DEPENDENT_FILTER_SOFTWARE_FILTER_NOT_FIT = 75,
+        OUTPUT_OR_INPUT_BUFFER_LENGTH_TOO_SMALL = 76
+    }
+}
+
+impl RxFilterError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            RxFilterError::NUMBER_OF_FILTER_EXCEEDED => "too many filters",
+            RxFilterError::NO_FILTERS_ARE_DEFINED => "no filters are defined",
+            RxFilterError::UPDATE_NOT_SUPPORTED => "update not supported",
+            RxFilterError::RULE_HEADER_FIELD_ID_OUT_OF_RANGE => "rule header field ID out of range",
+            RxFilterError::RULE_HEADER_COMBINATION_OPERATOR_OUT_OF_RANGE => {
+                "rule combination operator out of range"
+            }
+            RxFilterError::RULE_HEADER_OUT_OF_RANGE => "rule header out of range",
+            RxFilterError::RULE_HEADER_NOT_SUPPORTED => "rule header not supported",
+            RxFilterError::RULE_HEADER_FIELD_ID_ASCII_NOT_SUPPORTED => {
+                "ASCII rule header field not supported"
+            }
+            RxFilterError::RULE_FIELD_ID_NOT_SUPPORTED => "rule field not supported",
+            RxFilterError::FRAME_TYPE_NOT_SUPPORTED => "frame type not supported",
+            RxFilterError::RULE_HEADER_COMPARE_FUNC_OUT_OF_RANGE => {
+                "rule compare function out of range"
+            }
+            RxFilterError::RULE_HEADER_TRIGGER_OUT_OF_RANGE => "trigger out of range",
+            RxFilterError::RULE_HEADER_TRIGGER_COMPARE_FUNC_OUT_OF_RANGE => {
+                "trigger compare function out of range"
+            }
+            RxFilterError::RULE_HEADER_ACTION_TYPE_NOT_SUPPORTED => "action type not supported",
+            RxFilterError::DEPENDENT_FILTER_DO_NOT_EXIST_1 => "parent filter does not exist",
+            RxFilterError::DEPENDENT_FILTER_DO_NOT_EXIST_2 => "parent filter does not exist",
+            RxFilterError::DEPENDENT_FILTER_SYSTEM_STATE_DO_NOT_FIT => {
+                "parent filter system state does not fit"
+            }
+            RxFilterError::DEPENDENT_FILTER_LAYER_DO_NOT_FIT => "parent filter layer does not fit",
+            RxFilterError::ACTION_NO_REG_NUMBER => "action has no register number",
+            RxFilterError::NUMBER_OF_ARGS_EXCEEDED => "too many arguments",
+            RxFilterError::DEPEDENCY_NOT_ON_THE_SAME_LAYER => "parent filter not on the same layer",
+            RxFilterError::FILTER_DO_NOT_EXISTS => "filter does not exist",
+            RxFilterError::DEPENDENT_FILTER_DEPENDENCY_ACTION_IS_DROP => {
+                "parent filter drops the frames"
+            }
+            RxFilterError::NUMBER_OF_CONNECTION_POINTS_EXCEEDED => "too many connection points",
+            RxFilterError::DEPENDENCY_IS_DISABLED => "parent filter is disabled",
+            RxFilterError::CHILD_IS_ENABLED => "child filter is enabled",
+            RxFilterError::FILTER_HAS_CHILDS => "filter has children",
+            RxFilterError::DEPENDENT_FILTER_IS_NOT_ENABLED => "parent filter is not enabled",
+            RxFilterError::DEPENDENT_FILTER_IS_NOT_PERSISTENT => "parent filter is not persistent",
+            RxFilterError::WRONG_MULTICAST_ADDRESS => "wrong multicast address",
+            RxFilterError::WRONG_COMPARE_FUNC_FOR_BROADCAST_ADDRESS => {
+                "wrong compare function for a broadcast address"
+            }
+            RxFilterError::THE_FILTER_IS_NOT_OF_HEADER_TYPE => "filter is not of header type",
+            RxFilterError::WRONG_MULTICAST_BROADCAST_ADDRESS => {
+                "wrong multicast or broadcast address"
+            }
+            RxFilterError::FIELD_SUPPORT_ONLY_EQUAL_AND_NOTEQUAL => {
+                "field only supports equal and not equal"
+            }
+            RxFilterError::ACTION_USE_REG1_TO_REG4 => "action must use registers 1 to 4",
+            RxFilterError::ACTION_USE_REG5_TO_REG8 => "action must use registers 5 to 8",
+            RxFilterError::TRIGGER_USE_REG1_TO_REG4 => "trigger must use registers 1 to 4",
+            RxFilterError::TRIGGER_USE_REG5_TO_REG8 => "trigger must use registers 5 to 8",
+            RxFilterError::SYSTEM_STATE_NOT_SUPPORTED_FOR_THIS_FILTER => {
+                "system state not supported for this filter"
+            }
+            RxFilterError::DEPENDENCY_IS_NOT_PERSISTENT => "parent filter is not persistent",
+            RxFilterError::DEPENDENT_FILTER_SOFTWARE_FILTER_NOT_FIT => {
+                "parent software filter does not fit"
+            }
+            RxFilterError::OUTPUT_OR_INPUT_BUFFER_LENGTH_TOO_SMALL => "buffer too small",
+        }
+    }
 }
 
 c_like_enum! {
@@ -314,6 +1189,48 @@ impl SlSecParams {
             key_len: key.len() as u8,
         }
     }
+
+    pub fn wps_push_button() -> Self {
+        SlSecParams {
+            sec_type: SecurityType::WpsPushButtonConfig as u8,
+            key: ::core::ptr::null(),
+            key_len: 0,
+        }
+    }
+
+    pub fn wps_pin(pin: &str) -> Self {
+        SlSecParams {
+            sec_type: SecurityType::WpsPin as u8,
+            key: pin.as_ptr(),
+            key_len: pin.len() as u8,
+        }
+    }
+
+    pub fn p2p_push_button() -> Self {
+        SlSecParams {
+            sec_type: SecurityType::P2pPushButtonConfig as u8,
+            key: ::core::ptr::null(),
+            key_len: 0,
+        }
+    }
+
+    /// The PIN is entered on this device.
+    pub fn p2p_pin_keypad(pin: &str) -> Self {
+        SlSecParams {
+            sec_type: SecurityType::P2pPinKeypad as u8,
+            key: pin.as_ptr(),
+            key_len: pin.len() as u8,
+        }
+    }
+
+    /// The PIN is displayed by this device and entered on the peer.
+    pub fn p2p_pin_display(pin: &str) -> Self {
+        SlSecParams {
+            sec_type: SecurityType::P2pPinDisplay as u8,
+            key: pin.as_ptr(),
+            key_len: pin.len() as u8,
+        }
+    }
 }
 
 #[repr(C)]
@@ -337,6 +1254,27 @@ pub struct SlVersionFull {
     padding: u16,
 }
 
+/// The NWP date and time, used to check the validity dates of the
+/// certificates. Months and days are 1 based.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SlDateTime {
+    pub sec: u32,
+    pub min: u32,
+    pub hour: u32,
+    /// Day of the month - [1, 31]
+    pub day: u32,
+    /// Month - [1, 12]
+    pub mon: u32,
+    /// Year, eg. 2017
+    pub year: u32,
+    /// Not required when setting.
+    pub week_day: u32,
+    /// Not required when setting.
+    pub year_day: u32,
+    reserved: [u32; 3],
+}
+
 #[repr(C)]
 #[derive(Default)]
 pub struct SlPingReport {
@@ -435,6 +1373,96 @@ c_like_enum_neg! {
     }
 }
 
+impl FileSystemError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            FileSystemError::NOT_SUPPORTED => "not supported",
+            FileSystemError::FAILED_TO_READ => "failed to read",
+            FileSystemError::INVALID_MAGIC_NUM => "invalid magic number",
+            FileSystemError::DEVICE_NOT_LOADED => "device not loaded",
+            FileSystemError::FAILED_TO_CREATE_LOCK_OBJ => "failed to create the lock object",
+            FileSystemError::UNKNOWN => "unknown error",
+            FileSystemError::FS_ALREADY_LOADED => "file system already loaded",
+            FileSystemError::FAILED_TO_CREATE_FILE => "failed to create the file",
+            FileSystemError::INVALID_ARGS => "invalid arguments",
+            FileSystemError::EMPTY_ERROR => "empty error",
+            FileSystemError::FILE_NOT_EXISTS => "file does not exist",
+            FileSystemError::INVALID_FILE_ID => "invalid file ID",
+            FileSystemError::READ_DATA_LENGTH => "invalid read data length",
+            FileSystemError::ALLOC => "allocation failed",
+            FileSystemError::OFFSET_OUT_OF_RANGE => "offset out of range",
+            FileSystemError::FAILED_TO_WRITE => "failed to write",
+            FileSystemError::INVALID_HANDLE => "invalid file handle",
+            FileSystemError::FAILED_LOAD_FILE => "failed to load the file",
+            FileSystemError::CONTINUE_WRITE_MUST_BE_MOD_4 => {
+                "continued writes must be multiples of 4 bytes"
+            }
+            FileSystemError::FAILED_INIT_STORAGE => "failed to initialise the storage",
+            FileSystemError::FAILED_READ_NVFILE => "failed to read the NV file",
+            FileSystemError::BAD_FILE_MODE => "bad file mode",
+            FileSystemError::FILE_ACCESS_IS_DIFFERENT => "file opened with a different access",
+            FileSystemError::NO_ENTRIES_AVAILABLE => "no entries available",
+            FileSystemError::PROGRAM => "programming failed",
+            FileSystemError::FILE_ALREADY_EXISTS => "file already exists",
+            FileSystemError::INVALID_ACCESS_TYPE => "invalid access type",
+            FileSystemError::FILE_EXISTS_ON_DIFFERENT_DEVICE_ID => {
+                "file exists on a different device ID"
+            }
+            FileSystemError::FILE_MAX_SIZE_BIGGER_THAN_EXISTING_FILE => {
+                "maximum size bigger than the existing file"
+            }
+            FileSystemError::NO_AVAILABLE_BLOCKS => "no available blocks",
+            FileSystemError::FAILED_TO_READ_INTEGRITY_HEADER_1 => {
+                "failed to read the first integrity header"
+            }
+            FileSystemError::FAILED_TO_READ_INTEGRITY_HEADER_2 => {
+                "failed to read the second integrity header"
+            }
+            FileSystemError::FAILED_TO_ALLOCATE_MEM => "failed to allocate memory",
+            FileSystemError::NO_AVAILABLE_NV_INDEX => "no available NV index",
+            FileSystemError::FAILED_WRITE_NVMEM_HEADER => "failed to write the NV memory header",
+            FileSystemError::DEVICE_IS_NOT_FORMATTED => "device is not formatted",
+            FileSystemError::WARNING_FILE_NAME_NOT_KEPT => "file name not kept",
+            FileSystemError::SIZE_OF_FILE_EXT_EXCEEDED => "file extension size exceeded",
+            FileSystemError::FILE_IMAGE_IS_CORRUPTED => "file image is corrupted",
+            FileSystemError::INVALID_BUFFER_FOR_WRITE => "invalid write buffer",
+            FileSystemError::INVALID_BUFFER_FOR_READ => "invalid read buffer",
+            FileSystemError::FILE_MAX_SIZE_EXCEEDED => "file maximum size exceeded",
+            FileSystemError::MAX_FS_FILES_IS_SMALLER => "maximum number of files is smaller",
+            FileSystemError::MAX_FS_FILES_IS_LARGER => "maximum number of files is larger",
+            FileSystemError::FILE_HAS_RESERVED_NV_INDEX => "file has a reserved NV index",
+            FileSystemError::OVERLAP_DETECTION_THRESHHOLD => "overlap detection threshold reached",
+            FileSystemError::DATA_IS_NOT_ALIGNED => "data is not aligned",
+            FileSystemError::DATA_ADDRESS_SHOUD_BE_IN_DATA_RAM => {
+                "data address should be in data RAM"
+            }
+            FileSystemError::NO_DEVICE_IS_LOADED => "no device is loaded",
+            FileSystemError::TOKEN_IS_NOT_VALID => "token is not valid",
+            FileSystemError::FILE_UNVALID_FILE_SIZE => "invalid file size",
+            FileSystemError::SECURITY_ALLERT => "security alert",
+            FileSystemError::FILE_SYSTEM_IS_LOCKED => "file system is locked",
+            FileSystemError::WRONG_FILE_NAME => "wrong file name",
+            FileSystemError::FAILED_READ_NVMEM_HEADER => "failed to read the NV memory header",
+            FileSystemError::INCORRECT_OFFSET_ALIGNMENT => "incorrect offset alignment",
+            FileSystemError::SECURE_FILE_MUST_BE_COMMIT => "secure file must be committed",
+            FileSystemError::SECURITY_BUF_ALREADY_ALLOC => "security buffer already allocated",
+            FileSystemError::FILE_NAME_EXIST => "file name exists",
+            FileSystemError::CERT_CHAIN_ERROR => "certificate chain error",
+            FileSystemError::NOT_16_ALIGNED => "not 16 bytes aligned",
+            FileSystemError::WRONG_SIGNATURE_OR_CERTIFIC_NAME_LENGTH => {
+                "wrong signature or certificate name length"
+            }
+            FileSystemError::WRONG_SIGNATURE => "wrong signature",
+            FileSystemError::FILE_HAS_NOT_BEEN_CLOSE_CORRECTLY => {
+                "file has not been closed correctly"
+            }
+            FileSystemError::ERASING_FLASH => "flash is being erased",
+            FileSystemError::FILE_IS_NOT_SECURE_AND_SIGN => "file is not secure and signed",
+            FileSystemError::EMPTY_SFLASH => "serial flash is empty",
+        }
+    }
+}
+
 #[repr(C)]
 pub struct SlFsFileInfo {
     pub flags: u16,
@@ -443,6 +1471,18 @@ pub struct SlFsFileInfo {
     pub token: [u32; 4]
 }
 
+// File open flags (SlFileOpenFlags_e), set when a file is created.
+pub const FS_FILE_OPEN_FLAG_COMMIT: u32 = 0x1; // Failsafe, mirrored
+pub const FS_FILE_OPEN_FLAG_SECURE: u32 = 0x2; // Encrypted
+pub const FS_FILE_OPEN_FLAG_NO_SIGNATURE_TEST: u32 = 0x4; // Secure files only
+pub const FS_FILE_OPEN_FLAG_STATIC: u32 = 0x8; // Secure files only
+pub const FS_FILE_OPEN_FLAG_VENDOR: u32 = 0x10; // Secure files only
+pub const FS_FILE_PUBLIC_WRITE: u32 = 0x20; // Secure files only, no token to write
+pub const FS_FILE_PUBLIC_READ: u32 = 0x40; // Secure files only, no token to read
+
+// The signature that aborts the changes made to a failsafe file on close.
+pub const FS_ABORT_SIGNATURE: &'static [u8] = b"A";
+
 // Image file paths
 pub const IMG_BOOT_INFO       : &'static str = "/sys/mcubootinfo.bin";
 pub const IMG_FACTORY_DEFAULT : &'static str = "/sys/mcuimg1.bin";
@@ -471,14 +1511,26 @@ extern "C" {
                     callback: Option<extern "C" fn(status: u32)>)
                     -> i16;
     pub fn sl_Stop(timeout: u16) -> i16;
+    pub fn sl_DevGet(device_get_id: u8, option: *mut u8, len: *mut u8, val: *mut u8) -> i32;
+    pub fn sl_DevSet(device_set_id: u8, option: u8, len: u8, val: *const u8) -> i32;
 
     // From simplelink/socket.h
 
     // From simplelink/wlan.h
 
     pub fn sl_WlanSet(config_id: u16, config_opt: u16, len: u16, val: *const u8) -> i16;
+    pub fn sl_WlanGet(config_id: u16, config_opt: *mut u16, len: *mut u16, val: *mut u8) -> i16;
     pub fn sl_WlanSetMode(mode: u8) -> i16;
     pub fn sl_WlanPolicySet(typ: u8, policy: u8, val: *const u8, len: u8) -> i16;
+    pub fn sl_WlanPolicyGet(typ: u8, policy: u8, val: *mut u8, len: *mut u8) -> i16;
+    pub fn sl_WlanProfileAdd(ssid: *const u8,
+                             ssid_len: i16,
+                             mac_addr: *const u8,
+                             sec_params: *const SlSecParams,
+                             sec_params_ext: *const SlSecParamsExt,
+                             priority: u32,
+                             options: u32)
+                             -> i16;
     pub fn sl_WlanProfileDel(index: i16) -> i16;
     pub fn sl_WlanDisconnect() -> i16;
 
@@ -489,8 +1541,20 @@ extern "C" {
                           sec_params_ext: *const SlSecParamsExt)
                           -> i16;
 
+    pub fn sl_WlanGetNetworkList(index: u8, count: u8, entries: *mut SlWlanNetworkEntry) -> i16;
+    pub fn sl_WlanRxStatStart() -> i16;
+    pub fn sl_WlanRxStatStop() -> i16;
+    pub fn sl_WlanRxStatGet(rx_stat: *mut SlGetRxStatResponse, flags: u32) -> i16;
+
     // From simplelink/wlan_rx_filter.h
 
+    pub fn sl_WlanRxFilterAdd(rule_type: u8,
+                              flags: u8,
+                              rule: *const SlRxFilterRule,
+                              trigger: *const SlRxFilterTrigger,
+                              action: *const SlRxFilterAction,
+                              filter_id: *mut i8)
+                              -> i16;
     pub fn sl_WlanRxFilterSet(op: u8, buf: *const u8, len: u16) -> i16;
 
     // From simplelink/netcfg.h
@@ -524,10 +1588,12 @@ extern "C" {
     pub fn simplelink_set_status_bit(bit: u32);
     pub fn simplelink_clear_status_bit(bit: u32);
 
-    pub fn simplelink_get_version(version: *mut SlVersionFull);
     pub fn simplelink_get_driver_version(len: *mut u32) -> *const u8;
 
     pub fn simplelink_gateway_ip() -> u32;
+    pub fn simplelink_get_ipv6_info(ip: *mut u32, gateway: *mut u32, dns: *mut u32) -> bool;
+    pub fn simplelink_get_true_random(buf: *mut u8, len: u16) -> i32;
+    pub fn simplelink_get_connection_info(ssid: *mut u8, bssid: *mut u8) -> u8;
     pub fn simplelink_ping_packets_received() -> u32;
 
     pub fn SimpleLinkPingReport(report: *mut SlPingReport);
@@ -539,7 +1605,7 @@ extern "C" {
 
     pub fn sl_FsOpen(file_name: *const u8,
                      mode: u32,
-                     token: *const u32,
+                     token: *mut u32,
                      file_handle: *mut i32) -> i32;
     pub fn sl_FsClose(file_handle: i32,
                       certificate_file_name: *const u8,
@@ -561,6 +1627,7 @@ extern "C" {
     // simplelink.c
 
     pub fn sl_FsMode(write: bool, create: bool, failsafe: bool, max_size: u32) -> u32;
+    pub fn sl_FsModeWithFlags(write: bool, create: bool, flags: u32, max_size: u32) -> u32;
 
     // FLC and OTA
     //
@@ -4,6 +4,10 @@
 
 // Bindings for a subset of sdk/simplelink/include/socket.h
 
+use core::convert::TryFrom;
+
+use simplelink::SimpleLinkError;
+
 #[repr(i16)]
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Debug)]
@@ -34,207 +38,375 @@ pub enum SocketType {
     SOCK_RAW = 3, // RAW Socket
 }
 
-#[repr(i16)]
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum SocketError {
-    SOC_ERROR = -1, // Failure.
-    SOC_OK = 0, // Success.
-    INEXE = -8, // socket command in execution
-    EBADF = -9, // Bad file number
-    ENSOCK = -10, // The system limit on the total number of open socket, has been reached
-    EAGAIN = -11, // Try again
-    // EWOULDBLOCK = -11,
-    ENOMEM = -12, // Out of memory
-    EACCES = -13, // Permission denied
-    EFAULT = -14, // Bad address
-    ECLOSE = -15, // close socket operation failed to transmit all queued packets
-    EALREADY_ENABLED = -21, // Transceiver - Transceiver already ON. there could be only one
-    EINVAL = -22, // Invalid argument
-    EAUTO_CONNECT_OR_CONNECTING = -69, /* Transceiver - During connection, connected or auto mode started */
-    CONNECTION_PENDING = -72, /* Transceiver - Device is connected, disconnect first to open transceiver */
-    EUNSUPPORTED_ROLE = -86, // Transceiver - Trying to start when WLAN role is AP or P2P GO
-    EDESTADDRREQ = -89, // Destination address required
-    EPROTOTYPE = -91, // Protocol wrong type for socket
-    ENOPROTOOPT = -92, // Protocol not available
-    EPROTONOSUPPORT = -93, // Protocol not supported
-    ESOCKTNOSUPPORT = -94, // Socket type not supported
-    EOPNOTSUPP = -95, // Operation not supported on transport endpoint
-    EAFNOSUPPORT = -97, // Address family not supported by protocol
-    EADDRINUSE = -98, // Address already in use
-    EADDRNOTAVAIL = -99, // Cannot assign requested address
-    ENETUNREACH = -101, // Network is unreachable
-    ENOBUFS = -105, // No buffer space available
-    // EOBUFF = -105,
-    EISCONN = -106, // Transport endpoint is already connected
-    ENOTCONN = -107, // Transport endpoint is not connected
-    ETIMEDOUT = -110, // Connection timed out
-    ECONNREFUSED = -111, // Connection refused
-    EALREADY = -114, // Non blocking connect in progress, try again
-
-    ESEC_RSA_WRONG_TYPE_E = -130, // RSA wrong block type for RSA function
-    ESEC_RSA_BUFFER_E = -131, // RSA buffer error, output too small or
-    ESEC_BUFFER_E = -132, // output buffer too small or input too large
-    ESEC_ALGO_ID_E = -133, // setting algo id error
-    ESEC_PUBLIC_KEY_E = -134, // setting public key error
-    ESEC_DATE_E = -135, // setting date validity error
-    ESEC_SUBJECT_E = -136, // setting subject name error
-    ESEC_ISSUER_E = -137, // setting issuer  name error
-    ESEC_CA_TRUE_E = -138, // setting CA basic constraint true error
-    ESEC_EXTENSIONS_E = -139, // setting extensions error
-    ESEC_ASN_PARSE_E = -140, // ASN parsing error, invalid input
-    ESEC_ASN_VERSION_E = -141, // ASN version error, invalid number
-    ESEC_ASN_GETINT_E = -142, // ASN get big _i16 error, invalid data
-    ESEC_ASN_RSA_KEY_E = -143, // ASN key init error, invalid input
-    ESEC_ASN_OBJECT_ID_E = -144, // ASN object id error, invalid id
-    ESEC_ASN_TAG_NULL_E = -145, // ASN tag error, not null
-    ESEC_ASN_EXPECT_0_E = -146, // ASN expect error, not zero
-    ESEC_ASN_BITSTR_E = -147, // ASN bit string error, wrong id
-    ESEC_ASN_UNKNOWN_OID_E = -148, // ASN oid error, unknown sum id
-    ESEC_ASN_DATE_SZ_E = -149, // ASN date error, bad size
-    ESEC_ASN_BEFORE_DATE_E = -150, // ASN date error, current date before
-    ESEC_ASN_AFTER_DATE_E = -151, // ASN date error, current date after
-    ESEC_ASN_SIG_OID_E = -152, // ASN signature error, mismatched oid
-    ESEC_ASN_TIME_E = -153, // ASN time error, unknown time type
-    ESEC_ASN_INPUT_E = -154, // ASN input error, not enough data
-    ESEC_ASN_SIG_CONFIRM_E = -155, // ASN sig error, confirm failure
-    ESEC_ASN_SIG_HASH_E = -156, // ASN sig error, unsupported hash type
-    ESEC_ASN_SIG_KEY_E = -157, // ASN sig error, unsupported key type
-    ESEC_ASN_DH_KEY_E = -158, // ASN key init error, invalid input
-    ESEC_ASN_NTRU_KEY_E = -159, // ASN ntru key decode error, invalid input
-    ESEC_ECC_BAD_ARG_E = -170, // ECC input argument of wrong type
-    ESEC_ASN_ECC_KEY_E = -171, // ASN ECC bad input
-    ESEC_ECC_CURVE_OID_E = -172, // Unsupported ECC OID curve type
-    ESEC_BAD_FUNC_ARG = -173, // Bad function argument provided
-    ESEC_NOT_COMPILED_IN = -174, // Feature not compiled in
-    ESEC_UNICODE_SIZE_E = -175, // Unicode password too big
-    ESEC_NO_PASSWORD = -176, // no password provided by user
-    ESEC_ALT_NAME_E = -177, // alt name size problem, too big
-    ESEC_AES_GCM_AUTH_E = -180, // AES-GCM Authentication check failure
-    ESEC_AES_CCM_AUTH_E = -181, // AES-CCM Authentication check failure
-    SOCKET_ERROR_E = -208, // Error state on socket
-
-    ESEC_MEMORY_ERROR = -203, // out of memory
-    ESEC_VERIFY_FINISHED_ERROR = -204, // verify problem on finished
-    ESEC_VERIFY_MAC_ERROR = -205, // verify mac problem
-    ESEC_UNKNOWN_HANDSHAKE_TYPE = -207, // weird handshake type
-    // ESEC_SOCKET_ERROR_E = -208, // error state on socket
-    ESEC_SOCKET_NODATA = -209, // expected data, not there
-    ESEC_INCOMPLETE_DATA = -210, // don't have enough data to complete task
-    ESEC_UNKNOWN_RECORD_TYPE = -211, // unknown type in record hdr
-    ESEC_FATAL_ERROR = -213, // recvd alert fatal error
-    ESEC_ENCRYPT_ERROR = -214, // error during encryption
-    ESEC_NO_PEER_KEY = -216, // need peer's key
-    ESEC_NO_PRIVATE_KEY = -217, // need the private key
-    ESEC_RSA_PRIVATE_ERROR = -218, // error during rsa priv op
-    ESEC_NO_DH_PARAMS = -219, // server missing DH params
-    ESEC_BUILD_MSG_ERROR = -220, // build message failure
-    ESEC_BAD_HELLO = -221, // client hello malformed
-    ESEC_DOMAIN_NAME_MISMATCH = -222, // peer subject name mismatch
-    ESEC_WANT_READ = -223, // want read, call again
-    ESEC_NOT_READY_ERROR = -224, // handshake layer not ready
-    ESEC_PMS_VERSION_ERROR = -225, // pre m secret version error
-    ESEC_VERSION_ERROR = -226, // record layer version error
-    ESEC_WANT_WRITE = -227, // want write, call again
-    ESEC_BUFFER_ERROR = -228, // malformed buffer input
-    ESEC_VERIFY_CERT_ERROR = -229, // verify cert error
-    ESEC_VERIFY_SIGN_ERROR = -230, // verify sign error
-
-    ESEC_LENGTH_ERROR = -241, // record layer length error
-    ESEC_PEER_KEY_ERROR = -242, // can't decode peer key
-    ESEC_ZERO_RETURN = -243, // peer sent close notify
-    ESEC_SIDE_ERROR = -244, // wrong client/server type
-    ESEC_NO_PEER_CERT = -245, // peer didn't send key
-    ESEC_ECC_CURVETYPE_ERROR = -250, // Bad ECC Curve Type
-    ESEC_ECC_CURVE_ERROR = -251, // Bad ECC Curve
-    ESEC_ECC_PEERKEY_ERROR = -252, // Bad Peer ECC Key
-    ESEC_ECC_MAKEKEY_ERROR = -253, // Bad Make ECC Key
-    ESEC_ECC_EXPORT_ERROR = -254, // Bad ECC Export Key
-    ESEC_ECC_SHARED_ERROR = -255, // Bad ECC Shared Secret
-    ESEC_NOT_CA_ERROR = -257, // Not a CA cert error
-    ESEC_BAD_PATH_ERROR = -258, // Bad path for opendir
-    ESEC_BAD_CERT_MANAGER_ERROR = -259, // Bad Cert Manager
-    ESEC_MAX_CHAIN_ERROR = -268, // max chain depth exceeded
-    ESEC_SUITES_ERROR = -271, // suites pointer error
-    ESEC_SSL_NO_PEM_HEADER = -272, // no PEM header found
-    ESEC_OUT_OF_ORDER_E = -273, // out of order message
-    ESEC_SANITY_CIPHER_E = -275, // sanity check on cipher error
-    ESEC_GEN_COOKIE_E = -277, // Generate Cookie Error
-    ESEC_NO_PEER_VERIFY = -278, // Need peer cert verify Error
-    ESEC_UNKNOWN_SNI_HOST_NAME_E = -281, // Unrecognized host name Error
-    // begin negotiation parameter errors
-    ESEC_UNSUPPORTED_SUITE = -290, // unsupported cipher suite
-    ESEC_MATCH_SUITE_ERROR = -291, // can't match cipher suite
-
-    // ssl tls security start with -300 offset
-    ESEC_CLOSE_NOTIFY = -300, // ssl/tls alerts
-    ESEC_UNEXPECTED_MESSAGE = -310, // ssl/tls alerts
-    ESEC_BAD_RECORD_MAC = -320, // ssl/tls alerts
-    ESEC_DECRYPTION_FAILED = -321, // ssl/tls alerts
-    ESEC_RECORD_OVERFLOW = -322, // ssl/tls alerts
-    ESEC_DECOMPRESSION_FAILURE = -330, // ssl/tls alerts
-    ESEC_HANDSHAKE_FAILURE = -340, // ssl/tls alerts
-    ESEC_NO_CERTIFICATE = -341, // ssl/tls alerts
-    ESEC_BAD_CERTIFICATE = -342, // ssl/tls alerts
-    ESEC_UNSUPPORTED_CERTIFICATE = -343, // ssl/tls alerts
-    ESEC_CERTIFICATE_REVOKED = -344, // ssl/tls alerts
-    ESEC_CERTIFICATE_EXPIRED = -345, // ssl/tls alerts
-    ESEC_CERTIFICATE_UNKNOWN = -346, // ssl/tls alerts
-    ESEC_ILLEGAL_PARAMETER = -347, // ssl/tls alerts
-    ESEC_UNKNOWN_CA = -348, // ssl/tls alerts
-    ESEC_ACCESS_DENIED = -349, // ssl/tls alerts
-    ESEC_DECODE_ERROR = -350, // ssl/tls alerts
-    ESEC_DECRYPT_ERROR = -351, // ssl/tls alerts
-    ESEC_EXPORT_RESTRICTION = -360, // ssl/tls alerts
-    ESEC_PROTOCOL_VERSION = -370, // ssl/tls alerts
-    ESEC_INSUFFICIENT_SECURITY = -371, // ssl/tls alerts
-    ESEC_INTERNAL_ERROR = -380, // ssl/tls alerts
-    ESEC_USER_CANCELLED = -390, // ssl/tls alerts
-    ESEC_NO_RENEGOTIATION = -400, // ssl/tls alerts
-    ESEC_UNSUPPORTED_EXTENSION = -410, // ssl/tls alerts
-    ESEC_CERTIFICATE_UNOBTAINABLE = -411, // ssl/tls alerts
-    ESEC_UNRECOGNIZED_NAME = -412, // ssl/tls alerts
-    ESEC_BAD_CERTIFICATE_STATUS_RESPONSE = -413, // ssl/tls alerts
-    ESEC_BAD_CERTIFICATE_HASH_VALUE = -414, // ssl/tls alerts
-    // propierty secure
-    ESECGENERAL = -450, // error secure level general error
-    ESECDECRYPT = -451, // error secure level, decrypt recv packet fail
-    ESECCLOSED = -452, // secure layrer is closed by other size , tcp is still connected
-    ESECSNOVERIFY = -453, // Connected without server verification
-    ESECNOCAFILE = -454, // error secure level CA file not found
-    ESECMEMORY = -455, // error secure level No memory  space available
-    ESECBADCAFILE = -456, // error secure level bad CA file
-    ESECBADCERTFILE = -457, // error secure level bad Certificate file
-    ESECBADPRIVATEFILE = -458, // error secure level bad private file
-    ESECBADDHFILE = -459, // error secure level bad DH file
-    ESECT00MANYSSLOPENED = -460, // MAX SSL Sockets are opened
-    ESECDATEERROR = -461, // connected with certificate date verification error
-    ESECHANDSHAKETIMEDOUT = -462, // connection timed out due to handshake time
+c_like_enum_neg! {
+    SocketError {
+        SOC_ERROR = -1, // Failure.
+        INEXE = -8, // socket command in execution
+        EBADF = -9, // Bad file number
+        ENSOCK = -10, // The system limit on the total number of open socket, has been reached
+        EAGAIN = -11, // Try again
+        // EWOULDBLOCK = -11,
+        ENOMEM = -12, // Out of memory
+        EACCES = -13, // Permission denied
+        EFAULT = -14, // Bad address
+        ECLOSE = -15, // close socket operation failed to transmit all queued packets
+        EALREADY_ENABLED = -21, // Transceiver - Transceiver already ON. there could be only one
+        EINVAL = -22, // Invalid argument
+        EAUTO_CONNECT_OR_CONNECTING = -69, /* Transceiver - During connection, connected or auto mode started */
+        CONNECTION_PENDING = -72, /* Transceiver - Device is connected, disconnect first to open transceiver */
+        EUNSUPPORTED_ROLE = -86, // Transceiver - Trying to start when WLAN role is AP or P2P GO
+        EDESTADDRREQ = -89, // Destination address required
+        EPROTOTYPE = -91, // Protocol wrong type for socket
+        ENOPROTOOPT = -92, // Protocol not available
+        EPROTONOSUPPORT = -93, // Protocol not supported
+        ESOCKTNOSUPPORT = -94, // Socket type not supported
+        EOPNOTSUPP = -95, // Operation not supported on transport endpoint
+        EAFNOSUPPORT = -97, // Address family not supported by protocol
+        EADDRINUSE = -98, // Address already in use
+        EADDRNOTAVAIL = -99, // Cannot assign requested address
+        ENETUNREACH = -101, // Network is unreachable
+        ENOBUFS = -105, // No buffer space available
+        // EOBUFF = -105,
+        EISCONN = -106, // Transport endpoint is already connected
+        ENOTCONN = -107, // Transport endpoint is not connected
+        ETIMEDOUT = -110, // Connection timed out
+        ECONNREFUSED = -111, // Connection refused
+        EALREADY = -114, // Non blocking connect in progress, try again
+
+        ESEC_RSA_WRONG_TYPE_E = -130, // RSA wrong block type for RSA function
+        ESEC_RSA_BUFFER_E = -131, // RSA buffer error, output too small or
+        ESEC_BUFFER_E = -132, // output buffer too small or input too large
+        ESEC_ALGO_ID_E = -133, // setting algo id error
+        ESEC_PUBLIC_KEY_E = -134, // setting public key error
+        ESEC_DATE_E = -135, // setting date validity error
+        ESEC_SUBJECT_E = -136, // setting subject name error
+        ESEC_ISSUER_E = -137, // setting issuer  name error
+        ESEC_CA_TRUE_E = -138, // setting CA basic constraint true error
+        ESEC_EXTENSIONS_E = -139, // setting extensions error
+        ESEC_ASN_PARSE_E = -140, // ASN parsing error, invalid input
+        ESEC_ASN_VERSION_E = -141, // ASN version error, invalid number
+        ESEC_ASN_GETINT_E = -142, // ASN get big _i16 error, invalid data
+        ESEC_ASN_RSA_KEY_E = -143, // ASN key init error, invalid input
+        ESEC_ASN_OBJECT_ID_E = -144, // ASN object id error, invalid id
+        ESEC_ASN_TAG_NULL_E = -145, // ASN tag error, not null
+        ESEC_ASN_EXPECT_0_E = -146, // ASN expect error, not zero
+        ESEC_ASN_BITSTR_E = -147, // ASN bit string error, wrong id
+        ESEC_ASN_UNKNOWN_OID_E = -148, // ASN oid error, unknown sum id
+        ESEC_ASN_DATE_SZ_E = -149, // ASN date error, bad size
+        ESEC_ASN_BEFORE_DATE_E = -150, // ASN date error, current date before
+        ESEC_ASN_AFTER_DATE_E = -151, // ASN date error, current date after
+        ESEC_ASN_SIG_OID_E = -152, // ASN signature error, mismatched oid
+        ESEC_ASN_TIME_E = -153, // ASN time error, unknown time type
+        ESEC_ASN_INPUT_E = -154, // ASN input error, not enough data
+        ESEC_ASN_SIG_CONFIRM_E = -155, // ASN sig error, confirm failure
+        ESEC_ASN_SIG_HASH_E = -156, // ASN sig error, unsupported hash type
+        ESEC_ASN_SIG_KEY_E = -157, // ASN sig error, unsupported key type
+        ESEC_ASN_DH_KEY_E = -158, // ASN key init error, invalid input
+        ESEC_ASN_NTRU_KEY_E = -159, // ASN ntru key decode error, invalid input
+        ESEC_ECC_BAD_ARG_E = -170, // ECC input argument of wrong type
+        ESEC_ASN_ECC_KEY_E = -171, // ASN ECC bad input
+        ESEC_ECC_CURVE_OID_E = -172, // Unsupported ECC OID curve type
+        ESEC_BAD_FUNC_ARG = -173, // Bad function argument provided
+        ESEC_NOT_COMPILED_IN = -174, // Feature not compiled in
+        ESEC_UNICODE_SIZE_E = -175, // Unicode password too big
+        ESEC_NO_PASSWORD = -176, // no password provided by user
+        ESEC_ALT_NAME_E = -177, // alt name size problem, too big
+        ESEC_AES_GCM_AUTH_E = -180, // AES-GCM Authentication check failure
+        ESEC_AES_CCM_AUTH_E = -181, // AES-CCM Authentication check failure
+        SOCKET_ERROR_E = -208, // Error state on socket
+
+        ESEC_MEMORY_ERROR = -203, // out of memory
+        ESEC_VERIFY_FINISHED_ERROR = -204, // verify problem on finished
+        ESEC_VERIFY_MAC_ERROR = -205, // verify mac problem
+        ESEC_UNKNOWN_HANDSHAKE_TYPE = -207, // weird handshake type
+        // ESEC_SOCKET_ERROR_E = -208, // error state on socket
+        ESEC_SOCKET_NODATA = -209, // expected data, not there
+        ESEC_INCOMPLETE_DATA = -210, // don't have enough data to complete task
+        ESEC_UNKNOWN_RECORD_TYPE = -211, // unknown type in record hdr
+        ESEC_FATAL_ERROR = -213, // recvd alert fatal error
+        ESEC_ENCRYPT_ERROR = -214, // error during encryption
+        ESEC_NO_PEER_KEY = -216, // need peer's key
+        ESEC_NO_PRIVATE_KEY = -217, // need the private key
+        ESEC_RSA_PRIVATE_ERROR = -218, // error during rsa priv op
+        ESEC_NO_DH_PARAMS = -219, // server missing DH params
+        ESEC_BUILD_MSG_ERROR = -220, // build message failure
+        ESEC_BAD_HELLO = -221, // client hello malformed
+        ESEC_DOMAIN_NAME_MISMATCH = -222, // peer subject name mismatch
+        ESEC_WANT_READ = -223, // want read, call again
+        ESEC_NOT_READY_ERROR = -224, // handshake layer not ready
+        ESEC_PMS_VERSION_ERROR = -225, // pre m secret version error
+        ESEC_VERSION_ERROR = -226, // record layer version error
+        ESEC_WANT_WRITE = -227, // want write, call again
+        ESEC_BUFFER_ERROR = -228, // malformed buffer input
+        ESEC_VERIFY_CERT_ERROR = -229, // verify cert error
+        ESEC_VERIFY_SIGN_ERROR = -230, // verify sign error
+
+        ESEC_LENGTH_ERROR = -241, // record layer length error
+        ESEC_PEER_KEY_ERROR = -242, // can't decode peer key
+        ESEC_ZERO_RETURN = -243, // peer sent close notify
+        ESEC_SIDE_ERROR = -244, // wrong client/server type
+        ESEC_NO_PEER_CERT = -245, // peer didn't send key
+        ESEC_ECC_CURVETYPE_ERROR = -250, // Bad ECC Curve Type
+        ESEC_ECC_CURVE_ERROR = -251, // Bad ECC Curve
+        ESEC_ECC_PEERKEY_ERROR = -252, // Bad Peer ECC Key
+        ESEC_ECC_MAKEKEY_ERROR = -253, // Bad Make ECC Key
+        ESEC_ECC_EXPORT_ERROR = -254, // Bad ECC Export Key
+        ESEC_ECC_SHARED_ERROR = -255, // Bad ECC Shared Secret
+        ESEC_NOT_CA_ERROR = -257, // Not a CA cert error
+        ESEC_BAD_PATH_ERROR = -258, // Bad path for opendir
+        ESEC_BAD_CERT_MANAGER_ERROR = -259, // Bad Cert Manager
+        ESEC_MAX_CHAIN_ERROR = -268, // max chain depth exceeded
+        ESEC_SUITES_ERROR = -271, // suites pointer error
+        ESEC_SSL_NO_PEM_HEADER = -272, // no PEM header found
+        ESEC_OUT_OF_ORDER_E = -273, // out of order message
+        ESEC_SANITY_CIPHER_E = -275, // sanity check on cipher error
+        ESEC_GEN_COOKIE_E = -277, // Generate Cookie Error
+        ESEC_NO_PEER_VERIFY = -278, // Need peer cert verify Error
+        ESEC_UNKNOWN_SNI_HOST_NAME_E = -281, // Unrecognized host name Error
+        // begin negotiation parameter errors
+        ESEC_UNSUPPORTED_SUITE = -290, // unsupported cipher suite
+        ESEC_MATCH_SUITE_ERROR = -291, // can't match cipher suite
+
+        // ssl tls security start with -300 offset
+        ESEC_CLOSE_NOTIFY = -300, // ssl/tls alerts
+        ESEC_UNEXPECTED_MESSAGE = -310, // ssl/tls alerts
+        ESEC_BAD_RECORD_MAC = -320, // ssl/tls alerts
+        ESEC_DECRYPTION_FAILED = -321, // ssl/tls alerts
+        ESEC_RECORD_OVERFLOW = -322, // ssl/tls alerts
+        ESEC_DECOMPRESSION_FAILURE = -330, // ssl/tls alerts
+        ESEC_HANDSHAKE_FAILURE = -340, // ssl/tls alerts
+        ESEC_NO_CERTIFICATE = -341, // ssl/tls alerts
+        ESEC_BAD_CERTIFICATE = -342, // ssl/tls alerts
+        ESEC_UNSUPPORTED_CERTIFICATE = -343, // ssl/tls alerts
+        ESEC_CERTIFICATE_REVOKED = -344, // ssl/tls alerts
+        ESEC_CERTIFICATE_EXPIRED = -345, // ssl/tls alerts
+        ESEC_CERTIFICATE_UNKNOWN = -346, // ssl/tls alerts
+        ESEC_ILLEGAL_PARAMETER = -347, // ssl/tls alerts
+        ESEC_UNKNOWN_CA = -348, // ssl/tls alerts
+        ESEC_ACCESS_DENIED = -349, // ssl/tls alerts
+        ESEC_DECODE_ERROR = -350, // ssl/tls alerts
+        ESEC_DECRYPT_ERROR = -351, // ssl/tls alerts
+        ESEC_EXPORT_RESTRICTION = -360, // ssl/tls alerts
+        ESEC_PROTOCOL_VERSION = -370, // ssl/tls alerts
+        ESEC_INSUFFICIENT_SECURITY = -371, // ssl/tls alerts
+        ESEC_INTERNAL_ERROR = -380, // ssl/tls alerts
+        ESEC_USER_CANCELLED = -390, // ssl/tls alerts
+        ESEC_NO_RENEGOTIATION = -400, // ssl/tls alerts
+        ESEC_UNSUPPORTED_EXTENSION = -410, // ssl/tls alerts
+        ESEC_CERTIFICATE_UNOBTAINABLE = -411, // ssl/tls alerts
+        ESEC_UNRECOGNIZED_NAME = -412, // ssl/tls alerts
+        ESEC_BAD_CERTIFICATE_STATUS_RESPONSE = -413, // ssl/tls alerts
+        ESEC_BAD_CERTIFICATE_HASH_VALUE = -414, // ssl/tls alerts
+        // propierty secure
+        ESECGENERAL = -450, // error secure level general error
+        ESECDECRYPT = -451, // error secure level, decrypt recv packet fail
+        ESECCLOSED = -452, // secure layrer is closed by other size , tcp is still connected
+        ESECSNOVERIFY = -453, // Connected without server verification
+        ESECNOCAFILE = -454, // error secure level CA file not found
+        ESECMEMORY = -455, // error secure level No memory  space available
+        ESECBADCAFILE = -456, // error secure level bad CA file
+        ESECBADCERTFILE = -457, // error secure level bad Certificate file
+        ESECBADPRIVATEFILE = -458, // error secure level bad private file
+        ESECBADDHFILE = -459, // error secure level bad DH file
+        ESECT00MANYSSLOPENED = -460, // MAX SSL Sockets are opened
+        ESECDATEERROR = -461, // connected with certificate date verification error
+        ESECHANDSHAKETIMEDOUT = -462 // connection timed out due to handshake time
+    }
+}
+
+impl SocketError {
+    /// The description of the error, from the SDK.
+    pub fn description(&self) -> &'static str {
+        match *self {
+            SocketError::SOC_ERROR => "failure",
+            SocketError::INEXE => "socket command in execution",
+            SocketError::EBADF => "bad file number",
+            SocketError::ENSOCK => "the limit of open sockets has been reached",
+            SocketError::EAGAIN => "try again",
+            SocketError::ENOMEM => "out of memory",
+            SocketError::EACCES => "permission denied",
+            SocketError::EFAULT => "bad address",
+            SocketError::ECLOSE => "close socket operation failed to transmit all queued packets",
+            SocketError::EALREADY_ENABLED => "transceiver already on, there can be only one",
+            SocketError::EINVAL => "invalid argument",
+            SocketError::EAUTO_CONNECT_OR_CONNECTING => {
+                "transceiver: connecting, connected or auto mode started"
+            }
+            SocketError::CONNECTION_PENDING => "transceiver: device connected, disconnect first",
+            SocketError::EUNSUPPORTED_ROLE => "transceiver: the WLAN role is AP or P2P GO",
+            SocketError::EDESTADDRREQ => "destination address required",
+            SocketError::EPROTOTYPE => "protocol wrong type for socket",
+            SocketError::ENOPROTOOPT => "protocol not available",
+            SocketError::EPROTONOSUPPORT => "protocol not supported",
+            SocketError::ESOCKTNOSUPPORT => "socket type not supported",
+            SocketError::EOPNOTSUPP => "operation not supported on transport endpoint",
+            SocketError::EAFNOSUPPORT => "address family not supported by protocol",
+            SocketError::EADDRINUSE => "address already in use",
+            SocketError::EADDRNOTAVAIL => "cannot assign requested address",
+            SocketError::ENETUNREACH => "network is unreachable",
+            SocketError::ENOBUFS => "no buffer space available",
+            SocketError::EISCONN => "transport endpoint is already connected",
+            SocketError::ENOTCONN => "transport endpoint is not connected",
+            SocketError::ETIMEDOUT => "connection timed out",
+            SocketError::ECONNREFUSED => "connection refused",
+            SocketError::EALREADY => "non blocking connect in progress, try again",
+            SocketError::ESEC_RSA_WRONG_TYPE_E => "RSA wrong block type for RSA function",
+            SocketError::ESEC_RSA_BUFFER_E => {
+                "RSA buffer error, output too small or input too large"
+            }
+            SocketError::ESEC_BUFFER_E => "output buffer too small or input too large",
+            SocketError::ESEC_ALGO_ID_E => "setting algo id error",
+            SocketError::ESEC_PUBLIC_KEY_E => "setting public key error",
+            SocketError::ESEC_DATE_E => "setting date validity error",
+            SocketError::ESEC_SUBJECT_E => "setting subject name error",
+            SocketError::ESEC_ISSUER_E => "setting issuer  name error",
+            SocketError::ESEC_CA_TRUE_E => "setting CA basic constraint true error",
+            SocketError::ESEC_EXTENSIONS_E => "setting extensions error",
+            SocketError::ESEC_ASN_PARSE_E => "ASN parsing error, invalid input",
+            SocketError::ESEC_ASN_VERSION_E => "ASN version error, invalid number",
+            SocketError::ESEC_ASN_GETINT_E => "ASN get big int error, invalid data",
+            SocketError::ESEC_ASN_RSA_KEY_E => "ASN key init error, invalid input",
+            SocketError::ESEC_ASN_OBJECT_ID_E => "ASN object id error, invalid id",
+            SocketError::ESEC_ASN_TAG_NULL_E => "ASN tag error, not null",
+            SocketError::ESEC_ASN_EXPECT_0_E => "ASN expect error, not zero",
+            SocketError::ESEC_ASN_BITSTR_E => "ASN bit string error, wrong id",
+            SocketError::ESEC_ASN_UNKNOWN_OID_E => "ASN oid error, unknown sum id",
+            SocketError::ESEC_ASN_DATE_SZ_E => "ASN date error, bad size",
+            SocketError::ESEC_ASN_BEFORE_DATE_E => "ASN date error, current date before",
+            SocketError::ESEC_ASN_AFTER_DATE_E => "ASN date error, current date after",
+            SocketError::ESEC_ASN_SIG_OID_E => "ASN signature error, mismatched oid",
+            SocketError::ESEC_ASN_TIME_E => "ASN time error, unknown time type",
+            SocketError::ESEC_ASN_INPUT_E => "ASN input error, not enough data",
+            SocketError::ESEC_ASN_SIG_CONFIRM_E => "ASN sig error, confirm failure",
+            SocketError::ESEC_ASN_SIG_HASH_E => "ASN sig error, unsupported hash type",
+            SocketError::ESEC_ASN_SIG_KEY_E => "ASN sig error, unsupported key type",
+            SocketError::ESEC_ASN_DH_KEY_E => "ASN key init error, invalid input",
+            SocketError::ESEC_ASN_NTRU_KEY_E => "ASN ntru key decode error, invalid input",
+            SocketError::ESEC_ECC_BAD_ARG_E => "ECC input argument of wrong type",
+            SocketError::ESEC_ASN_ECC_KEY_E => "ASN ECC bad input",
+            SocketError::ESEC_ECC_CURVE_OID_E => "unsupported ECC OID curve type",
+            SocketError::ESEC_BAD_FUNC_ARG => "bad function argument provided",
+            SocketError::ESEC_NOT_COMPILED_IN => "feature not compiled in",
+            SocketError::ESEC_UNICODE_SIZE_E => "unicode password too big",
+            SocketError::ESEC_NO_PASSWORD => "no password provided by user",
+            SocketError::ESEC_ALT_NAME_E => "alt name size problem, too big",
+            SocketError::ESEC_AES_GCM_AUTH_E => "AES-GCM Authentication check failure",
+            SocketError::ESEC_AES_CCM_AUTH_E => "AES-CCM Authentication check failure",
+            SocketError::SOCKET_ERROR_E => "error state on socket",
+            SocketError::ESEC_MEMORY_ERROR => "out of memory",
+            SocketError::ESEC_VERIFY_FINISHED_ERROR => "verify problem on finished",
+            SocketError::ESEC_VERIFY_MAC_ERROR => "verify mac problem",
+            SocketError::ESEC_UNKNOWN_HANDSHAKE_TYPE => "weird handshake type",
+            SocketError::ESEC_SOCKET_NODATA => "expected data, not there",
+            SocketError::ESEC_INCOMPLETE_DATA => "don't have enough data to complete task",
+            SocketError::ESEC_UNKNOWN_RECORD_TYPE => "unknown type in record hdr",
+            SocketError::ESEC_FATAL_ERROR => "recvd alert fatal error",
+            SocketError::ESEC_ENCRYPT_ERROR => "error during encryption",
+            SocketError::ESEC_NO_PEER_KEY => "need peer's key",
+            SocketError::ESEC_NO_PRIVATE_KEY => "need the private key",
+            SocketError::ESEC_RSA_PRIVATE_ERROR => "error during rsa priv op",
+            SocketError::ESEC_NO_DH_PARAMS => "server missing DH params",
+            SocketError::ESEC_BUILD_MSG_ERROR => "build message failure",
+            SocketError::ESEC_BAD_HELLO => "client hello malformed",
+            SocketError::ESEC_DOMAIN_NAME_MISMATCH => "peer subject name mismatch",
+            SocketError::ESEC_WANT_READ => "want read, call again",
+            SocketError::ESEC_NOT_READY_ERROR => "handshake layer not ready",
+            SocketError::ESEC_PMS_VERSION_ERROR => "pre m secret version error",
+            SocketError::ESEC_VERSION_ERROR => "record layer version error",
+            SocketError::ESEC_WANT_WRITE => "want write, call again",
+            SocketError::ESEC_BUFFER_ERROR => "malformed buffer input",
+            SocketError::ESEC_VERIFY_CERT_ERROR => "verify cert error",
+            SocketError::ESEC_VERIFY_SIGN_ERROR => "verify sign error",
+            SocketError::ESEC_LENGTH_ERROR => "record layer length error",
+            SocketError::ESEC_PEER_KEY_ERROR => "can't decode peer key",
+            SocketError::ESEC_ZERO_RETURN => "peer sent close notify",
+            SocketError::ESEC_SIDE_ERROR => "wrong client/server type",
+            SocketError::ESEC_NO_PEER_CERT => "peer didn't send key",
+            SocketError::ESEC_ECC_CURVETYPE_ERROR => "bad ECC Curve Type",
+            SocketError::ESEC_ECC_CURVE_ERROR => "bad ECC Curve",
+            SocketError::ESEC_ECC_PEERKEY_ERROR => "bad Peer ECC Key",
+            SocketError::ESEC_ECC_MAKEKEY_ERROR => "bad Make ECC Key",
+            SocketError::ESEC_ECC_EXPORT_ERROR => "bad ECC Export Key",
+            SocketError::ESEC_ECC_SHARED_ERROR => "bad ECC Shared Secret",
+            SocketError::ESEC_NOT_CA_ERROR => "not a CA cert error",
+            SocketError::ESEC_BAD_PATH_ERROR => "bad path for opendir",
+            SocketError::ESEC_BAD_CERT_MANAGER_ERROR => "bad Cert Manager",
+            SocketError::ESEC_MAX_CHAIN_ERROR => "max chain depth exceeded",
+            SocketError::ESEC_SUITES_ERROR => "suites pointer error",
+            SocketError::ESEC_SSL_NO_PEM_HEADER => "no PEM header found",
+            SocketError::ESEC_OUT_OF_ORDER_E => "out of order message",
+            SocketError::ESEC_SANITY_CIPHER_E => "sanity check on cipher error",
+            SocketError::ESEC_GEN_COOKIE_E => "generate Cookie Error",
+            SocketError::ESEC_NO_PEER_VERIFY => "need peer cert verify Error",
+            SocketError::ESEC_UNKNOWN_SNI_HOST_NAME_E => "unrecognized host name Error",
+            SocketError::ESEC_UNSUPPORTED_SUITE => "unsupported cipher suite",
+            SocketError::ESEC_MATCH_SUITE_ERROR => "can't match cipher suite",
+            SocketError::ESEC_CLOSE_NOTIFY => "SSL/TLS alert: close notify",
+            SocketError::ESEC_UNEXPECTED_MESSAGE => "SSL/TLS alert: unexpected message",
+            SocketError::ESEC_BAD_RECORD_MAC => "SSL/TLS alert: bad record mac",
+            SocketError::ESEC_DECRYPTION_FAILED => "SSL/TLS alert: decryption failed",
+            SocketError::ESEC_RECORD_OVERFLOW => "SSL/TLS alert: record overflow",
+            SocketError::ESEC_DECOMPRESSION_FAILURE => "SSL/TLS alert: decompression failure",
+            SocketError::ESEC_HANDSHAKE_FAILURE => "SSL/TLS alert: handshake failure",
+            SocketError::ESEC_NO_CERTIFICATE => "SSL/TLS alert: no certificate",
+            SocketError::ESEC_BAD_CERTIFICATE => "SSL/TLS alert: bad certificate",
+            SocketError::ESEC_UNSUPPORTED_CERTIFICATE => "SSL/TLS alert: unsupported certificate",
+            SocketError::ESEC_CERTIFICATE_REVOKED => "SSL/TLS alert: certificate revoked",
+            SocketError::ESEC_CERTIFICATE_EXPIRED => "SSL/TLS alert: certificate expired",
+            SocketError::ESEC_CERTIFICATE_UNKNOWN => "SSL/TLS alert: certificate unknown",
+            SocketError::ESEC_ILLEGAL_PARAMETER => "SSL/TLS alert: illegal parameter",
+            SocketError::ESEC_UNKNOWN_CA => "SSL/TLS alert: unknown ca",
+            SocketError::ESEC_ACCESS_DENIED => "SSL/TLS alert: access denied",
+            SocketError::ESEC_DECODE_ERROR => "SSL/TLS alert: decode error",
+            SocketError::ESEC_DECRYPT_ERROR => "SSL/TLS alert: decrypt error",
+            SocketError::ESEC_EXPORT_RESTRICTION => "SSL/TLS alert: export restriction",
+            SocketError::ESEC_PROTOCOL_VERSION => "SSL/TLS alert: protocol version",
+            SocketError::ESEC_INSUFFICIENT_SECURITY => "SSL/TLS alert: insufficient security",
+            SocketError::ESEC_INTERNAL_ERROR => "SSL/TLS alert: internal error",
+            SocketError::ESEC_USER_CANCELLED => "SSL/TLS alert: user cancelled",
+            SocketError::ESEC_NO_RENEGOTIATION => "SSL/TLS alert: no renegotiation",
+            SocketError::ESEC_UNSUPPORTED_EXTENSION => "SSL/TLS alert: unsupported extension",
+            SocketError::ESEC_CERTIFICATE_UNOBTAINABLE => "SSL/TLS alert: certificate unobtainable",
+            SocketError::ESEC_UNRECOGNIZED_NAME => "SSL/TLS alert: unrecognized name",
+            SocketError::ESEC_BAD_CERTIFICATE_STATUS_RESPONSE => {
+                "SSL/TLS alert: bad certificate status response"
+            }
+            SocketError::ESEC_BAD_CERTIFICATE_HASH_VALUE => {
+                "SSL/TLS alert: bad certificate hash value"
+            }
+            SocketError::ESECGENERAL => "error secure level general error",
+            SocketError::ESECDECRYPT => "error secure level, decrypt recv packet fail",
+            SocketError::ESECCLOSED => {
+                "secure layrer is closed by other size , tcp is still connected"
+            }
+            SocketError::ESECSNOVERIFY => "connected without server verification",
+            SocketError::ESECNOCAFILE => "error secure level CA file not found",
+            SocketError::ESECMEMORY => "error secure level No memory  space available",
+            SocketError::ESECBADCAFILE => "error secure level bad CA file",
+            SocketError::ESECBADCERTFILE => "error secure level bad Certificate file",
+            SocketError::ESECBADPRIVATEFILE => "error secure level bad private file",
+            SocketError::ESECBADDHFILE => "error secure level bad DH file",
+            SocketError::ESECT00MANYSSLOPENED => "MAX SSL Sockets are opened",
+            SocketError::ESECDATEERROR => "connected with certificate date verification error",
+            SocketError::ESECHANDSHAKETIMEDOUT => "connection timed out due to handshake time",
+        }
+    }
 }
 
 pub type RawSocket = i16;
 
-// Casts a RawSocket into an Error to check the return value of sl_Socket.
+// Converts a RawSocket into an Error to check the return value of sl_Socket.
+// Codes unknown to SocketError become SOC_ERROR.
 impl ::core::convert::Into<SocketError> for RawSocket {
     fn into(self) -> SocketError {
-        unsafe { ::core::intrinsics::transmute::<RawSocket, SocketError>(self) }
+        SocketError::try_from(self as i32).unwrap_or(SocketError::SOC_ERROR)
     }
 }
 
-// Used for return types that are either an error or a buffer size.
+// Used for return types that are either an error or a buffer size. The
+// negative values convert with `SocketError::try_from`.
 pub type SizeOrError = i16;
 
-// Used to convert a SizeOf Error into a Result for functions like sl_Send
-impl ::core::convert::TryInto<SocketError> for SizeOrError {
-    type Err = ();
-    fn try_into(self) -> Result<SocketError, Self::Err> {
-        if self >= 0 {
-            return  Err(());
-        }
-        Ok(unsafe { ::core::intrinsics::transmute::<SizeOrError, SocketError>(self) })
-    }
-}
-
 // Enum for the return type of SL_FD_ISSET
 #[repr(i16)]
 #[allow(non_camel_case_types)]
@@ -274,6 +446,66 @@ impl ::core::default::Default for SlSockAddrIn_t {
     fn default() -> Self { unsafe { ::core::mem::zeroed() } }
 }
 
+// An IPv6 address, in network byte order.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SlIn6Addr_t {
+    pub s6_addr: [u8; 16usize],
+    _align: [u32; 0],
+}
+
+impl SlIn6Addr_t {
+    pub fn from_octets(octets: [u8; 16]) -> Self {
+        SlIn6Addr_t {
+            s6_addr: octets,
+            _align: [],
+        }
+    }
+
+    pub fn octets(&self) -> [u8; 16] {
+        self.s6_addr
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SlSockAddrIn6_t {
+    pub sin6_family: Family,
+    pub sin6_port: u16, // port in network byte order.
+    pub sin6_flowinfo: u32,
+    pub sin6_addr: SlIn6Addr_t,
+    pub sin6_scope_id: u32,
+}
+
+impl ::core::default::Default for SlSockAddrIn6_t {
+    fn default() -> Self { unsafe { ::core::mem::zeroed() } }
+}
+
+// Storage large enough for any of the socket addresses, to be cast into a
+// SlSockAddr_t pointer. The family is kept as a raw value since it is filled
+// by the NWP on accept and recvfrom.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SlSockAddrStorage_t {
+    pub family: u16,
+    pub data: [u8; 26usize],
+    _align: [u32; 0],
+}
+
+impl SlSockAddrStorage_t {
+    pub fn as_ptr(&self) -> *const SlSockAddr_t {
+        self as *const _ as *const SlSockAddr_t
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut SlSockAddr_t {
+        self as *mut _ as *mut SlSockAddr_t
+    }
+}
+
+impl ::core::default::Default for SlSockAddrStorage_t {
+    fn default() -> Self { unsafe { ::core::mem::zeroed() } }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 #[derive(Debug)]
@@ -391,7 +623,7 @@ extern "C" {
     ///
     /// On success, zero is returned.
     /// On error, a negative number is returned.
-    pub fn sl_Close(socket: RawSocket) -> SocketError;
+    pub fn sl_Close(socket: RawSocket) -> i16;
 
     /// Initiate a connection on a socket
     ///
@@ -420,7 +652,7 @@ extern "C" {
     ///                     On failure, negative value.
     ///                        SL_POOL_IS_EMPTY may be return in case there are no resources in the system
     ///                          In this case try again later or increase MAX_CONCURRENT_ACTIONS
-    pub fn sl_Connect(socket: RawSocket, addr: *const SlSockAddr_t, addrlen: i16) -> SocketError;
+    pub fn sl_Connect(socket: RawSocket, addr: *const SlSockAddr_t, addrlen: i16) -> i16;
 
     /// set socket options
     ///
@@ -545,7 +777,7 @@ extern "C" {
                          optname: OptionName,
                          optval: *const u8,
                          optlen: SlSocklen_t)
-                         -> SocketError;
+                         -> i16;
 
     /// Get socket options
     ///
@@ -584,7 +816,7 @@ extern "C" {
                          optname: OptionName,
                          optval: *mut u8,
                          optlen: *mut SlSocklen_t)
-                         -> SocketError;
+                         -> i16;
 
     /// read data from TCP socket
     ///
@@ -649,7 +881,79 @@ extern "C" {
     /// addrlen          contains the size of the structure pointed to by addr
     ///
     ///                 On success, zero is returned. On error, a negative error code is returned.
-    pub fn sl_Bind(socket: RawSocket, addr: *const SlSockAddr_t, addrlen: i16) -> SocketError;
+    pub fn sl_Bind(socket: RawSocket, addr: *const SlSockAddr_t, addrlen: i16) -> i16;
+
+    /// accept a connection on a socket
+    ///
+    /// This function is used with connection-based socket types (SOCK_STREAM).
+    /// It extracts the first connection request on the queue of pending
+    /// connections, creates a new connected socket, and returns a new file
+    /// descriptor referring to that socket.
+    /// The newly created socket is not in the listening state. The
+    /// original socket sd is unaffected by this call.
+    ///
+    /// socket           socket descriptor (handle)
+    /// addr             the argument addr is a pointer to a sockaddr structure.
+    ///                  This structure is filled in with the address of the
+    ///                  peer socket. May be null.
+    /// addrlen          the addrlen argument is a value-result argument: it
+    ///                  should initially contain the size of the structure
+    ///                  pointed to by addr, on return it will contain the
+    ///                  actual length (in bytes) of the address returned.
+    ///
+    ///                  On success, a socket handle.
+    ///                  On a non-blocking accept a possible negative value is SL_EAGAIN.
+    ///                  On failure, negative value.
+    pub fn sl_Accept(socket: RawSocket,
+                     addr: *mut SlSockAddr_t,
+                     addrlen: *mut SlSocklen_t) -> RawSocket;
+
+    /// read data from socket
+    ///
+    /// function receives a message from a connection-mode or
+    /// connectionless-mode socket
+    ///
+    /// socket           socket handle
+    /// buf              Points to the buffer where the message should be stored.
+    /// len              Specifies the length in bytes of the buffer pointed to by the buffer argument.
+    ///                  Range: 1-16000 bytes
+    /// flags            Specifies the type of message reception.
+    ///                  On this version, this parameter is not supported.
+    /// from             pointer to an address structure indicating the source
+    ///                  address.
+    /// fromlen          source address structure size. This parameter MUST be set to the size
+    ///                  of the structure pointed to by addr.
+    ///
+    ///                  return the number of bytes received,
+    ///                  or a negative value if an error occurred.
+    pub fn sl_RecvFrom(socket: RawSocket,
+                       buf: *mut u8,
+                       len: i16,
+                       flags: i16,
+                       from: *mut SlSockAddr_t,
+                       fromlen: *mut SlSocklen_t) -> SizeOrError;
+
+    /// write data to socket
+    ///
+    /// This function is used to transmit a message to another socket
+    /// (connection less socket SOCK_DGRAM, SOCK_RAW).
+    ///
+    /// socket           socket handle
+    /// buf              Points to a buffer containing the message to be sent
+    /// len              message size in bytes. Range: 1-1460 bytes
+    /// flags            Specifies the type of message transmission.
+    ///                  On this version, this parameter is not supported
+    /// to               pointer to an address structure indicating the destination
+    ///                  address.
+    /// tolen            destination address structure size
+    ///
+    ///                  Return the number of transmitted bytes, or -1 if an error occurred
+    pub fn sl_SendTo(socket: RawSocket,
+                     buf: *const u8,
+                     len: i16,
+                     flags: i16,
+                     to: *const SlSockAddr_t,
+                     tolen: SlSocklen_t) -> SizeOrError;
 
     ///  listen for connections on a socket
     ///
@@ -664,7 +968,7 @@ extern "C" {
     /// backlog          specifies the listen queue depth.
     ///
     ///          On success, zero is returned. On error, a negative error code is returned.
-    pub fn sl_Listen(socket: RawSocket, backlog: i16) -> SocketError;
+    pub fn sl_Listen(socket: RawSocket, backlog: i16) -> i16;
 
     ///  Monitor socket activity
     ///
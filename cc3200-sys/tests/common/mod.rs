@@ -0,0 +1,56 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// What the tests running the file system code of the main crate share: its
+// error, io and rtc modules, with stubs for the modules they use, and
+// `mount`. The included files refer to these modules from the crate root, so
+// the tests import them there:
+//
+//   mod common;
+//   use common::{error, io, mount, rtc, simplelink, time};
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+
+use cc3200_sys::host::{fs_mount, FsMount};
+
+pub mod simplelink {
+    pub use cc3200_sys::simplelink::*;
+
+    pub struct SimpleLink {}
+
+    impl SimpleLink {
+        pub fn check_started() -> Result<(), SimpleLinkError> {
+            Ok(())
+        }
+
+        // The tests hold the lock of their mount, so the catalog needs none.
+        pub fn lock_catalog() -> Result<(), SimpleLinkError> {
+            Ok(())
+        }
+    }
+}
+
+pub mod time {
+    pub type Seconds = i64;
+}
+
+#[path = "../../../src/error.rs"]
+pub mod error;
+#[path = "../../../src/io.rs"]
+pub mod io;
+#[path = "../../../src/rtc.rs"]
+pub mod rtc;
+
+/// Mounts an empty simulated file system for the test `name`. Each test
+/// binary has its own directory, since they run in parallel.
+pub fn mount(name: &str) -> FsMount {
+    let exe = env::current_exe().unwrap();
+    let suite = exe.file_stem().unwrap();
+    let root = env::temp_dir().join("cc3200-sys-tests").join(suite).join(name);
+    let _ = fs::remove_dir_all(&root);
+    fs_mount(&root)
+}
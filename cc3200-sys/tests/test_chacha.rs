@@ -0,0 +1,95 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Checks the ChaCha20 core of the random number generator against the
+// vectors of RFC 7539.
+
+#[path = "../../src/chacha.rs"]
+mod chacha;
+
+fn hex(s: &str) -> Vec<u8> {
+    let digits: Vec<u8> = s.bytes().filter(|b| !(*b as char).is_whitespace()).collect();
+    digits.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+        .collect()
+}
+
+fn words<T: Default + AsMut<[u32]>>(bytes: &[u8]) -> T {
+    let mut words = T::default();
+    for (word, chunk) in words.as_mut().iter_mut().zip(bytes.chunks(4)) {
+        *word = (chunk[0] as u32) | ((chunk[1] as u32) << 8) | ((chunk[2] as u32) << 16) |
+                ((chunk[3] as u32) << 24);
+    }
+    words
+}
+
+fn check_block(key: &[u8], counter: u32, nonce: &[u8], expected: &str) {
+    let mut out = [0; 64];
+    chacha::block(&words(key), counter, &words(nonce), &mut out);
+    assert_eq!(&out[..], &hex(expected)[..]);
+}
+
+// 2.1.1
+#[test]
+fn test_quarter_round() {
+    let mut state = [0; 16];
+    state[0] = 0x11111111;
+    state[1] = 0x01020304;
+    state[2] = 0x9b8d6f43;
+    state[3] = 0x01234567;
+    chacha::quarter_round(&mut state, 0, 1, 2, 3);
+    assert_eq!(&state[..4], &[0xea2a92f4, 0xcb1cf8ce, 0x4581472e, 0x5881c4bb]);
+}
+
+// 2.3.2
+#[test]
+fn test_block() {
+    let key: Vec<u8> = (0..32).collect();
+    check_block(&key,
+                1,
+                &hex("000000090000004a00000000"),
+                "10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e
+                 d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e");
+}
+
+// A.1
+#[test]
+fn test_key_stream() {
+    let zero_key = [0; 32];
+    let zero_nonce = [0; 12];
+    check_block(&zero_key,
+                0,
+                &zero_nonce,
+                "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7
+                 da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586");
+    check_block(&zero_key,
+                1,
+                &zero_nonce,
+                "9f07e7be5551387a98ba977c732d080dcb0f29a048e3656912c6533e32ee7aed
+                 29b721769ce64e43d57133b074d839d531ed1f28510afb45ace10a1f4b794d6f");
+
+    let mut key = [0; 32];
+    key[31] = 1;
+    check_block(&key,
+                1,
+                &zero_nonce,
+                "3aeb5224ecf849929b9d828db1ced4dd832025e8018b8160b82284f3c949aa5a
+                 8eca00bbb4a73bdad192b5c42f73f2fd4e273644c8b36125a64addeb006c13a0");
+
+    let mut key = [0; 32];
+    key[1] = 0xff;
+    check_block(&key,
+                2,
+                &zero_nonce,
+                "72d54dfbf12ec44b362692df94137f328fea8da73990265ec1bbbea1ae9af0ca
+                 13b25aa26cb4a648cb9b9d1be65b2c0924a66c54d545ec1b7374f4872e99f096");
+
+    let mut nonce = [0; 12];
+    nonce[11] = 2;
+    check_block(&zero_key,
+                0,
+                &nonce,
+                "c2c64d378cd536374ae204b9ef933fcd1a8b2288b3dfa49672ab765b54ee27c7
+                 8a970e0e955c14f3a88e741b97c286f75f8fc299e8148362fa198a39531bed6d");
+}
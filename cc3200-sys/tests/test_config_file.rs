@@ -0,0 +1,179 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Runs the config file parser of the main crate against the simulated file
+// system, like test_fs.rs.
+
+// Needs the simulated file system.
+#![cfg(feature = "host-sim")]
+#![feature(collections)]
+
+extern crate cc3200_sys;
+extern crate collections;
+// For the `core` paths of the included files.
+extern crate core;
+extern crate numeric_utils;
+
+
+
+mod common;
+
+use common::{error, io, mount, rtc, simplelink, time};
+
+#[allow(dead_code)]
+#[path = "../../src/config_file.rs"]
+mod config_file;
+
+use config_file::{ConfigFile, Value};
+use error::{DataError, ErrorKind};
+use io::{File, Read, Write};
+
+const CONFIG: &'static str = "\
+# Device configuration
+name = \"sensor \\\"A\\\"\" # the device name
+
+[wifi]
+ssid = OpenWireless.org
+retries = 3
+timeout = 2.5
+dhcp = true
+
+; Calibration
+[sensor.offsets]
+x = -0.125
+y = +12
+scale = 1e3
+";
+
+fn expect_error(text: &str, line: usize, description: &str) {
+    match ConfigFile::parse(text.as_bytes()) {
+        Err(err) => {
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+            assert_eq!((err.line(), err.description()), (Some(line), description));
+        }
+        Ok(_) => panic!("expected {}", description),
+    }
+}
+
+#[test]
+fn test_parse() {
+    let config = ConfigFile::parse(CONFIG.as_bytes()).unwrap();
+    assert_eq!(config.get_str("", "name"), Some("sensor \"A\""));
+    assert_eq!(config.get_str("wifi", "ssid"), Some("OpenWireless.org"));
+    assert_eq!(config.get_int("wifi", "retries"), Some(3));
+    assert_eq!(config.get_float("wifi", "timeout"), Some(2.5));
+    assert_eq!(config.get_bool("wifi", "dhcp"), Some(true));
+    assert_eq!(config.get("sensor.offsets", "x"), Some(&Value::Float(-0.125)));
+    assert_eq!(config.get("sensor.offsets", "y"), Some(&Value::Int(12)));
+    assert_eq!(config.get_float("sensor.offsets", "scale"), Some(1000.0));
+    // Integers read as floats, but not the other way around.
+    assert_eq!(config.get_float("wifi", "retries"), Some(3.0));
+    assert_eq!(config.get_int("wifi", "timeout"), None);
+    assert_eq!(config.get("wifi", "missing"), None);
+
+    let names: Vec<&str> = config.sections().iter().map(|section| section.name()).collect();
+    assert_eq!(names, ["", "wifi", "sensor.offsets"]);
+}
+
+#[test]
+fn test_errors() {
+    expect_error("[wifi\nssid = home\n", 1, "unterminated section header");
+    expect_error("\n[wifi] ssid = home\n", 2, "unexpected text after the section header");
+    expect_error("[wifi]\n[wifi]\n", 2, "duplicate section");
+    expect_error("[wifi]\nssid home\n", 2, "expected key = value");
+    expect_error("[wifi]\n\nmy ssid = home\n", 3, "invalid key");
+    expect_error("a = 1\na = 2\n", 2, "duplicate key");
+    expect_error("a = \"home\n", 1, "unterminated string");
+    expect_error("a = \"home\" b\n", 1, "unexpected text after the value");
+    expect_error("a = \"\\x\"\n", 1, "invalid escape");
+    expect_error("a =  # nothing\n", 1, "missing value");
+    expect_error("a = 99999999999999999999\n", 1, "integer out of range");
+    expect_error("a = 1.2.3\n", 1, "invalid number");
+    expect_error("a = 1e999\n", 1, "number out of range");
+    let err = ConfigFile::parse("a = 1.2.3\n".as_bytes()).err().unwrap();
+    assert_eq!(format!("{}", err), "InvalidInput: line 1: invalid number");
+}
+
+#[test]
+fn test_comments() {
+    let config = ConfigFile::parse("; first\na = home ; the network\nb = 1 # one\nc = 2;\n\
+                                    [wifi] ; the section\nd = \"x;y\" ; quoted\n"
+            .as_bytes())
+        .unwrap();
+    assert_eq!(config.get_str("", "a"), Some("home"));
+    assert_eq!(config.get_int("", "b"), Some(1));
+    assert_eq!(config.get_int("", "c"), Some(2));
+    assert_eq!(config.get_str("wifi", "d"), Some("x;y"));
+}
+
+#[test]
+fn test_set_rejects_what_cant_be_read_back() {
+    let mut config = ConfigFile::new();
+    for &(section, key) in &[("wifi", "my key"), ("wifi", ""), ("my wifi", "ssid"), ("[a]", "b")] {
+        let err = config.set(section, key, Value::Int(1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+    for &value in &[std::f64::NAN, std::f64::INFINITY, std::f64::NEG_INFINITY] {
+        let err = config.set("ota", "interval", Value::Float(value)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+    assert!(config.sections().is_empty());
+    config.set("", "name", Value::Int(1)).unwrap();
+    assert_eq!(config.get_int("", "name"), Some(1));
+}
+
+#[test]
+fn test_write_reads_back() {
+    let mut config = ConfigFile::parse(CONFIG.as_bytes()).unwrap();
+    config.set("wifi", "ssid", Value::Str(String::from("tab\there"))).unwrap();
+    config.set("ota", "url", Value::Str(String::from("http://example.com/"))).unwrap();
+    config.set("ota", "interval", Value::Float(60.0)).unwrap();
+    assert_eq!(config.remove("wifi", "dhcp"), Some(Value::Bool(true)));
+
+    let mut buf = Vec::new();
+    config.write_to(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf.clone()).unwrap(),
+               "name = \"sensor \\\"A\\\"\"\n\
+                \n\
+                [wifi]\n\
+                ssid = \"tab\\there\"\n\
+                retries = 3\n\
+                timeout = 2.5\n\
+                \n\
+                [sensor.offsets]\n\
+                x = -0.125\n\
+                y = 12\n\
+                scale = 1000.0\n\
+                \n\
+                [ota]\n\
+                url = \"http://example.com/\"\n\
+                interval = 60.0\n");
+
+    let read_back = ConfigFile::parse(&buf[..]).unwrap();
+    for section in config.sections() {
+        for &(ref key, ref value) in section.entries() {
+            assert_eq!(read_back.get(section.name(), key), Some(value));
+        }
+    }
+}
+
+#[test]
+fn test_save_and_open() {
+    let _fs = mount("save_and_open");
+    File::create("config.ini", 512, false).unwrap().write_all(CONFIG.as_bytes()).unwrap();
+    let mut config = ConfigFile::open("config.ini").unwrap();
+    config.set("wifi", "retries", Value::Int(5)).unwrap();
+    config.save("config.ini", 512).unwrap();
+    assert_eq!(ConfigFile::open("config.ini").unwrap().get_int("wifi", "retries"), Some(5));
+
+    // A config too large for its file leaves the file alone.
+    config.set("wifi", "key", Value::Str((0..600).map(|_| 'k').collect())).unwrap();
+    match config.save("config.ini", 512) {
+        Err(ref err) if err.data_error() == Some(DataError::TooLarge) => {}
+        result => panic!("unexpected {:?}", result),
+    }
+    let mut text = String::new();
+    File::open("config.ini").unwrap().read_to_string(&mut text).unwrap();
+    assert!(!text.contains("kkk"));
+}
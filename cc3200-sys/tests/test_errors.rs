@@ -0,0 +1,164 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate cc3200_sys;
+// For the `core` paths of error.rs.
+extern crate core;
+
+use cc3200_sys::simplelink::{self, DriverError, FileSystemError, NetAppError, SimpleLinkError,
+                             WlanError};
+use cc3200_sys::socket::SocketError;
+
+#[allow(dead_code)]
+#[path = "../../src/error.rs"]
+mod error;
+
+use error::{DataError, Error, ErrorKind};
+
+#[test]
+fn test_wlan_codes() {
+    match SimpleLinkError::from_wlan_code(-71) {
+        SimpleLinkError::Wlan(WlanError::INVALID_ROLE) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+    // Not a WLAN error, but a driver one.
+    match SimpleLinkError::from_wlan_code(-100) {
+        SimpleLinkError::Driver(DriverError::API_ABORTED) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+    match SimpleLinkError::from_wlan_code(-1000) {
+        SimpleLinkError::ValueError("WlanError", -1000) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+}
+
+#[test]
+fn test_device_codes() {
+    // -2 is ROLE_AP_ERR for the WLAN calls, but INVALID_INPUT for the others.
+    match SimpleLinkError::from_device_code(-2) {
+        SimpleLinkError::Driver(DriverError::INVALID_INPUT) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+    match SimpleLinkError::from_device_code(-71) {
+        SimpleLinkError::ValueError("DriverError", -71) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+}
+
+#[test]
+fn test_netapp_codes() {
+    match SimpleLinkError::from_netapp_code(-161) {
+        SimpleLinkError::NetApp(NetAppError::DNS_NO_SERVER) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+    match SimpleLinkError::from_netapp_code(-5) {
+        SimpleLinkError::Driver(DriverError::MALLOC_ERROR) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+}
+
+#[test]
+fn test_socket_codes() {
+    match SimpleLinkError::from_socket_code(-111) {
+        SimpleLinkError::Socket(SocketError::ECONNREFUSED) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+    match SimpleLinkError::from_socket_code(-1000) {
+        SimpleLinkError::ValueError("SocketError", -1000) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+    // 0 is a success, not an error.
+    match SimpleLinkError::from_socket_code(0) {
+        SimpleLinkError::ValueError("SocketError", 0) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+    // Unknown codes don't turn into an invalid SocketError.
+    let unknown: SocketError = (-1000 as i16).into();
+    assert_eq!(unknown, SocketError::SOC_ERROR);
+}
+
+#[test]
+fn test_fs_codes() {
+    match SimpleLinkError::from_fs_code(-11) {
+        SimpleLinkError::FileSystem(FileSystemError::FILE_NOT_EXISTS) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+}
+
+#[test]
+fn test_code() {
+    for &code in &[-71, -100, -1000] {
+        assert_eq!(SimpleLinkError::from_wlan_code(code).code(), code);
+    }
+    for &code in &[-161, -230, -2] {
+        assert_eq!(SimpleLinkError::from_netapp_code(code).code(), code);
+    }
+    for &code in &[-1, -67] {
+        assert_eq!(SimpleLinkError::from_fs_code(code).code(), code);
+    }
+    for &code in &[-462, -11] {
+        assert_eq!(SimpleLinkError::from_socket_code(code).code(), code);
+    }
+    assert_eq!(SimpleLinkError::NotStarted.code(), simplelink::NOT_STARTED_CODE);
+    assert!(SimpleLinkError::NotStarted.code() != DriverError::API_ABORTED as i32);
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(format!("{}", SimpleLinkError::from_wlan_code(-71)),
+               "WLAN error -71: invalid role for the operation");
+    assert_eq!(format!("{}", SimpleLinkError::from_fs_code(-11)),
+               "File system error -11: file does not exist");
+    assert_eq!(format!("{}", SimpleLinkError::from_socket_code(-111)),
+               "Socket error -111: connection refused");
+    assert_eq!(format!("{}", SimpleLinkError::from_netapp_code(-161)),
+               "NetApp error -161: no DNS server was specified");
+    assert_eq!(format!("{}", SimpleLinkError::NotStarted),
+               "Device error -2000: the NWP isn't started");
+    assert_eq!(format!("{}", SimpleLinkError::ValueError("WlanError", -1000)),
+               "ValueError: Unknown enum value: -1000 for WlanError");
+}
+
+#[test]
+fn test_error_kinds() {
+    let kinds = vec![(SimpleLinkError::from_fs_code(-11), ErrorKind::NotFound),
+                     (SimpleLinkError::from_fs_code(-9), ErrorKind::InvalidInput),
+                     (SimpleLinkError::from_socket_code(-11), ErrorKind::WouldBlock),
+                     (SimpleLinkError::from_socket_code(-111), ErrorKind::Network),
+                     (SimpleLinkError::from_wlan_code(-71), ErrorKind::Device),
+                     (SimpleLinkError::from_device_code(-2), ErrorKind::InvalidInput),
+                     (SimpleLinkError::NotStarted, ErrorKind::Device)];
+    for (err, kind) in kinds {
+        assert_eq!(Error::from(err).kind(), kind);
+    }
+
+    // The SDK error stays available.
+    let err = Error::from(SocketError::ETIMEDOUT);
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    match err.simplelink() {
+        Some(&SimpleLinkError::Socket(SocketError::ETIMEDOUT)) => {}
+        e => panic!("unexpected {:?}", e),
+    }
+    assert!(Error::from(FileSystemError::FILE_NOT_EXISTS).is_fs(FileSystemError::FILE_NOT_EXISTS));
+
+    let err = Error::new(ErrorKind::Device, "I2C transfer failed");
+    assert!(err.simplelink().is_none());
+    assert_eq!(format!("{}", err), "Device: I2C transfer failed");
+    assert_eq!(format!("{}", Error::from(SimpleLinkError::from_fs_code(-11))),
+               "File system error -11: file does not exist");
+}
+
+#[test]
+fn test_data_errors() {
+    let err = Error::data("settings", DataError::UnsupportedVersion(3));
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(err.data_error(), Some(DataError::UnsupportedVersion(3)));
+    assert!(err.simplelink().is_none());
+    assert_eq!(format!("{}", err), "InvalidInput: settings unsupported version 3");
+
+    let err = Error::data("catalog", DataError::Corrupted);
+    assert_eq!(err.kind(), ErrorKind::Io);
+    assert_eq!(format!("{}", err), "Io: catalog corrupted");
+    assert_eq!(Error::new(ErrorKind::Io, "corrupted").data_error(), None);
+}
@@ -0,0 +1,628 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Runs io::File and the image updates against the simulated file system.
+// These modules only need the sl_Fs* functions, so they are built here
+// from the sources of the main crate.
+
+// Needs the simulated file system.
+#![cfg(feature = "host-sim")]
+#![feature(collections)]
+
+extern crate cc3200_sys;
+extern crate collections;
+// For the `core` paths of the included files.
+extern crate core;
+extern crate numeric_utils;
+
+use std::ptr;
+
+use cc3200_sys::host::fs_mode;
+use cc3200_sys::simplelink::{FileSystemError, FS_FILE_OPEN_FLAG_COMMIT,
+                             FS_FILE_OPEN_FLAG_NO_SIGNATURE_TEST, FS_FILE_OPEN_FLAG_SECURE,
+                             IMG_BOOT_INFO, IMG_USER_1, IMG_USER_2, sl_FsClose, sl_FsDel,
+                             sl_FsOpen, sl_FsWrite};
+
+mod common;
+
+use common::{error, io, mount, rtc, simplelink, time};
+
+#[allow(dead_code)]
+#[path = "../../src/update.rs"]
+mod update;
+
+use error::{DataError, Error, ErrorKind};
+use io::{BufRead, BufReader, BufWriter, Catalog, CatalogEntry, DiskUsage, File, FileFlags, Read,
+         Seek, SeekFrom, Write, CATALOG_FILE};
+use rtc::RTC;
+use update::{ImageStatus, Update};
+
+fn read_all(name: &str) -> Vec<u8> {
+    let mut buf = Vec::new();
+    File::open(name).unwrap().read_to_end(&mut buf).unwrap();
+    buf
+}
+
+fn expect_fs_error<T>(result: Result<T, Error>, expected: FileSystemError) {
+    match result {
+        Err(ref err) if err.is_fs(expected) => {}
+        Err(err) => panic!("unexpected {:?}", err),
+        Ok(_) => panic!("expected {:?}", expected),
+    }
+}
+
+#[test]
+fn test_create_write_read() {
+    let _fs = mount("create_write_read");
+    {
+        let mut file = File::create("/data/hello.txt", 100, false).unwrap();
+        assert_eq!(file.write(b"hello").unwrap(), 5);
+        assert_eq!(file.write(b" world").unwrap(), 6);
+    }
+    let mut text = String::new();
+    File::open("/data/hello.txt").unwrap().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "hello world");
+
+    // The size is rounded up to the 256 bytes granularity.
+    let info = File::get_info("/data/hello.txt").unwrap();
+    assert_eq!(info.file_length, 11);
+    assert_eq!(info.allocated_length, 256);
+    assert_eq!(info.token, [0; 4]);
+}
+
+#[test]
+fn test_names_stay_in_the_mount() {
+    let _fs = mount("names_stay_in_the_mount");
+    for name in &["/../escaped.txt", "/data/../../escaped.txt", "/data/./hello.txt"] {
+        expect_fs_error(File::create(name, 100, false), FileSystemError::WRONG_FILE_NAME);
+        expect_fs_error(File::open(name), FileSystemError::WRONG_FILE_NAME);
+        expect_fs_error(File::remove(name), FileSystemError::WRONG_FILE_NAME);
+    }
+}
+
+#[test]
+fn test_read_to_end_spans_chunks() {
+    let _fs = mount("read_to_end");
+    let data: Vec<u8> = (0..200).map(|i| i as u8).collect();
+    File::create("big", 200, false).unwrap().write(&data).unwrap();
+    assert_eq!(read_all("big"), data);
+}
+
+#[test]
+fn test_max_len() {
+    let _fs = mount("max_len");
+    let mut file = File::create("small", 256, false).unwrap();
+    file.write(&[0; 200]).unwrap();
+    expect_fs_error(file.write(&[0; 100]), FileSystemError::FILE_MAX_SIZE_EXCEEDED);
+}
+
+#[test]
+fn test_create_existing_keeps_allocation() {
+    let _fs = mount("create_existing");
+    File::create("file", 1000, false).unwrap().write(b"first").unwrap();
+    // Creating an existing file opens it for writing, with its old size.
+    let mut file = File::create("file", 4096, false).unwrap();
+    assert_eq!(File::get_info("file").unwrap().allocated_length, 1024);
+    expect_fs_error(file.write(&[0; 2000]), FileSystemError::FILE_MAX_SIZE_EXCEEDED);
+}
+
+#[test]
+fn test_open_for_write_truncates() {
+    let _fs = mount("truncate");
+    File::create("file", 256, false).unwrap().write(b"old content").unwrap();
+    let _file = File::create("file", 256, false).unwrap();
+    assert_eq!(read_all("file"), b"");
+}
+
+#[test]
+fn test_failsafe_commit_on_close() {
+    let _fs = mount("failsafe");
+    File::create("config", 256, true).unwrap().write(b"one").unwrap();
+    {
+        let mut file = File::create("config", 256, true).unwrap();
+        file.write(b"two").unwrap();
+        // The previous content stays until the file is closed.
+        assert_eq!(read_all("config"), b"one");
+    }
+    assert_eq!(read_all("config"), b"two");
+    assert_eq!(File::get_info("config").unwrap().flags as u32, FS_FILE_OPEN_FLAG_COMMIT);
+}
+
+#[test]
+fn test_failsafe_abort() {
+    let _fs = mount("failsafe_abort");
+    File::create("config", 256, true).unwrap().write(b"one").unwrap();
+    unsafe {
+        let mut handle = -1;
+        let mode = cc3200_sys::simplelink::sl_FsMode(true, false, false, 0);
+        assert_eq!(sl_FsOpen(b"config\0".as_ptr(), mode, ptr::null_mut(), &mut handle), 0);
+        assert_eq!(sl_FsWrite(handle, 0, b"two".as_ptr(), 3), 3);
+        assert_eq!(sl_FsClose(handle, ptr::null(), b"A".as_ptr(), 1), 0);
+    }
+    assert_eq!(read_all("config"), b"one");
+}
+
+#[test]
+fn test_len_and_capacity() {
+    let _fs = mount("len_and_capacity");
+    let mut file = File::create("file", 1000, false).unwrap();
+    assert_eq!(file.len().unwrap(), 0);
+    assert_eq!(file.capacity(), 1024);
+    file.write(b"0123456789").unwrap();
+    assert_eq!(file.len().unwrap(), 10);
+    drop(file);
+
+    let file = File::open("file").unwrap();
+    assert_eq!(file.len().unwrap(), 10);
+    assert_eq!(file.capacity(), 1024);
+}
+
+#[test]
+fn test_seek() {
+    let _fs = mount("seek");
+    File::create("file", 256, false).unwrap().write(b"hello world").unwrap();
+    let mut file = File::open("file").unwrap();
+    let mut buf = [0; 5];
+
+    assert_eq!(file.seek(SeekFrom::Start(6)).unwrap(), 6);
+    file.read(&mut buf).unwrap();
+    assert_eq!(&buf, b"world");
+
+    assert_eq!(file.seek(SeekFrom::Current(-11)).unwrap(), 0);
+    file.read(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+
+    assert_eq!(file.seek(SeekFrom::End(-5)).unwrap(), 6);
+    file.read(&mut buf).unwrap();
+    assert_eq!(&buf, b"world");
+
+    assert_eq!(file.seek(SeekFrom::End(0)).unwrap(), 11);
+    assert_eq!(file.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn test_seek_bounds() {
+    let _fs = mount("seek_bounds");
+    let mut file = File::create("file", 256, false).unwrap();
+    expect_fs_error(file.seek(SeekFrom::Current(-1)), FileSystemError::INVALID_ARGS);
+    expect_fs_error(file.seek(SeekFrom::Start(257)), FileSystemError::OFFSET_OUT_OF_RANGE);
+    assert_eq!(file.seek(SeekFrom::Start(256)).unwrap(), 256);
+
+    // Writing after a seek leaves a hole, and extends the file.
+    file.seek(SeekFrom::Start(4)).unwrap();
+    file.write(b"data").unwrap();
+    assert_eq!(file.len().unwrap(), 8);
+    drop(file);
+    assert_eq!(read_all("file"), [0, 0, 0, 0, b'd', b'a', b't', b'a']);
+}
+
+#[test]
+fn test_read_exact_and_write_all() {
+    let _fs = mount("exact");
+    File::create("file", 256, false).unwrap().write_all(b"0123456789").unwrap();
+    let mut file = File::open("file").unwrap();
+    let mut buf = [0; 4];
+    file.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"0123");
+    file.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"4567");
+    match file.read_exact(&mut buf) {
+        Err(ref err) if err.kind() == ErrorKind::Io => {}
+        r => panic!("unexpected {:?}", r),
+    }
+    // The bytes read are consumed.
+    assert_eq!(file.read(&mut buf).unwrap(), 0);
+
+    let mut file = File::create("file", 256, false).unwrap();
+    expect_fs_error(file.write_all(&[0; 300]), FileSystemError::FILE_MAX_SIZE_EXCEEDED);
+}
+
+#[test]
+fn test_buffered_lines() {
+    let _fs = mount("lines");
+    {
+        let mut writer = BufWriter::with_capacity(16, File::create("log", 1024, false).unwrap());
+        for i in 0..10 {
+            writeln!(writer, "line {}", i).unwrap();
+        }
+        write!(writer, "last\r\nno newline").unwrap();
+        writer.flush().unwrap();
+    }
+
+    let reader = BufReader::with_capacity(8, File::open("log").unwrap());
+    let lines: Vec<String> = reader.lines().map(|line| line.unwrap()).collect();
+    assert_eq!(lines.len(), 12);
+    assert_eq!(lines[0], "line 0");
+    assert_eq!(lines[9], "line 9");
+    assert_eq!(lines[10], "last");
+    assert_eq!(lines[11], "no newline");
+}
+
+#[test]
+fn test_read_line_invalid_utf8() {
+    let _fs = mount("read_line_invalid_utf8");
+    File::create("text", 256, false).unwrap().write_all(b"ok\n\xff\xfe\nnext\n").unwrap();
+    let mut reader = BufReader::with_capacity(4, File::open("text").unwrap());
+    let mut line = String::new();
+    assert_eq!(reader.read_line(&mut line).unwrap(), 3);
+    match reader.read_line(&mut line) {
+        Err(ref err) if err.kind() == ErrorKind::InvalidData => {}
+        r => panic!("unexpected {:?}", r),
+    }
+    assert_eq!(line, "ok\n");
+    assert_eq!(reader.read_line(&mut line).unwrap(), 5);
+    assert_eq!(line, "ok\nnext\n");
+
+    let mut text = String::new();
+    match File::open("text").unwrap().read_to_string(&mut text) {
+        Err(ref err) if err.kind() == ErrorKind::InvalidData => {}
+        r => panic!("unexpected {:?}", r),
+    }
+}
+
+#[test]
+fn test_buffered_seek() {
+    let _fs = mount("buffered_seek");
+    File::create("file", 256, false).unwrap().write_all(b"0123456789").unwrap();
+    let mut reader = BufReader::with_capacity(8, File::open("file").unwrap());
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf).unwrap();
+    // The reader read ahead, but relative seeks start from what was returned.
+    assert_eq!(reader.seek(SeekFrom::Current(2)).unwrap(), 4);
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"45");
+}
+
+#[test]
+fn test_buffered_writer_into_inner() {
+    let _fs = mount("into_inner");
+    let mut writer = BufWriter::new(File::create("file", 256, false).unwrap());
+    writer.write_all(b"buffered").unwrap();
+    assert_eq!(writer.get_ref().len().unwrap(), 0);
+    let file = writer.into_inner().unwrap();
+    assert_eq!(file.len().unwrap(), 8);
+}
+
+#[test]
+fn test_copy() {
+    let _fs = mount("copy");
+    let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    File::create("src", 1000, false).unwrap().write_all(&data).unwrap();
+    {
+        let mut src = File::open("src").unwrap();
+        let mut dst = File::create("dst", 1000, false).unwrap();
+        assert_eq!(io::copy(&mut src, &mut dst).unwrap(), 1000);
+    }
+    assert_eq!(read_all("dst"), data);
+}
+
+#[test]
+fn test_remove() {
+    let _fs = mount("remove");
+    File::create("file", 256, false).unwrap();
+    File::remove("file").unwrap();
+    expect_fs_error(File::open("file"), FileSystemError::FILE_NOT_EXISTS);
+    expect_fs_error(File::remove("file"), FileSystemError::FILE_NOT_EXISTS);
+    expect_fs_error(File::get_info("file"), FileSystemError::FILE_NOT_EXISTS);
+    assert_eq!(File::remove("file").unwrap_err().kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn test_secure_file_token() {
+    let _fs = mount("secure");
+    let flags = FS_FILE_OPEN_FLAG_COMMIT | FS_FILE_OPEN_FLAG_SECURE |
+                FS_FILE_OPEN_FLAG_NO_SIGNATURE_TEST;
+    let mut token = 0;
+    unsafe {
+        let mut handle = -1;
+        assert_eq!(sl_FsOpen(b"secret\0".as_ptr(), fs_mode(3, 0, 1, flags), &mut token,
+                             &mut handle),
+                   0);
+        assert_eq!(sl_FsClose(handle, ptr::null(), ptr::null(), 0), 0);
+    }
+    assert!(token != 0);
+    assert_eq!(File::get_info("secret").unwrap().token[0], token);
+    unsafe {
+        assert_eq!(sl_FsDel(b"secret\0".as_ptr(), 0),
+                   FileSystemError::TOKEN_IS_NOT_VALID as i16);
+        assert_eq!(sl_FsDel(b"secret\0".as_ptr(), token), 0);
+    }
+}
+
+#[test]
+fn test_secure_file_must_be_failsafe() {
+    let _fs = mount("secure_failsafe");
+    let flags = FileFlags { secure: true, no_signature_test: true, ..FileFlags::default() };
+    expect_fs_error(File::create_with_flags("secret", 256, flags, None),
+                    FileSystemError::SECURE_FILE_MUST_BE_COMMIT);
+}
+
+#[test]
+fn test_secure_file() {
+    let _fs = mount("secure_file");
+    let flags = FileFlags {
+        failsafe: true,
+        secure: true,
+        no_signature_test: true,
+        ..FileFlags::default()
+    };
+    let mut file = File::create_with_flags("key", 256, flags, None).unwrap();
+    let token = file.token();
+    assert!(token != 0);
+    file.write_all(b"secret key").unwrap();
+    file.close().unwrap();
+
+    let info = File::get_info_with_token("key", token).unwrap();
+    assert_eq!(FileFlags::from_bits(info.flags), flags);
+    expect_fs_error(File::open("key"), FileSystemError::TOKEN_IS_NOT_VALID);
+    let mut text = String::new();
+    File::open_with_token("key", token).unwrap().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "secret key");
+
+    // Rewriting the file needs its token too.
+    expect_fs_error(File::create_with_flags("key", 256, flags, None),
+                    FileSystemError::TOKEN_IS_NOT_VALID);
+    File::create_with_flags("key", 256, flags, Some(token)).unwrap().write_all(b"new").unwrap();
+    let mut text = String::new();
+    File::open_with_token("key", token).unwrap().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "new");
+
+    expect_fs_error(File::remove("key"), FileSystemError::TOKEN_IS_NOT_VALID);
+    File::remove_with_token("key", token).unwrap();
+}
+
+#[test]
+fn test_public_read_secure_file() {
+    let _fs = mount("public_read");
+    let flags = FileFlags {
+        failsafe: true,
+        secure: true,
+        no_signature_test: true,
+        public_read: true,
+        ..FileFlags::default()
+    };
+    File::create_with_flags("cert", 256, flags, None).unwrap().write_all(b"public").unwrap();
+    assert_eq!(read_all("cert"), b"public");
+    expect_fs_error(File::remove("cert"), FileSystemError::TOKEN_IS_NOT_VALID);
+}
+
+#[test]
+fn test_signed_close() {
+    let _fs = mount("signed_close");
+    File::create("ca.der", 256, false).unwrap().write_all(b"certificate").unwrap();
+    let flags = FileFlags { failsafe: true, secure: true, ..FileFlags::default() };
+
+    let mut file = File::create_with_flags("firmware", 256, flags, None).unwrap();
+    let token = file.token();
+    file.write_all(b"unsigned").unwrap();
+    expect_fs_error(file.close(),
+                    FileSystemError::WRONG_SIGNATURE_OR_CERTIFIC_NAME_LENGTH);
+
+    let mut file = File::create_with_flags("firmware", 256, flags, Some(token)).unwrap();
+    file.write_all(b"unknown certificate").unwrap();
+    expect_fs_error(file.close_signed(&[0x55; 256], "missing.der"),
+                    FileSystemError::CERT_CHAIN_ERROR);
+
+    let mut file = File::create_with_flags("firmware", 256, flags, Some(token)).unwrap();
+    file.write_all(b"signed").unwrap();
+    file.close_signed(&[0x55; 256], "ca.der").unwrap();
+
+    // Only the signed content was committed.
+    let mut buf = Vec::new();
+    File::open_with_token("firmware", token).unwrap().read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"signed");
+}
+
+#[test]
+fn test_abort() {
+    let _fs = mount("abort");
+    File::create("config", 256, true).unwrap().write_all(b"one").unwrap();
+    let mut file = File::create("config", 256, true).unwrap();
+    file.write_all(b"two").unwrap();
+    file.abort().unwrap();
+    assert_eq!(read_all("config"), b"one");
+}
+
+#[test]
+fn test_catalog() {
+    let _fs = mount("catalog");
+    RTC::set(1500000000);
+    let mut catalog = Catalog::open("catalog", 1024).unwrap();
+    assert!(catalog.entries().is_empty());
+
+    let mut file = catalog.create("data", 300, FileFlags::default(), None).unwrap();
+    file.write_all(b"hello").unwrap();
+    // The entry is saved before the file is written.
+    assert_eq!(Catalog::open("catalog", 1024).unwrap().get("data").unwrap().len, 0);
+    catalog.close(file).unwrap();
+
+    RTC::set(1500000100);
+    let flags = FileFlags { failsafe: true, ..FileFlags::default() };
+    let mut file = catalog.create("config", 256, flags, None).unwrap();
+    file.write_all(b"a=1").unwrap();
+    catalog.close(file).unwrap();
+
+    let catalog = Catalog::open("catalog", 1024).unwrap();
+    let names: Vec<&str> = catalog.entries().iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, ["data", "config"]);
+    let data = catalog.get("data").unwrap();
+    assert_eq!((data.len, data.capacity, data.timestamp), (5, 512, 1500000000));
+    assert_eq!(data.flags, FileFlags::default());
+    let config = catalog.get("config").unwrap();
+    assert_eq!((config.len, config.flags, config.timestamp), (3, flags, 1500000100));
+    assert_eq!(catalog.usage(), DiskUsage { files: 2, len: 8, capacity: 768 });
+}
+
+#[test]
+fn test_catalog_gc() {
+    let _fs = mount("catalog_gc");
+    let mut catalog = Catalog::open("catalog", 1024).unwrap();
+    let flags = FileFlags { failsafe: true, ..FileFlags::default() };
+    let mut file = catalog.create("config", 256, flags, None).unwrap();
+    file.write_all(b"one").unwrap();
+    catalog.close(file).unwrap();
+
+    // An aborted write keeps the previous content and length.
+    let mut file = catalog.create("config", 256, flags, None).unwrap();
+    file.write_all(b"three").unwrap();
+    file.abort().unwrap();
+    assert_eq!(catalog.get("config").unwrap().len, 3);
+
+    // Entries of the files that are gone are dropped, and the files changed
+    // without the catalog are updated.
+    catalog.create("lost", 256, FileFlags::default(), None).unwrap();
+    File::remove("lost").unwrap();
+    File::create("config", 256, true).unwrap().write_all(b"four").unwrap();
+    assert_eq!(catalog.gc().unwrap(), 1);
+    assert!(catalog.get("lost").is_none());
+    assert_eq!(catalog.get("config").unwrap().len, 4);
+    assert_eq!(catalog.gc().unwrap(), 0);
+
+    catalog.remove("config").unwrap();
+    expect_fs_error(File::open("config"), FileSystemError::FILE_NOT_EXISTS);
+    assert!(Catalog::open("catalog", 1024).unwrap().entries().is_empty());
+
+    File::create("catalog", 1024, true).unwrap().write_all(b"not a catalog").unwrap();
+    match Catalog::open("catalog", 1024) {
+        Err(ref err) if err.data_error() == Some(DataError::Corrupted) => {}
+        Err(err) => panic!("unexpected {:?}", err),
+        Ok(_) => panic!("expected a corrupted catalog"),
+    }
+}
+
+#[test]
+fn test_catalog_rejects_long_names() {
+    let _fs = mount("catalog_long_names");
+    let mut catalog = Catalog::open("catalog", 1024).unwrap();
+    // Made of short parts, which the host file system accepts.
+    let mut long_name: String = (0..128).map(|_| "d/").collect();
+    long_name.push('f');
+    match catalog.create(&long_name, 256, FileFlags::default(), None) {
+        Err(ref err) if err.kind() == ErrorKind::InvalidInput => {}
+        Err(err) => panic!("unexpected {:?}", err),
+        Ok(_) => panic!("expected a rejected name"),
+    }
+    assert!(catalog.entries().is_empty());
+
+    // Nor are they added when closing a file created without the catalog.
+    let file = File::create(&long_name, 256, false).unwrap();
+    assert_eq!(catalog.close(file).unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert!(Catalog::open("catalog", 1024).unwrap().entries().is_empty());
+}
+
+#[test]
+fn test_catalog_create_records_the_file() {
+    let _fs = mount("catalog_recreate");
+    let mut catalog = Catalog::open("catalog", 1024).unwrap();
+    catalog.create("data", 256, FileFlags::default(), None).unwrap().close().unwrap();
+
+    // The file was replaced without the catalog.
+    File::remove("data").unwrap();
+    let flags = FileFlags { failsafe: true, ..FileFlags::default() };
+    catalog.create("data", 1024, flags, None).unwrap().close().unwrap();
+    let entry = Catalog::open("catalog", 1024).unwrap().get("data").unwrap().clone();
+    assert_eq!((entry.capacity, entry.flags), (1024, flags));
+
+    // An existing file keeps its size and flags.
+    catalog.create("data", 4096, FileFlags::default(), None).unwrap().close().unwrap();
+    let entry = catalog.get("data").unwrap();
+    assert_eq!((entry.capacity, entry.flags), (1024, flags));
+}
+
+#[test]
+fn test_catalog_gc_with_tokens() {
+    let _fs = mount("catalog_gc_tokens");
+    let mut catalog = Catalog::open("catalog", 1024).unwrap();
+    let flags = FileFlags {
+        failsafe: true,
+        secure: true,
+        no_signature_test: true,
+        ..FileFlags::default()
+    };
+    let mut file = catalog.create("key", 256, flags, None).unwrap();
+    let token = file.token();
+    file.write_all(b"secret").unwrap();
+    catalog.close(file).unwrap();
+    assert_eq!(catalog.get("key").unwrap().len, 6);
+
+    File::create_with_flags("key", 256, flags, Some(token)).unwrap().write_all(b"new").unwrap();
+    let tokens = |entry: &CatalogEntry| if entry.name == "key" { token } else { 0 };
+    assert_eq!(catalog.gc_with_tokens(tokens).unwrap(), 0);
+    let entry = catalog.get("key").unwrap();
+    assert_eq!((entry.len, entry.flags), (3, flags));
+}
+
+#[test]
+fn test_default_catalog() {
+    let _fs = mount("default_catalog");
+    Catalog::with_default(|catalog| {
+        let file = try!(catalog.create("data", 256, FileFlags::default(), None));
+        catalog.close(file)
+    }).unwrap();
+    assert!(Catalog::open_default().unwrap().get("data").is_some());
+
+    // A corrupted catalog is replaced.
+    File::create(CATALOG_FILE, 1024, true).unwrap().write_all(b"not a catalog").unwrap();
+    Catalog::with_default(|catalog| catalog.remove("data")).unwrap();
+    Catalog::with_default(|catalog| {
+        let file = try!(catalog.create("other", 256, FileFlags::default(), None));
+        catalog.close(file)
+    }).unwrap();
+    let names: Vec<String> = Catalog::open_default().unwrap()
+        .entries()
+        .iter()
+        .map(|entry| entry.name.clone())
+        .collect();
+    assert_eq!(names, ["other"]);
+}
+
+#[test]
+fn test_boot_info_factory_reset() {
+    let _fs = mount("boot_info_factory");
+    let boot_info = Update::get_boot_info().unwrap();
+    assert_eq!(boot_info.active_image, 0);
+    match boot_info.image_status {
+        ImageStatus::NOTEST => {}
+        _ => panic!("unexpected image status"),
+    }
+    // The active image, 3 padding bytes and the little endian status.
+    assert_eq!(read_all(IMG_BOOT_INFO), [0, 0, 0, 0, 0xba, 0xdc, 0xcd, 0xab]);
+}
+
+#[test]
+fn test_boot_info_decoding() {
+    let _fs = mount("boot_info_decoding");
+    File::create(IMG_BOOT_INFO, 8, false)
+        .unwrap()
+        .write(&[1, 0, 0, 0, 0x21, 0x43, 0x34, 0x12])
+        .unwrap();
+    let boot_info = Update::get_boot_info().unwrap();
+    assert_eq!(boot_info.active_image, 1);
+    match boot_info.image_status {
+        ImageStatus::TESTING => {}
+        _ => panic!("unexpected image status"),
+    }
+    assert_eq!(boot_info.next_image_filename(), IMG_USER_2);
+}
+
+#[test]
+fn test_next_image_replaces_small_file() {
+    let _fs = mount("next_image_small");
+    File::create(IMG_USER_1, 1000, false).unwrap().write(b"old").unwrap();
+    Update::next_image(8192).unwrap().write(&[0; 8192]).unwrap();
+    let info = File::get_info(IMG_USER_1).unwrap();
+    assert_eq!(info.file_length, 8192);
+    assert!(info.allocated_length >= 8192);
+    // The image is recorded in the default catalog.
+    let catalog = Catalog::open_default().unwrap();
+    assert_eq!(catalog.get(IMG_USER_1).unwrap().capacity, info.allocated_length as u64);
+}
+
+#[test]
+fn test_next_image_reuses_large_file() {
+    let _fs = mount("next_image_large");
+    File::create(IMG_USER_1, 16384, false).unwrap();
+    Update::next_image(4096).unwrap();
+    assert_eq!(File::get_info(IMG_USER_1).unwrap().allocated_length, 16384);
+}
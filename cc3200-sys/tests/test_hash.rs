@@ -0,0 +1,160 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Runs the software hashes of the main crate against known vectors, and the
+// file helpers against the simulated file system, like test_fs.rs.
+
+// Needs the simulated file system.
+#![cfg(feature = "host-sim")]
+#![feature(collections)]
+
+extern crate cc3200_sys;
+extern crate collections;
+// For the `core` paths of the included files.
+extern crate core;
+extern crate numeric_utils;
+
+
+
+mod common;
+
+use common::{error, io, mount, rtc, simplelink, time};
+
+#[allow(dead_code)]
+#[path = "../../src/hash.rs"]
+mod hash;
+
+use error::ErrorKind;
+use hash::{Algorithm, Crc32, Hasher, Hmac};
+use io::{File, Write};
+
+const QUICK_FOX: &'static [u8] = b"The quick brown fox jumps over the lazy dog";
+const TWO_BLOCKS: &'static [u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+
+fn digest(algorithm: Algorithm, data: &[u8]) -> String {
+    format!("{}", hash::digest(algorithm, data))
+}
+
+fn hmac(algorithm: Algorithm, key: &[u8], data: &[u8]) -> String {
+    format!("{}", hash::hmac(algorithm, key, data))
+}
+
+#[test]
+fn test_digest_vectors() {
+    assert_eq!(digest(Algorithm::Md5, b""), "d41d8cd98f00b204e9800998ecf8427e");
+    assert_eq!(digest(Algorithm::Md5, b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+    assert_eq!(digest(Algorithm::Md5, QUICK_FOX), "9e107d9d372bb6826bd81d3542a419d6");
+
+    assert_eq!(digest(Algorithm::Sha1, b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(digest(Algorithm::Sha1, b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(digest(Algorithm::Sha1, TWO_BLOCKS), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+
+    assert_eq!(digest(Algorithm::Sha224, b""),
+               "d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f");
+    assert_eq!(digest(Algorithm::Sha224, b"abc"),
+               "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7");
+
+    assert_eq!(digest(Algorithm::Sha256, b""),
+               "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    assert_eq!(digest(Algorithm::Sha256, b"abc"),
+               "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(digest(Algorithm::Sha256, TWO_BLOCKS),
+               "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+
+    assert_eq!(hash::digest(Algorithm::Sha224, b"abc").as_bytes().len(), 28);
+}
+
+#[test]
+fn test_hmac_vectors() {
+    // RFC 2202 and RFC 4231.
+    assert_eq!(hmac(Algorithm::Md5, &[0x0b; 16], b"Hi There"),
+               "9294727a3638bb1c13f48ef8158bfc9d");
+    assert_eq!(hmac(Algorithm::Sha1, &[0x0b; 20], b"Hi There"),
+               "b617318655057264e28bc0b6fb378c8ef146be00");
+    assert_eq!(hmac(Algorithm::Sha224, &[0x0b; 20], b"Hi There"),
+               "896fb1128abbdf196832107cd49df33f47b4b1169912ba4f53684b22");
+    assert_eq!(hmac(Algorithm::Sha256, b"Jefe", b"what do ya want for nothing?"),
+               "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    // Keys longer than a block are hashed first.
+    assert_eq!(hmac(Algorithm::Sha256,
+                    &[0xaa; 131],
+                    b"Test Using Larger Than Block-Size Key - Hash Key First"),
+               "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(hash::crc32(b""), 0);
+    assert_eq!(hash::crc32(b"123456789"), 0xcbf43926);
+    assert_eq!(hash::crc32(QUICK_FOX), 0x414fa339);
+
+    let mut crc = Crc32::new();
+    for chunk in QUICK_FOX.chunks(5) {
+        crc.update(chunk);
+    }
+    assert_eq!(crc.finish(), 0x414fa339);
+}
+
+#[test]
+fn test_streaming() {
+    // One million 'a', by uneven chunks.
+    let data = [b'a'; 999];
+    let mut hasher = Hasher::new(Algorithm::Sha256);
+    let mut len = 0;
+    while len < 1000000 {
+        let chunk = std::cmp::min(1000000 - len, data.len());
+        hasher.update(&data[..chunk]);
+        len += chunk;
+    }
+    assert_eq!(format!("{}", hasher.finish().unwrap()),
+               "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+
+    let mut hasher = Hasher::new(Algorithm::Sha1);
+    io::copy(&mut TWO_BLOCKS, &mut hasher).unwrap();
+    assert_eq!(hasher.finish().unwrap(), hash::digest(Algorithm::Sha1, TWO_BLOCKS));
+
+    let mut hmac = Hmac::new(Algorithm::Md5, b"key");
+    for chunk in QUICK_FOX.chunks(7) {
+        hmac.write_all(chunk).unwrap();
+    }
+    assert_eq!(format!("{}", hmac.finish().unwrap()), "80070713463e7749b90c2dc24911e275");
+}
+
+#[test]
+fn test_announced_length() {
+    let mut hasher = Hasher::with_len(Algorithm::Md5, QUICK_FOX.len());
+    hasher.update(&QUICK_FOX[..10]);
+    hasher.update(&QUICK_FOX[10..]);
+    assert_eq!(hasher.finish().unwrap(), hash::digest(Algorithm::Md5, QUICK_FOX));
+
+    let mut hasher = Hasher::with_len(Algorithm::Md5, 4);
+    hasher.update(b"abc");
+    let err = hasher.finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let mut hmac = Hmac::with_len(Algorithm::Sha256, b"key", 2);
+    hmac.update(b"abc");
+    assert_eq!(hmac.finish().unwrap_err().kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_files() {
+    let _fs = mount("files");
+    let mut data = Vec::new();
+    for i in 0..3000 {
+        data.push((i % 251) as u8);
+    }
+    let mut file = File::create("firmware.bin", 4096, false).unwrap();
+    file.write_all(&data).unwrap();
+    file.close().unwrap();
+
+    assert_eq!(hash::digest_file(Algorithm::Sha256, "firmware.bin").unwrap(),
+               hash::digest(Algorithm::Sha256, &data));
+    assert_eq!(hash::hmac_file(Algorithm::Sha1, b"secret", "firmware.bin").unwrap(),
+               hash::hmac(Algorithm::Sha1, b"secret", &data));
+    assert_eq!(hash::crc32_file("firmware.bin").unwrap(), hash::crc32(&data));
+
+    let err = hash::digest_file(Algorithm::Md5, "missing.bin").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,127 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Runs the log files of the main crate against the simulated file system,
+// like test_fs.rs.
+
+// Needs the simulated file system.
+#![cfg(feature = "host-sim")]
+#![feature(collections)]
+
+extern crate cc3200_sys;
+extern crate collections;
+// For the `core` paths of the included files.
+extern crate core;
+extern crate numeric_utils;
+
+use std::cmp;
+
+
+mod common;
+
+use common::{error, io, mount, rtc, simplelink, time};
+
+#[allow(dead_code)]
+#[path = "../../src/log_file.rs"]
+mod log_file;
+
+use io::{Catalog, File, Read, Write};
+use log_file::LogFiles;
+
+fn dump(log_files: &mut LogFiles) -> String {
+    let mut buf = Vec::new();
+    let len = log_files.copy_to(&mut buf).unwrap();
+    assert_eq!(len, buf.len() as u64);
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn test_records() {
+    let _fs = mount("records");
+    let mut log_files = LogFiles::open("log", 2, 256).unwrap();
+    assert!(log_files.file_names().unwrap().is_empty());
+    log_files.write_record(b"first\n").unwrap();
+    log_files.write_record(b"second\n").unwrap();
+    assert_eq!(log_files.file_names().unwrap(), ["log.0"]);
+    assert_eq!(dump(&mut log_files), "#log 00000000\nfirst\nsecond\n");
+
+    // Reading the files closed the current one.
+    log_files.write_record(b"third\n").unwrap();
+    assert_eq!(log_files.file_names().unwrap(), ["log.0", "log.1"]);
+    assert_eq!(dump(&mut log_files),
+               "#log 00000000\nfirst\nsecond\n#log 00000001\nthird\n");
+}
+
+#[test]
+fn test_files_are_cataloged() {
+    let _fs = mount("cataloged");
+    let mut log_files = LogFiles::open("log", 2, 256).unwrap();
+    log_files.write_record(b"first\n").unwrap();
+    log_files.rotate().unwrap();
+    let catalog = Catalog::open_default().unwrap();
+    let names: Vec<&str> = catalog.entries().iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, ["log.0", "log.1"]);
+    // The length is recorded when a file is closed.
+    assert_eq!(catalog.get("log.0").unwrap().len, 20);
+
+    log_files.clear().unwrap();
+    assert!(Catalog::open_default().unwrap().entries().is_empty());
+}
+
+#[test]
+fn test_rotation() {
+    let _fs = mount("rotation");
+    let mut log_files = LogFiles::open("log", 3, 256).unwrap();
+    // 14 bytes of header and 2 records of 100 bytes fit in a file.
+    for i in 0..7 {
+        let record: Vec<u8> = (0..100).map(|_| b'0' + i).collect();
+        log_files.write_record(&record).unwrap();
+    }
+    // The oldest file, with records 0 and 1, was reused.
+    assert_eq!(log_files.file_names().unwrap(), ["log.1", "log.2", "log.0"]);
+    let mut expected = Vec::new();
+    for &(sequence, first) in &[(1, b'2'), (2, b'4'), (3, b'6')] {
+        expected.extend_from_slice(format!("#log {:08x}\n", sequence).as_bytes());
+        for record in first..cmp::min(first + 2, b'7') {
+            expected.extend((0..100).map(|_| record));
+        }
+    }
+    assert_eq!(dump(&mut log_files).into_bytes(), expected);
+
+    // Records are truncated to the size of a file.
+    log_files.clear().unwrap();
+    log_files.write_record(&[b'x'; 300]).unwrap();
+    assert_eq!(dump(&mut log_files).len(), 256);
+}
+
+#[test]
+fn test_reopen_starts_new_file() {
+    let _fs = mount("reopen");
+    {
+        let mut log_files = LogFiles::open("log", 2, 256).unwrap();
+        log_files.write_record(b"before reboot\n").unwrap();
+    }
+    let mut log_files = LogFiles::open("log", 2, 256).unwrap();
+    log_files.write_record(b"after reboot\n").unwrap();
+    assert_eq!(dump(&mut log_files),
+               "#log 00000000\nbefore reboot\n#log 00000001\nafter reboot\n");
+
+    // Files without a valid header are ignored.
+    File::create("log.0", 256, false).unwrap().write_all(b"garbage").unwrap();
+    let mut log_files = LogFiles::open("log", 2, 256).unwrap();
+    assert_eq!(log_files.file_names().unwrap(), ["log.1"]);
+    log_files.write_record(b"next\n").unwrap();
+    assert_eq!(log_files.file_names().unwrap(), ["log.1", "log.0"]);
+}
+
+#[test]
+fn test_write_spans_files() {
+    let _fs = mount("write_spans_files");
+    let mut log_files = LogFiles::open("log", 2, 256).unwrap();
+    log_files.write_all(&[b'a'; 300]).unwrap();
+    assert_eq!(dump(&mut log_files).matches('a').count(), 300);
+    let mut text = String::new();
+    File::open("log.0").unwrap().read_to_string(&mut text).unwrap();
+    assert_eq!(text.len(), 256);
+}
@@ -0,0 +1,112 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate cc3200_sys;
+// For the `core` paths of net.rs.
+extern crate core;
+
+#[allow(dead_code)]
+#[path = "../../src/net.rs"]
+mod net;
+
+use net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+fn v6(s: &str) -> Ipv6Addr {
+    match Ipv6Addr::parse(s) {
+        Some(ip) => ip,
+        None => panic!("{} didn't parse", s),
+    }
+}
+
+#[test]
+fn test_ipv4_parse() {
+    assert_eq!(Ipv4Addr::parse("192.168.1.254"), Some(Ipv4Addr::new(192, 168, 1, 254)));
+    assert_eq!(Ipv4Addr::parse("0.0.0.0"), Some(Ipv4Addr::unspecified()));
+    for s in &["", "1.2.3", "1.2.3.4.5", "1..3.4", "256.1.1.1", "1.2.3.0004", "+1.2.3.4",
+               "1.+2.3.4", "-1.2.3.4", "1.2.3.a", " 1.2.3.4"] {
+        assert_eq!(Ipv4Addr::parse(s), None, "{}", s);
+    }
+}
+
+#[test]
+fn test_ipv6_parse() {
+    assert_eq!(v6("2001:db8:0:0:0:0:0:1"), Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+    assert_eq!(v6("2001:DB8::1"), Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+    assert_eq!(v6("::"), Ipv6Addr::unspecified());
+    assert!(v6("::1").is_loopback());
+    assert_eq!(v6("fe80::"), Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0));
+    assert_eq!(v6("1:2:3::6:7:8"), Ipv6Addr::new(1, 2, 3, 0, 0, 6, 7, 8));
+    // "::" may stand for a single group.
+    assert_eq!(v6("1:2:3:4:5:6:7::"), Ipv6Addr::new(1, 2, 3, 4, 5, 6, 7, 0));
+    assert_eq!(v6("::2:3:4:5:6:7:8"), Ipv6Addr::new(0, 2, 3, 4, 5, 6, 7, 8));
+}
+
+#[test]
+fn test_ipv6_parse_embedded_ipv4() {
+    let mapped = v6("::ffff:192.168.1.2");
+    assert_eq!(mapped, Ipv6Addr::new(0, 0, 0, 0, 0, 0xffff, 0xc0a8, 0x0102));
+    assert_eq!(mapped.to_ipv4_mapped(), Some(Ipv4Addr::new(192, 168, 1, 2)));
+    assert_eq!(v6("::1.2.3.4"), Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0x0102, 0x0304));
+    assert_eq!(v6("1:2:3:4:5:6:1.2.3.4"), Ipv6Addr::new(1, 2, 3, 4, 5, 6, 0x0102, 0x0304));
+    assert_eq!(v6("64:ff9b::10.0.0.1"), Ipv6Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0x0a00, 1));
+}
+
+#[test]
+fn test_ipv6_parse_invalid() {
+    for s in &["",
+               ":",
+               ":::",
+               "1::2::3",
+               "1:2:3:4:5:6:7",
+               "1:2:3:4:5:6:7:8:9",
+               "1:2:3:4:5:6:7:8::",
+               ":1:2:3:4:5:6:7",
+               "1:2:3:4:5:6:7:",
+               "12345::",
+               "g::",
+               "+1::",
+               "::+1",
+               "1:2:3:4:5:6:7:1.2.3.4",
+               "1:2:3:4:5:6::1.2.3.4",
+               "::1.2.3",
+               "::256.1.1.1",
+               "::1.2.3.4:5",
+               ":1.2.3.4",
+               "1.2.3.4",
+               "fe80::1%1"] {
+        assert_eq!(Ipv6Addr::parse(s), None, "{}", s);
+    }
+}
+
+#[test]
+fn test_ipv6_display() {
+    let cases = [("2001:db8::1", "2001:db8::1"),
+                 ("::", "::"),
+                 ("::1", "::1"),
+                 ("fe80::", "fe80::"),
+                 ("1:0:0:2:0:0:0:3", "1:0:0:2::3"),
+                 // The first of two runs of the same length is compressed.
+                 ("1:0:0:2:3:0:0:4", "1::2:3:0:0:4"),
+                 // A single zero group is never compressed.
+                 ("1:2:3:4:5:6:0:8", "1:2:3:4:5:6:0:8"),
+                 ("2001:DB8:0:0:0:0:0:ABCD", "2001:db8::abcd"),
+                 ("::ffff:192.168.1.2", "::ffff:c0a8:102")];
+    for &(s, expected) in cases.iter() {
+        assert_eq!(format!("{}", v6(s)), expected);
+        assert_eq!(v6(expected), v6(s));
+    }
+}
+
+#[test]
+fn test_ip_parse() {
+    assert_eq!(IpAddr::parse("10.0.0.1"), Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))));
+    assert_eq!(IpAddr::parse("::1"), Some(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))));
+    assert_eq!(IpAddr::parse("::ffff:10.0.0.1"),
+               Some(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0xffff, 0x0a00, 1))));
+    assert_eq!(IpAddr::parse("+10.0.0.1"), None);
+    assert_eq!(IpAddr::parse("10.0.0.1:80"), None);
+    assert_eq!(IpAddr::parse("example.com"), None);
+    assert_eq!(format!("{}", IpAddr::parse("10.0.0.1").unwrap()), "10.0.0.1");
+    assert_eq!(format!("{}", IpAddr::parse("2001:db8::1").unwrap()), "2001:db8::1");
+}
@@ -0,0 +1,29 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate cc3200_sys;
+
+use cc3200_sys::simplelink::{OsiError, RxFilterId, SimpleLinkError, WlanRxFilterOpBuf};
+
+#[test]
+fn test_filter_mask() {
+    let buf = WlanRxFilterOpBuf::from_filters(&[RxFilterId(0), RxFilterId(9), RxFilterId(127)])
+        .unwrap();
+    let mut expected = [0; 16];
+    expected[0] = 0x80;
+    expected[1] = 0x40;
+    expected[15] = 0x01;
+    assert_eq!(buf.mask, expected);
+}
+
+#[test]
+fn test_invalid_filter_id() {
+    let mut buf = WlanRxFilterOpBuf::no_filters();
+    match buf.add_filter(RxFilterId(-1)) {
+        Err(SimpleLinkError::Osi(OsiError::OSI_INVALID_PARAMS)) => {}
+        result => panic!("unexpected {:?}", result),
+    }
+    assert_eq!(buf.mask, [0; 16]);
+    assert!(WlanRxFilterOpBuf::from_filters(&[RxFilterId(3), RxFilterId(-128)]).is_err());
+}
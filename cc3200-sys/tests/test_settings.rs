@@ -0,0 +1,226 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Runs the settings store of the main crate against the simulated file
+// system, like test_fs.rs.
+
+// Needs the simulated file system.
+#![cfg(feature = "host-sim")]
+#![feature(collections)]
+
+extern crate cc3200_sys;
+extern crate collections;
+// For the `core` paths of the included files.
+extern crate core;
+extern crate numeric_utils;
+
+
+
+mod common;
+
+use common::{error, io, mount, rtc, simplelink, time};
+
+#[allow(dead_code)]
+#[path = "../../src/hash.rs"]
+mod hash;
+#[allow(dead_code)]
+#[path = "../../src/settings.rs"]
+mod settings;
+
+use io::{Catalog, File, Read, Write};
+use error::{DataError, Error, ErrorKind};
+use settings::{Migration, Settings, Value, WIFI_KEY_FILE};
+
+fn expect_error<T>(result: Result<T, Error>, kind: ErrorKind, description: &str) {
+    match result {
+        Err(ref err) if err.kind() == kind && err.description() == description => {}
+        Err(err) => panic!("unexpected {:?}", err),
+        Ok(_) => panic!("expected {}", description),
+    }
+}
+
+fn expect_data_error<T>(result: Result<T, Error>, expected: DataError) {
+    match result {
+        Err(ref err) if err.data_error() == Some(expected) => {}
+        Err(err) => panic!("unexpected {:?}", err),
+        Ok(_) => panic!("expected {:?}", expected),
+    }
+}
+
+fn expect_corrupted(result: Result<Settings, Error>) {
+    expect_data_error(result, DataError::Corrupted);
+}
+
+fn expect_too_large(result: Result<(), Error>) {
+    expect_data_error(result, DataError::TooLarge);
+}
+
+#[test]
+fn test_missing_file_is_empty() {
+    let _fs = mount("missing");
+    let settings = Settings::open("settings", 1024).unwrap();
+    assert_eq!(settings.version(), 0);
+    assert_eq!(settings.keys().count(), 0);
+    assert_eq!(settings.get_str_or("ssid", "default"), "default");
+    assert_eq!(settings.get_int_or("port", 80), 80);
+    assert_eq!(settings.get_bool_or("dhcp", true), true);
+    assert_eq!(settings.get_blob_or("key", b"none"), b"none");
+}
+
+#[test]
+fn test_save_and_open() {
+    let _fs = mount("save_and_open");
+    let mut settings = Settings::new("settings", 1024);
+    settings.set_str("wifi.ssid", "OpenWireless.org").unwrap();
+    settings.set_int("ota.port", -8080).unwrap();
+    settings.set_bool("ota.enabled", true).unwrap();
+    settings.set_blob("device.key", &[0, 1, 2, 0xff]).unwrap();
+    settings.save().unwrap();
+
+    let settings = Settings::open("settings", 1024).unwrap();
+    assert_eq!(settings.get_str("wifi.ssid"), Some("OpenWireless.org"));
+    assert_eq!(settings.get_int("ota.port"), Some(-8080));
+    assert_eq!(settings.get_bool("ota.enabled"), Some(true));
+    assert_eq!(settings.get_blob("device.key"), Some(&[0, 1, 2, 0xff][..]));
+    assert_eq!(settings.keys().collect::<Vec<_>>(),
+               ["wifi.ssid", "ota.port", "ota.enabled", "device.key"]);
+}
+
+#[test]
+fn test_set_replaces_and_remove() {
+    let _fs = mount("replace");
+    let mut settings = Settings::new("settings", 1024);
+    settings.set_int("value", 1).unwrap();
+    settings.set_str("value", "one").unwrap();
+    // The typed getters don't convert.
+    assert_eq!(settings.get_int("value"), None);
+    assert_eq!(settings.get("value"), Some(&Value::Str(String::from("one"))));
+    assert_eq!(settings.remove("value"), Some(Value::Str(String::from("one"))));
+    assert!(!settings.contains("value"));
+    assert_eq!(settings.remove("value"), None);
+}
+
+#[test]
+fn test_limits() {
+    let _fs = mount("limits");
+    let mut settings = Settings::new("settings", 256);
+    expect_too_large(settings.set_str("", "empty key"));
+    let long_key: String = (0..256).map(|_| 'k').collect();
+    expect_too_large(settings.set_int(&long_key, 0));
+
+    settings.set_blob("blob", &[0; 300]).unwrap();
+    expect_too_large(settings.save());
+}
+
+#[test]
+fn test_saved_settings_are_cataloged() {
+    let _fs = mount("cataloged");
+    Settings::new("settings", 1024).save().unwrap();
+    let catalog = Catalog::open_default().unwrap();
+    let entry = catalog.get("settings").unwrap();
+    assert!(entry.flags.failsafe);
+    assert_eq!(entry.len, File::get_info("settings").unwrap().file_length as u64);
+}
+
+#[test]
+fn test_corruption_is_detected() {
+    let _fs = mount("corruption");
+    let mut settings = Settings::new("settings", 1024);
+    settings.set_str("wifi.ssid", "home").unwrap();
+    settings.save().unwrap();
+
+    let mut buf = Vec::new();
+    File::open("settings").unwrap().read_to_end(&mut buf).unwrap();
+    let last = buf.len() - 1;
+    buf[last] ^= 1;
+    File::create("settings", 1024, true).unwrap().write_all(&buf).unwrap();
+    expect_corrupted(Settings::open("settings", 1024));
+
+    File::create("settings", 1024, true).unwrap().write_all(b"not settings").unwrap();
+    expect_corrupted(Settings::open("settings", 1024));
+
+    // Corrupted settings can be replaced.
+    Settings::new("settings", 1024).save().unwrap();
+    assert_eq!(Settings::open("settings", 1024).unwrap().keys().count(), 0);
+}
+
+fn add_ssid(settings: &mut Settings) -> Result<(), Error> {
+    settings.set_str("ssid", "home")
+}
+
+fn rename_ssid(settings: &mut Settings) -> Result<(), Error> {
+    if let Some(Value::Str(ssid)) = settings.remove("ssid") {
+        try!(settings.set_str("wifi.ssid", &ssid));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_migrations() {
+    let _fs = mount("migrations");
+    let mut settings = Settings::open("settings", 1024).unwrap();
+    assert!(settings.migrate(&[add_ssid as Migration]).unwrap());
+    assert_eq!(settings.version(), 1);
+
+    let mut settings = Settings::open("settings", 1024).unwrap();
+    assert_eq!(settings.version(), 1);
+    assert_eq!(settings.get_str("ssid"), Some("home"));
+    assert!(!settings.migrate(&[add_ssid as Migration]).unwrap());
+
+    // Only the new migration runs.
+    let migrations = [add_ssid as Migration, rename_ssid];
+    assert!(settings.migrate(&migrations).unwrap());
+    let mut settings = Settings::open("settings", 1024).unwrap();
+    assert_eq!(settings.version(), 2);
+    assert_eq!(settings.get_str("ssid"), None);
+    assert_eq!(settings.get_str("wifi.ssid"), Some("home"));
+
+    // Older firmware can't use the settings of a newer one.
+    expect_data_error(settings.migrate(&[add_ssid as Migration]),
+                      DataError::UnsupportedVersion(2));
+    assert_eq!(settings.version(), 2);
+}
+
+fn fail(_: &mut Settings) -> Result<(), Error> {
+    Err(Error::new(ErrorKind::Io, "migration failed"))
+}
+
+#[test]
+fn test_failed_migration_keeps_the_version() {
+    let _fs = mount("failed_migration");
+    let mut settings = Settings::open("settings", 1024).unwrap();
+    expect_error(settings.migrate(&[add_ssid as Migration, fail]),
+                 ErrorKind::Io,
+                 "migration failed");
+    assert_eq!(settings.version(), 0);
+    assert_eq!(settings.get_str("ssid"), None);
+    assert_eq!(Settings::open("settings", 1024).unwrap().version(), 0);
+
+    assert!(settings.migrate(&[add_ssid as Migration]).unwrap());
+    assert_eq!(settings.version(), 1);
+}
+
+#[test]
+fn test_wifi_key_is_secure() {
+    let _fs = mount("wifi_key");
+    let mut settings = Settings::open_or_default();
+    assert_eq!(settings.wifi_credentials("default").unwrap(), ("default", None));
+
+    settings.set_str("wifi.ssid", "home").unwrap();
+    Settings::set_wifi_key(Some("passphrase")).unwrap();
+    assert_eq!(settings.wifi_credentials("default").unwrap(),
+               ("home", Some(String::from("passphrase"))));
+    assert!(File::get_info(WIFI_KEY_FILE).map(|info| io::FileFlags::from_bits(info.flags).secure)
+        .unwrap());
+    // The key isn't kept with the other settings.
+    assert_eq!(settings.keys().collect::<Vec<_>>(), ["wifi.ssid"]);
+
+    let long_key: String = (0..65).map(|_| 'k').collect();
+    assert_eq!(Settings::set_wifi_key(Some(&long_key)).unwrap_err().kind(),
+               ErrorKind::InvalidInput);
+    assert_eq!(Settings::wifi_key().unwrap(), Some(String::from("passphrase")));
+
+    Settings::set_wifi_key(None).unwrap();
+    assert_eq!(Settings::wifi_key().unwrap(), None);
+}
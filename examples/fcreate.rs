@@ -26,24 +26,14 @@ extern crate log;
 extern crate collections;
 
 use cc3200::cc3200::{Board};
-use cc3200::simplelink::{FileSystemError, SimpleLink, SimpleLinkError};
+use cc3200::error::Error;
+use cc3200::simplelink::{FileSystemError, SimpleLink, SimpleLinkSession};
 use cc3200::io::{File};
 
 use freertos_rs::{Task};
 
 static VERSION: &'static str = "1.0";
 
-#[derive(Debug)]
-pub enum Error {
-    SLE(SimpleLinkError),
-}
-
-impl From<SimpleLinkError> for Error {
-    fn from(err: SimpleLinkError) -> Error {
-        Error::SLE(err)
-    }
-}
-
 macro_rules! ignore {
     ($e:expr) => ({
         match $e {
@@ -56,8 +46,7 @@ macro_rules! ignore {
 fn fcreate_demo() -> Result<(), Error> {
 
     try!(SimpleLink::start_spawn_task());
-    SimpleLink::init_app_variables();
-    try!(SimpleLink::start());
+    let _session = try!(SimpleLinkSession::start());
 
     let filename = "myfile.txt";
 
@@ -86,7 +75,7 @@ fn fcreate_demo() -> Result<(), Error> {
         if lbound == ubound {
             // cannot legally happen in practice
             println!("Recursion bug detected");
-            return Err(Error::from(SimpleLinkError::FileSystem(FileSystemError::UNKNOWN)))
+            return Err(Error::from(FileSystemError::UNKNOWN))
         }
     }
 
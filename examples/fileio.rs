@@ -26,8 +26,9 @@ extern crate log;
 extern crate collections;
 
 use cc3200::cc3200::{Board};
-use cc3200::simplelink::{SimpleLink, SimpleLinkError};
-use cc3200::io::{File, Read, Seek, Write};
+use cc3200::error::Error;
+use cc3200::simplelink::{SimpleLink, SimpleLinkSession};
+use cc3200::io::{File, Read, Seek, SeekFrom, Write};
 
 use freertos_rs::{Task};
 
@@ -35,17 +36,6 @@ use collections::String;
 
 static VERSION: &'static str = "1.0";
 
-#[derive(Debug)]
-pub enum Error {
-    SLE(SimpleLinkError),
-}
-
-impl From<SimpleLinkError> for Error {
-    fn from(err: SimpleLinkError) -> Error {
-        Error::SLE(err)
-    }
-}
-
 macro_rules! ignore {
     ($e:expr) => ({
         match $e {
@@ -58,8 +48,7 @@ macro_rules! ignore {
 fn fileio_demo() -> Result<(), Error> {
 
     try!(SimpleLink::start_spawn_task());
-    SimpleLink::init_app_variables();
-    try!(SimpleLink::start());
+    let _session = try!(SimpleLinkSession::start());
 
     let filename = "myfile.txt";
     let test_string = "Hello world";
@@ -98,7 +87,7 @@ fn fileio_demo() -> Result<(), Error> {
 
     println!("Read string \"{}\"", str);
 
-    try!(file.seek(6));
+    try!(file.seek(SeekFrom::Start(6)));
     try!(file.read_to_string(&mut str)); // "world"
 
     println!("Read string \"{}\" at offset 6", str);
@@ -26,10 +26,11 @@ extern crate log;
 extern crate collections;
 
 use cc3200::cc3200::{Board, LedEnum, LedName, Update};
+use cc3200::error::{Error, ErrorKind};
 use cc3200::io::File;
-use cc3200::simplelink::{self, NetConfigSet, Policy, SimpleLink, SimpleLinkError, WlanConfig,
-                         WlanMode, WlanRxFilterOp, WlanRxFilterOpBuf};
+use cc3200::simplelink::{RestoreOptions, SimpleLink, SimpleLinkSession, SlSecParams, WlanMode};
 use cc3200::simplelink::IMG_BOOT_INFO;
+use cc3200::settings::Settings;
 use core::str;
 
 use freertos_rs::{CurrentTask, Duration, Task};
@@ -38,35 +39,6 @@ mod config;
 
 static VERSION: &'static str = "1.0";
 
-#[derive(Debug)]
-#[allow(non_camel_case_types)]
-pub enum AppError {
-    DEVICE_NOT_IN_STATION_MODE,
-    PING_FAILED,
-    INTERNET_CONNECTION_FAILED,
-    LAN_CONNECTION_FAILED,
-    INVALID_IMAGE_SIZE,
-    IMAGE_DOWNLOAD_FAILED,
-}
-
-#[derive(Debug)]
-pub enum Error {
-    SLE(SimpleLinkError),
-    App(AppError),
-}
-
-impl From<SimpleLinkError> for Error {
-    fn from(err: SimpleLinkError) -> Error {
-        Error::SLE(err)
-    }
-}
-
-impl From<AppError> for Error {
-    fn from(err: AppError) -> Error {
-        Error::App(err)
-    }
-}
-
 macro_rules! ignore {
     ($e:expr) => ({
         match $e {
@@ -76,97 +48,19 @@ macro_rules! ignore {
     })
 }
 
-fn configure_simple_link_to_default() -> Result<(), Error> {
-    let mode = SimpleLink::start()?;
-    if mode != WlanMode::ROLE_STA {
-        if mode == WlanMode::ROLE_AP {
-            // If the device is in AP mode, then we need to wait for the
-            // acquired event before doing anything.
-
-            while !SimpleLink::is_ip_acquired() {
-                CurrentTask::delay(Duration::ms(100));
-            }
-        }
-
-        // Switch to STA mode and restart
-
-        SimpleLink::wlan_set_mode(WlanMode::ROLE_STA)?;
-        SimpleLink::stop(255)?;
-        let mode = SimpleLink::start()?;
-        if mode != WlanMode::ROLE_STA {
-            return Err(Error::App(AppError::DEVICE_NOT_IN_STATION_MODE));
-        }
-    }
-
-    // Get the device's version-information
-    let ver = SimpleLink::get_version();
-
-    println!("Host Driver Version: {}", SimpleLink::get_driver_version());
-    println!("Build Version {}.{}.{}.{}.31.{}.{}.{}.{}.{}.{}.{}.{}",
-             ver.nwp_version[0],
-             ver.nwp_version[1],
-             ver.nwp_version[2],
-             ver.nwp_version[3],
-             ver.fw_version[0],
-             ver.fw_version[1],
-             ver.fw_version[2],
-             ver.fw_version[3],
-             ver.phy_version[0],
-             ver.phy_version[1],
-             ver.phy_version[2],
-             ver.phy_version[3]);
-
-    // Set connection policy to Auto + SmartConfig
-    //      (Device's default connection policy)
-    SimpleLink::wlan_set_policy(Policy::ConnectionDefault, &[])?;
-
-    // Remove all profiles
-    SimpleLink::wlan_delete_profile(0xff)?;
-
-    // Device is in station mode. Disconnect previous connection, if any.
-    if SimpleLink::wlan_disconnect().is_ok() {
-        // This means that we were previously connected. Wait for the
-        // notification event.
-        while !SimpleLink::is_connected() {
-            CurrentTask::delay(Duration::ms(100));
-        }
-    }
-
-    // Enable DHCP client
-    SimpleLink::netcfg_set(NetConfigSet::Ipv4StaP2pClientDhcpEnable, &[1])?;
-
-    // Disable Scan
-    SimpleLink::wlan_set_policy(Policy::ScanDisable, &[])?;
-
-    // Set Tx power level for station mode
-    // Number between 0-15, as dB offset from max power - 0 will set max power
-
-    SimpleLink::wlan_set(WlanConfig::GeneralStaTxPower, &[0])?;
-
-    // Set PM policy to normal
-    SimpleLink::wlan_set_policy(Policy::PowerNormal, &[])?;
-
-    // Unregister mDNS services
-    SimpleLink::netapp_mdns_unregister_service("")?;
-
-    // Remove  all 64 filters (8*8)
-
-    let all_filters = WlanRxFilterOpBuf::all_filters();
-    SimpleLink::wlan_rx_filter(WlanRxFilterOp::Remove, &all_filters)?;
-
-    SimpleLink::stop(simplelink::SL_STOP_TIMEOUT)?;
-
-    SimpleLink::init_app_variables();
-    Ok(())
-}
-
 fn wlan_connect() -> Result<(), Error> {
 
-    let sec_params = config::security_params();
+    // Settings saved on the device override the defaults of config.rs.
+    let settings = Settings::open_or_default();
+    let (ssid, key) = try!(settings.wifi_credentials(config::SSID));
+    let sec_params = match key {
+        Some(ref key) => Some(SlSecParams::wpa2(key)),
+        None => config::security_params(),
+    };
 
-    SimpleLink::wlan_connect(config::SSID, &[], sec_params, None)?;
+    SimpleLink::wlan_connect(ssid, &[], sec_params, None)?;
 
-    println!("Connecting to {} ...", config::SSID);
+    println!("Connecting to {} ...", ssid);
     // Wait for WLAN event
     while !SimpleLink::is_connected() || !SimpleLink::is_ip_acquired() {
         // Toggle LEDs to indicate Connection Progress
@@ -190,20 +84,20 @@ fn complete_board_update() -> Result<(), Error> {
     Ok(())
 }
 
-fn wlan_station_mode() -> Result<(), Error> {
+fn wlan_station_mode() -> Result<SimpleLinkSession, Error> {
     SimpleLink::init_app_variables();
 
-    configure_simple_link_to_default()?;
-    let mode = SimpleLink::start()?;
-    if mode != WlanMode::ROLE_STA {
-        return Err(Error::App(AppError::DEVICE_NOT_IN_STATION_MODE));
+    SimpleLink::restore_defaults(&RestoreOptions::default())?;
+    let session = SimpleLinkSession::start()?;
+    if session.mode() != WlanMode::ROLE_STA {
+        return Err(Error::new(ErrorKind::Device, "device not in station mode"));
     }
     println!("Device started as STATION");
 
     wlan_connect()?;
 
     println!("Connection established w/ AP and IP is acquired");
-    Ok(())
+    Ok(session)
 }
 
 fn full_ota_mcuimga() -> Result<(), Error> {
@@ -211,12 +105,12 @@ fn full_ota_mcuimga() -> Result<(), Error> {
     Board::led_configure(&[LedEnum::LED1]);
 
     SimpleLink::start_spawn_task()?;
-    wlan_station_mode()?;
+    let session = wlan_station_mode()?;
 
     complete_board_update()?;
 
     // Power off the network processor.
-    SimpleLink::stop(simplelink::SL_STOP_TIMEOUT)?;
+    session.stop()?;
     Ok(())
 }
 
@@ -26,9 +26,10 @@ extern crate log;
 extern crate collections;
 
 use cc3200::cc3200::{Board, LedEnum, LedName, Update};
-use cc3200::io::{File, Read, Write};
-use cc3200::simplelink::{self, NetConfigSet, Policy, SimpleLink, SimpleLinkError, WlanConfig,
-                         WlanMode, WlanRxFilterOp, WlanRxFilterOpBuf};
+use cc3200::error::{Error, ErrorKind};
+use cc3200::io::{Catalog, File, FileFlags, Read, Write};
+use cc3200::simplelink::{RestoreOptions, SimpleLink, SimpleLinkSession, SlSecParams, WlanMode};
+use cc3200::settings::Settings;
 use cc3200::socket_channel::SocketChannel;
 use collections::{String, Vec};
 use core::str;
@@ -45,35 +46,6 @@ static VERSION: &'static str = "1.0";
 
 static UPDATE_IMG: &'static str = "/update/mcuimg.bin";
 
-#[derive(Debug)]
-#[allow(non_camel_case_types)]
-pub enum AppError {
-    DEVICE_NOT_IN_STATION_MODE,
-    PING_FAILED,
-    INTERNET_CONNECTION_FAILED,
-    LAN_CONNECTION_FAILED,
-    INVALID_IMAGE_SIZE,
-    IMAGE_DOWNLOAD_FAILED,
-}
-
-#[derive(Debug)]
-pub enum Error {
-    SLE(SimpleLinkError),
-    App(AppError),
-}
-
-impl From<SimpleLinkError> for Error {
-    fn from(err: SimpleLinkError) -> Error {
-        Error::SLE(err)
-    }
-}
-
-impl From<AppError> for Error {
-    fn from(err: AppError) -> Error {
-        Error::App(err)
-    }
-}
-
 macro_rules! ignore {
     ($e:expr) => ({
         match $e {
@@ -82,97 +54,19 @@ macro_rules! ignore {
     })
 }
 
-fn configure_simple_link_to_default() -> Result<(), Error> {
-    let mode = SimpleLink::start()?;
-    if mode != WlanMode::ROLE_STA {
-        if mode == WlanMode::ROLE_AP {
-            // If the device is in AP mode, then we need to wait for the
-            // acquired event before doing anything.
-
-            while !SimpleLink::is_ip_acquired() {
-                CurrentTask::delay(Duration::ms(100));
-            }
-        }
-
-        // Switch to STA mode and restart
-
-        SimpleLink::wlan_set_mode(WlanMode::ROLE_STA)?;
-        SimpleLink::stop(255)?;
-        let mode = SimpleLink::start()?;
-        if mode != WlanMode::ROLE_STA {
-            return Err(Error::App(AppError::DEVICE_NOT_IN_STATION_MODE));
-        }
-    }
-
-    // Get the device's version-information
-    let ver = SimpleLink::get_version();
-
-    println!("Host Driver Version: {}", SimpleLink::get_driver_version());
-    println!("Build Version {}.{}.{}.{}.31.{}.{}.{}.{}.{}.{}.{}.{}",
-             ver.nwp_version[0],
-             ver.nwp_version[1],
-             ver.nwp_version[2],
-             ver.nwp_version[3],
-             ver.fw_version[0],
-             ver.fw_version[1],
-             ver.fw_version[2],
-             ver.fw_version[3],
-             ver.phy_version[0],
-             ver.phy_version[1],
-             ver.phy_version[2],
-             ver.phy_version[3]);
-
-    // Set connection policy to Auto + SmartConfig
-    //      (Device's default connection policy)
-    SimpleLink::wlan_set_policy(Policy::ConnectionDefault, &[])?;
-
-    // Remove all profiles
-    SimpleLink::wlan_delete_profile(0xff)?;
-
-    // Device is in station mode. Disconnect previous connection, if any.
-    if SimpleLink::wlan_disconnect().is_ok() {
-        // This means that we were previously connected. Wait for the
-        // notification event.
-        while !SimpleLink::is_connected() {
-            CurrentTask::delay(Duration::ms(100));
-        }
-    }
-
-    // Enable DHCP client
-    SimpleLink::netcfg_set(NetConfigSet::Ipv4StaP2pClientDhcpEnable, &[1])?;
-
-    // Disable Scan
-    SimpleLink::wlan_set_policy(Policy::ScanDisable, &[])?;
-
-    // Set Tx power level for station mode
-    // Number between 0-15, as dB offset from max power - 0 will set max power
-
-    SimpleLink::wlan_set(WlanConfig::GeneralStaTxPower, &[0])?;
-
-    // Set PM policy to normal
-    SimpleLink::wlan_set_policy(Policy::PowerNormal, &[])?;
-
-    // Unregister mDNS services
-    SimpleLink::netapp_mdns_unregister_service("")?;
-
-    // Remove  all 64 filters (8*8)
-
-    let all_filters = WlanRxFilterOpBuf::all_filters();
-    SimpleLink::wlan_rx_filter(WlanRxFilterOp::Remove, &all_filters)?;
-
-    SimpleLink::stop(simplelink::SL_STOP_TIMEOUT)?;
-
-    SimpleLink::init_app_variables();
-    Ok(())
-}
-
 fn wlan_connect() -> Result<(), Error> {
 
-    let sec_params = config::security_params();
+    // Settings saved on the device override the defaults of config.rs.
+    let settings = Settings::open_or_default();
+    let (ssid, key) = try!(settings.wifi_credentials(config::SSID));
+    let sec_params = match key {
+        Some(ref key) => Some(SlSecParams::wpa2(key)),
+        None => config::security_params(),
+    };
 
-    SimpleLink::wlan_connect(config::SSID, &[], sec_params, None)?;
+    SimpleLink::wlan_connect(ssid, &[], sec_params, None)?;
 
-    println!("Connecting to {} ...", config::SSID);
+    println!("Connecting to {} ...", ssid);
     // Wait for WLAN event
     while !SimpleLink::is_connected() || !SimpleLink::is_ip_acquired() {
         // Toggle LEDs to indicate Connection Progress
@@ -194,7 +88,7 @@ fn content_length(headers: &Vec<(HttpHeader, String)>) -> Result<usize, Error> {
                         return Ok(res);
                     },
                     Err(_) => {
-                        return Err(Error::App(AppError::INVALID_IMAGE_SIZE));
+                        return Err(Error::new(ErrorKind::InvalidInput, "invalid image size"));
                     }
                 };
             }
@@ -203,11 +97,11 @@ fn content_length(headers: &Vec<(HttpHeader, String)>) -> Result<usize, Error> {
             }
         }
     }
-    return Err(Error::App(AppError::INVALID_IMAGE_SIZE));
+    return Err(Error::new(ErrorKind::InvalidInput, "invalid image size"));
 }
 
 fn get_update(filename: &str, url: &str) -> Result<(), Error> {
-    let mut client = Client::new(SocketChannel::new().unwrap());
+    let mut client = Client::new(SocketChannel::new()?);
     let response = client.get(url)
         .open()
         .unwrap()
@@ -219,7 +113,10 @@ fn get_update(filename: &str, url: &str) -> Result<(), Error> {
     println!("Received URL {}, downloading update...", url);
 
     let file_length = content_length(&response.headers)?;
-    let mut file = File::create(filename, file_length, true)?;
+    let flags = FileFlags { failsafe: true, ..FileFlags::default() };
+    let mut file = Catalog::with_default(|catalog| {
+        catalog.create(filename, file_length, flags, None)
+    })?;
 
     let mut buf = [0u8; 64];
     let buflen = buf.len();
@@ -232,13 +129,13 @@ fn get_update(filename: &str, url: &str) -> Result<(), Error> {
                 if err == ChannelError::EndOfStream {
                     break;
                 } else {
-                    return Err(Error::App(AppError::IMAGE_DOWNLOAD_FAILED));
+                    return Err(Error::from(err));
                 }
             },
         }
     }
 
-    Ok(())
+    Catalog::with_default(|catalog| catalog.close(file))
 }
 
 fn apply_update(filename: &str) -> Result<(), Error> {
@@ -270,6 +167,7 @@ fn apply_update(filename: &str) -> Result<(), Error> {
         len += image.write(&buf[0..buflen])?;
     }
 
+    Catalog::with_default(|catalog| catalog.close(image))?;
     println!("Wrote {} bytes to image.", len);
 
     // 4) Test
@@ -280,34 +178,36 @@ fn apply_update(filename: &str) -> Result<(), Error> {
 
 fn update_board() -> Result<(), Error> {
 
-    ignore!(File::remove(UPDATE_IMG));
+    ignore!(Catalog::with_default(|catalog| catalog.remove(UPDATE_IMG)));
 
-    if let Err(err) = get_update(UPDATE_IMG, FULL_OTA_URL) {
-        ignore!(File::remove(UPDATE_IMG));
+    let settings = Settings::open_or_default();
+    let url = settings.get_str_or("ota.url", FULL_OTA_URL);
+    if let Err(err) = get_update(UPDATE_IMG, url) {
+        ignore!(Catalog::with_default(|catalog| catalog.remove(UPDATE_IMG)));
         return Err(err);
     }
 
     apply_update(UPDATE_IMG)?;
-    ignore!(File::remove(UPDATE_IMG));
+    ignore!(Catalog::with_default(|catalog| catalog.remove(UPDATE_IMG)));
 
     println!("Press RESET to run updated image...");
     Ok(())
 }
 
-fn wlan_station_mode() -> Result<(), Error> {
+fn wlan_station_mode() -> Result<SimpleLinkSession, Error> {
     SimpleLink::init_app_variables();
 
-    configure_simple_link_to_default()?;
-    let mode = SimpleLink::start()?;
-    if mode != WlanMode::ROLE_STA {
-        return Err(Error::App(AppError::DEVICE_NOT_IN_STATION_MODE));
+    SimpleLink::restore_defaults(&RestoreOptions::default())?;
+    let session = SimpleLinkSession::start()?;
+    if session.mode() != WlanMode::ROLE_STA {
+        return Err(Error::new(ErrorKind::Device, "device not in station mode"));
     }
     println!("Device started as STATION");
 
     wlan_connect()?;
 
     println!("Connection established w/ AP and IP is acquired");
-    Ok(())
+    Ok(session)
 }
 
 fn full_ota_demo() -> Result<(), Error> {
@@ -315,7 +215,7 @@ fn full_ota_demo() -> Result<(), Error> {
     Board::led_configure(&[LedEnum::LED1]);
 
     SimpleLink::start_spawn_task()?;
-    wlan_station_mode()?;
+    let session = wlan_station_mode()?;
 
     // Commit ourselves to be the active image; this will
     // ensure that image #2 will be selected for testing.
@@ -324,7 +224,7 @@ fn full_ota_demo() -> Result<(), Error> {
     update_board()?;
 
     // Power off the network processor.
-    SimpleLink::stop(simplelink::SL_STOP_TIMEOUT)?;
+    session.stop()?;
     Ok(())
 }
 
@@ -26,8 +26,9 @@ extern crate log;
 extern crate collections;
 
 use cc3200::cc3200::{Board, LedEnum, LedName};
-use cc3200::simplelink::{self, NetConfigSet, Policy, SimpleLink, SimpleLinkError, WlanConfig,
-                         WlanMode, WlanRxFilterOp, WlanRxFilterOpBuf};
+use cc3200::error::{Error, ErrorKind};
+use cc3200::simplelink::{RestoreOptions, SimpleLink, SimpleLinkSession, SlSecParams, WlanMode};
+use cc3200::settings::Settings;
 use cc3200::socket_channel::SocketChannel;
 
 use freertos_rs::{CurrentTask, Duration, Task};
@@ -38,124 +39,19 @@ mod config;
 
 static VERSION: &'static str = "1.0";
 
-#[derive(Debug)]
-#[allow(non_camel_case_types)]
-pub enum AppError {
-    DEVICE_NOT_IN_STATION_MODE,
-    PING_FAILED,
-    INTERNET_CONNECTION_FAILED,
-    LAN_CONNECTION_FAILED,
-}
-
-#[derive(Debug)]
-pub enum Error {
-    SLE(SimpleLinkError),
-    App(AppError),
-}
-
-impl From<SimpleLinkError> for Error {
-    fn from(err: SimpleLinkError) -> Error {
-        Error::SLE(err)
-    }
-}
-
-impl From<AppError> for Error {
-    fn from(err: AppError) -> Error {
-        Error::App(err)
-    }
-}
-
-fn configure_simple_link_to_default() -> Result<(), Error> {
-    let mode = try!(SimpleLink::start());
-    if mode != WlanMode::ROLE_STA {
-        if mode == WlanMode::ROLE_AP {
-            // If the device is in AP mode, then we need to wait for the
-            // acquired event before doing anything.
-
-            while !SimpleLink::is_ip_acquired() {
-                CurrentTask::delay(Duration::ms(100));
-            }
-        }
-
-        // Switch to STA mode and restart
-
-        try!(SimpleLink::wlan_set_mode(WlanMode::ROLE_STA));
-        try!(SimpleLink::stop(255));
-        let mode = try!(SimpleLink::start());
-        if mode != WlanMode::ROLE_STA {
-            return Err(Error::App(AppError::DEVICE_NOT_IN_STATION_MODE));
-        }
-    }
-
-    // Get the device's version-information
-    let ver = SimpleLink::get_version();
-
-    info!("Host Driver Version: {}", SimpleLink::get_driver_version());
-    info!("Build Version {}.{}.{}.{}.31.{}.{}.{}.{}.{}.{}.{}.{}",
-          ver.nwp_version[0],
-          ver.nwp_version[1],
-          ver.nwp_version[2],
-          ver.nwp_version[3],
-          ver.fw_version[0],
-          ver.fw_version[1],
-          ver.fw_version[2],
-          ver.fw_version[3],
-          ver.phy_version[0],
-          ver.phy_version[1],
-          ver.phy_version[2],
-          ver.phy_version[3]);
-
-    // Set connection policy to Auto + SmartConfig
-    //      (Device's default connection policy)
-    try!(SimpleLink::wlan_set_policy(Policy::ConnectionDefault, &[]));
-
-    // Remove all profiles
-    try!(SimpleLink::wlan_delete_profile(0xff));
-
-    // Device is in station mode. Disconnect previous connection, if any.
-    if SimpleLink::wlan_disconnect().is_ok() {
-        // This means that we were previously connected. Wait for the
-        // notification event.
-        while !SimpleLink::is_connected() {
-            CurrentTask::delay(Duration::ms(100));
-        }
-    }
-
-    // Enable DHCP client
-    try!(SimpleLink::netcfg_set(NetConfigSet::Ipv4StaP2pClientDhcpEnable, &[1]));
-
-    // Disable Scan
-    try!(SimpleLink::wlan_set_policy(Policy::ScanDisable, &[]));
-
-    // Set Tx power level for station mode
-    // Number between 0-15, as dB offset from max power - 0 will set max power
-
-    try!(SimpleLink::wlan_set(WlanConfig::GeneralStaTxPower, &[0]));
-
-    // Set PM policy to normal
-    try!(SimpleLink::wlan_set_policy(Policy::PowerNormal, &[]));
-
-    // Unregister mDNS services
-    try!(SimpleLink::netapp_mdns_unregister_service(""));
-
-    // Remove  all 64 filters (8*8)
-
-    let all_filters = WlanRxFilterOpBuf::all_filters();
-    try!(SimpleLink::wlan_rx_filter(WlanRxFilterOp::Remove, &all_filters));
-
-    try!(SimpleLink::stop(simplelink::SL_STOP_TIMEOUT));
-
-    SimpleLink::init_app_variables();
-    Ok(())
-}
-
 fn wlan_connect() -> Result<(), Error> {
 
-    let sec_params = config::security_params();
+    // Settings saved on the device override the defaults of config.rs.
+    let settings = Settings::open_or_default();
+    let (ssid, key) = try!(settings.wifi_credentials(config::SSID));
+    let sec_params = match key {
+        Some(ref key) => Some(SlSecParams::wpa2(key)),
+        None => config::security_params(),
+    };
 
-    try!(SimpleLink::wlan_connect(config::SSID, &[], sec_params, None));
+    try!(SimpleLink::wlan_connect(ssid, &[], sec_params, None));
 
-    info!("Connecting to {} ...", config::SSID);
+    info!("Connecting to {} ...", ssid);
     // Wait for WLAN event
     while !SimpleLink::is_connected() || !SimpleLink::is_ip_acquired() {
         // Toggle LEDs to indicate Connection Progress
@@ -167,13 +63,13 @@ fn wlan_connect() -> Result<(), Error> {
     Ok(())
 }
 
-fn wlan_station_mode() -> Result<(), Error> {
+fn wlan_station_mode() -> Result<SimpleLinkSession, Error> {
     SimpleLink::init_app_variables();
 
-    try!(configure_simple_link_to_default());
-    let mode = try!(SimpleLink::start());
-    if mode != WlanMode::ROLE_STA {
-        return Err(Error::App(AppError::DEVICE_NOT_IN_STATION_MODE));
+    try!(SimpleLink::restore_defaults(&RestoreOptions::default()));
+    let session = try!(SimpleLinkSession::start());
+    if session.mode() != WlanMode::ROLE_STA {
+        return Err(Error::new(ErrorKind::Device, "device not in station mode"));
     }
     info!("Device started as STATION");
 
@@ -182,7 +78,7 @@ fn wlan_station_mode() -> Result<(), Error> {
     info!("Connection established w/ AP and IP is aquired");
 
     loop {
-        let mut client = Client::new(SocketChannel::new().unwrap());
+        let mut client = Client::new(try!(SocketChannel::new()));
         let response = client.get("http://firefox.com/")
             .open()
             .unwrap()
@@ -194,7 +90,7 @@ fn wlan_station_mode() -> Result<(), Error> {
         CurrentTask::delay(Duration::ms(1000))
     }
 
-    Ok(())
+    Ok(session)
 }
 
 fn http_demo() -> Result<(), Error> {
@@ -202,10 +98,10 @@ fn http_demo() -> Result<(), Error> {
     Board::led_configure(&[LedEnum::LED1]);
 
     try!(SimpleLink::start_spawn_task());
-    try!(wlan_station_mode());
+    let session = try!(wlan_station_mode());
 
     // Power off the network processor.
-    try!(SimpleLink::stop(simplelink::SL_STOP_TIMEOUT));
+    try!(session.stop());
     Ok(())
 }
 
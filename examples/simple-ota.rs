@@ -26,9 +26,10 @@ extern crate log;
 extern crate collections;
 
 use cc3200::cc3200::{Board, LedEnum, LedName, Update};
-use cc3200::io::{File, Read, Write};
-use cc3200::simplelink::{self, NetConfigSet, Policy, SimpleLink, SimpleLinkError, WlanConfig,
-                         WlanMode, WlanRxFilterOp, WlanRxFilterOpBuf};
+use cc3200::error::{Error, ErrorKind};
+use cc3200::io::{Catalog, File, FileFlags, Read, Write};
+use cc3200::simplelink::{RestoreOptions, SimpleLink, SimpleLinkSession, SlSecParams, WlanMode};
+use cc3200::settings::Settings;
 use cc3200::socket_channel::SocketChannel;
 use collections::{String, Vec};
 use core::str;
@@ -43,35 +44,6 @@ use config::SIMPLE_OTA_URL;
 
 static VERSION: &'static str = "1.0";
 
-#[derive(Debug)]
-#[allow(non_camel_case_types)]
-pub enum AppError {
-    DEVICE_NOT_IN_STATION_MODE,
-    PING_FAILED,
-    INTERNET_CONNECTION_FAILED,
-    LAN_CONNECTION_FAILED,
-    INVALID_IMAGE_SIZE,
-    IMAGE_DOWNLOAD_FAILED,
-}
-
-#[derive(Debug)]
-pub enum Error {
-    SLE(SimpleLinkError),
-    App(AppError),
-}
-
-impl From<SimpleLinkError> for Error {
-    fn from(err: SimpleLinkError) -> Error {
-        Error::SLE(err)
-    }
-}
-
-impl From<AppError> for Error {
-    fn from(err: AppError) -> Error {
-        Error::App(err)
-    }
-}
-
 macro_rules! ignore {
     ($e:expr) => ({
         match $e {
@@ -80,97 +52,19 @@ macro_rules! ignore {
     })
 }
 
-fn configure_simple_link_to_default() -> Result<(), Error> {
-    let mode = SimpleLink::start()?;
-    if mode != WlanMode::ROLE_STA {
-        if mode == WlanMode::ROLE_AP {
-            // If the device is in AP mode, then we need to wait for the
-            // acquired event before doing anything.
-
-            while !SimpleLink::is_ip_acquired() {
-                CurrentTask::delay(Duration::ms(100));
-            }
-        }
-
-        // Switch to STA mode and restart
-
-        SimpleLink::wlan_set_mode(WlanMode::ROLE_STA)?;
-        SimpleLink::stop(255)?;
-        let mode = SimpleLink::start()?;
-        if mode != WlanMode::ROLE_STA {
-            return Err(Error::App(AppError::DEVICE_NOT_IN_STATION_MODE));
-        }
-    }
-
-    // Get the device's version-information
-    let ver = SimpleLink::get_version();
-
-    println!("Host Driver Version: {}", SimpleLink::get_driver_version());
-    println!("Build Version {}.{}.{}.{}.31.{}.{}.{}.{}.{}.{}.{}.{}",
-             ver.nwp_version[0],
-             ver.nwp_version[1],
-             ver.nwp_version[2],
-             ver.nwp_version[3],
-             ver.fw_version[0],
-             ver.fw_version[1],
-             ver.fw_version[2],
-             ver.fw_version[3],
-             ver.phy_version[0],
-             ver.phy_version[1],
-             ver.phy_version[2],
-             ver.phy_version[3]);
-
-    // Set connection policy to Auto + SmartConfig
-    //      (Device's default connection policy)
-    SimpleLink::wlan_set_policy(Policy::ConnectionDefault, &[])?;
-
-    // Remove all profiles
-    SimpleLink::wlan_delete_profile(0xff)?;
-
-    // Device is in station mode. Disconnect previous connection, if any.
-    if SimpleLink::wlan_disconnect().is_ok() {
-        // This means that we were previously connected. Wait for the
-        // notification event.
-        while !SimpleLink::is_connected() {
-            CurrentTask::delay(Duration::ms(100));
-        }
-    }
-
-    // Enable DHCP client
-    SimpleLink::netcfg_set(NetConfigSet::Ipv4StaP2pClientDhcpEnable, &[1])?;
-
-    // Disable Scan
-    SimpleLink::wlan_set_policy(Policy::ScanDisable, &[])?;
-
-    // Set Tx power level for station mode
-    // Number between 0-15, as dB offset from max power - 0 will set max power
-
-    SimpleLink::wlan_set(WlanConfig::GeneralStaTxPower, &[0])?;
-
-    // Set PM policy to normal
-    SimpleLink::wlan_set_policy(Policy::PowerNormal, &[])?;
-
-    // Unregister mDNS services
-    SimpleLink::netapp_mdns_unregister_service("")?;
-
-    // Remove  all 64 filters (8*8)
-
-    let all_filters = WlanRxFilterOpBuf::all_filters();
-    SimpleLink::wlan_rx_filter(WlanRxFilterOp::Remove, &all_filters)?;
-
-    SimpleLink::stop(simplelink::SL_STOP_TIMEOUT)?;
-
-    SimpleLink::init_app_variables();
-    Ok(())
-}
-
 fn wlan_connect() -> Result<(), Error> {
 
-    let sec_params = config::security_params();
+    // Settings saved on the device override the defaults of config.rs.
+    let settings = Settings::open_or_default();
+    let (ssid, key) = try!(settings.wifi_credentials(config::SSID));
+    let sec_params = match key {
+        Some(ref key) => Some(SlSecParams::wpa2(key)),
+        None => config::security_params(),
+    };
 
-    SimpleLink::wlan_connect(config::SSID, &[], sec_params, None)?;
+    SimpleLink::wlan_connect(ssid, &[], sec_params, None)?;
 
-    println!("Connecting to {} ...", config::SSID);
+    println!("Connecting to {} ...", ssid);
     // Wait for WLAN event
     while !SimpleLink::is_connected() || !SimpleLink::is_ip_acquired() {
         // Toggle LEDs to indicate Connection Progress
@@ -192,7 +86,7 @@ fn content_length(headers: &Vec<(HttpHeader, String)>) -> Result<usize, Error> {
                         return Ok(res);
                     },
                     Err(_) => {
-                        return Err(Error::App(AppError::INVALID_IMAGE_SIZE));
+                        return Err(Error::new(ErrorKind::InvalidInput, "invalid image size"));
                     }
                 };
             }
@@ -201,11 +95,11 @@ fn content_length(headers: &Vec<(HttpHeader, String)>) -> Result<usize, Error> {
             }
         }
     }
-    return Err(Error::App(AppError::INVALID_IMAGE_SIZE));
+    return Err(Error::new(ErrorKind::InvalidInput, "invalid image size"));
 }
 
 fn get_update(filename: &str, url: &str) -> Result<(), Error> {
-    let mut client = Client::new(SocketChannel::new().unwrap());
+    let mut client = Client::new(SocketChannel::new()?);
     let response = client.get(url)
         .open()
         .unwrap()
@@ -217,7 +111,10 @@ fn get_update(filename: &str, url: &str) -> Result<(), Error> {
     println!("Received URL {}, downloading update...", url);
 
     let file_length = content_length(&response.headers)?;
-    let mut file = File::create(filename, file_length, true)?;
+    let flags = FileFlags { failsafe: true, ..FileFlags::default() };
+    let mut file = Catalog::with_default(|catalog| {
+        catalog.create(filename, file_length, flags, None)
+    })?;
 
     let mut buf = [0u8; 64];
     let buflen = buf.len();
@@ -230,13 +127,13 @@ fn get_update(filename: &str, url: &str) -> Result<(), Error> {
                 if err == ChannelError::EndOfStream {
                     break;
                 } else {
-                    return Err(Error::App(AppError::IMAGE_DOWNLOAD_FAILED));
+                    return Err(Error::from(err));
                 }
             },
         }
     }
 
-    Ok(())
+    Catalog::with_default(|catalog| catalog.close(file))
 }
 
 fn apply_update(filename: &str, imagename: &str) -> Result<(), Error> {
@@ -246,7 +143,9 @@ fn apply_update(filename: &str, imagename: &str) -> Result<(), Error> {
     println!("Found update {} of {} bytes.", filename, info.file_length);
 
     // 2) Open image file for writing
-    let mut image = File::create(imagename, info.file_length as usize, false)?;
+    let mut image = Catalog::with_default(|catalog| {
+        catalog.create(imagename, info.file_length as usize, FileFlags::default(), None)
+    })?;
 
     // 3) Copy update to image
 
@@ -268,6 +167,7 @@ fn apply_update(filename: &str, imagename: &str) -> Result<(), Error> {
         len += image.write(&buf[0..buflen])?;
     }
 
+    Catalog::with_default(|catalog| catalog.close(image))?;
     println!("Wrote {} bytes to {}.", len, imagename);
 
     // 4) Commit
@@ -281,10 +181,12 @@ fn update_board() -> Result<(), Error> {
     let imagename = "/sys/mcuimg.bin";
     let filename = "/update/mcuimg.bin";
 
-    ignore!(File::remove(filename));
+    ignore!(Catalog::with_default(|catalog| catalog.remove(filename)));
 
-    if let Err(err) = get_update(filename, SIMPLE_OTA_URL) {
-        ignore!(File::remove(filename));
+    let settings = Settings::open_or_default();
+    let url = settings.get_str_or("ota.url", SIMPLE_OTA_URL);
+    if let Err(err) = get_update(filename, url) {
+        ignore!(Catalog::with_default(|catalog| catalog.remove(filename)));
         return Err(err);
     };
 
@@ -294,20 +196,20 @@ fn update_board() -> Result<(), Error> {
     Ok(())
 }
 
-fn wlan_station_mode() -> Result<(), Error> {
+fn wlan_station_mode() -> Result<SimpleLinkSession, Error> {
     SimpleLink::init_app_variables();
 
-    configure_simple_link_to_default()?;
-    let mode = SimpleLink::start()?;
-    if mode != WlanMode::ROLE_STA {
-        return Err(Error::App(AppError::DEVICE_NOT_IN_STATION_MODE));
+    SimpleLink::restore_defaults(&RestoreOptions::default())?;
+    let session = SimpleLinkSession::start()?;
+    if session.mode() != WlanMode::ROLE_STA {
+        return Err(Error::new(ErrorKind::Device, "device not in station mode"));
     }
     println!("Device started as STATION");
 
     wlan_connect()?;
 
     println!("Connection established w/ AP and IP is acquired");
-    Ok(())
+    Ok(session)
 }
 
 fn http_demo() -> Result<(), Error> {
@@ -315,12 +217,12 @@ fn http_demo() -> Result<(), Error> {
     Board::led_configure(&[LedEnum::LED1]);
 
     SimpleLink::start_spawn_task()?;
-    wlan_station_mode()?;
+    let session = wlan_station_mode()?;
 
     update_board()?;
 
     // Power off the network processor.
-    SimpleLink::stop(simplelink::SL_STOP_TIMEOUT)?;
+    session.stop()?;
     Ok(())
 }
 
@@ -26,24 +26,14 @@ extern crate log;
 extern crate collections;
 
 use cc3200::cc3200::{Board, Update};
-use cc3200::simplelink::{SimpleLink, SimpleLinkError};
-use cc3200::io::{File, Read, Write};
+use cc3200::error::Error;
+use cc3200::simplelink::{SimpleLink, SimpleLinkSession};
+use cc3200::io::{Catalog, File, FileFlags, Read, Write};
 
 use freertos_rs::{Task};
 
 static VERSION: &'static str = "1.0";
 
-#[derive(Debug)]
-pub enum Error {
-    SLE(SimpleLinkError),
-}
-
-impl From<SimpleLinkError> for Error {
-    fn from(err: SimpleLinkError) -> Error {
-        Error::SLE(err)
-    }
-}
-
 macro_rules! ignore {
     ($e:expr) => ({
         match $e {
@@ -55,8 +45,7 @@ macro_rules! ignore {
 fn update_demo() -> Result<(), Error> {
 
     SimpleLink::start_spawn_task()?;
-    SimpleLink::init_app_variables();
-    SimpleLink::start()?;
+    let _session = SimpleLinkSession::start()?;
 
     let imagename = "/sys/mcuimg.bin";
     let filename = "/update/mcuimg.bin";
@@ -69,7 +58,9 @@ fn update_demo() -> Result<(), Error> {
 
     // 2) Open image file for writing
 
-    let mut image = File::create(imagename, info.file_length as usize, false)?;
+    let mut image = Catalog::with_default(|catalog| {
+        catalog.create(imagename, info.file_length as usize, FileFlags::default(), None)
+    })?;
 
     // 3) Copy update to image
 
@@ -95,6 +86,7 @@ fn update_demo() -> Result<(), Error> {
 
         println!("Wrote {} bytes to {}.", len, imagename);
     }
+    Catalog::with_default(|catalog| catalog.close(image))?;
 
     // 4) Commit
 
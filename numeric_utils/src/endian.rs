@@ -0,0 +1,22 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Little-endian integers of 1 to 8 bytes, as stored in the files.
+
+/// Writes the `buf.len()` low bytes of `value` to `buf`, least significant
+/// first.
+pub fn write_le(buf: &mut [u8], value: u64) {
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = (value >> (8 * i)) as u8;
+    }
+}
+
+/// Reads the little-endian integer of `buf.len()` bytes.
+pub fn read_le(buf: &[u8]) -> u64 {
+    let mut value = 0;
+    for (i, byte) in buf.iter().enumerate() {
+        value |= (*byte as u64) << (8 * i);
+    }
+    value
+}
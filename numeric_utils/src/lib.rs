@@ -9,3 +9,6 @@ pub use parse::*;
 
 pub mod format;
 pub use format::*;
+
+pub mod endian;
+pub use endian::*;
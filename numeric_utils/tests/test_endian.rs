@@ -0,0 +1,32 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate numeric_utils;
+
+use numeric_utils::{read_le, write_le};
+
+#[test]
+fn test_round_trip() {
+    let mut buf = [0; 8];
+    write_le(&mut buf[..2], 0x1234);
+    assert_eq!(&buf[..2], &[0x34, 0x12]);
+    assert_eq!(read_le(&buf[..2]), 0x1234);
+
+    write_le(&mut buf[..4], 0xdeadbeef);
+    assert_eq!(&buf[..4], &[0xef, 0xbe, 0xad, 0xde]);
+    assert_eq!(read_le(&buf[..4]), 0xdeadbeef);
+
+    write_le(&mut buf, 0x0102030405060708);
+    assert_eq!(buf, [8, 7, 6, 5, 4, 3, 2, 1]);
+    assert_eq!(read_le(&buf), 0x0102030405060708);
+}
+
+#[test]
+fn test_truncation() {
+    // Only the low bytes fit.
+    let mut buf = [0; 2];
+    write_le(&mut buf, 0xabcdef);
+    assert_eq!(read_le(&buf), 0xcdef);
+    assert_eq!(read_le(&[]), 0);
+}
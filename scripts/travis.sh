@@ -24,4 +24,5 @@ for example_file in examples/*.rs; do
     fi
 done
 
-cd numeric_utils && cargo test
+(cd numeric_utils && cargo test)
+(cd cc3200-sys && cargo test --features host-sim)
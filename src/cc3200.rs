@@ -10,18 +10,12 @@ use core::result::Result;
 use self::cc3200_sys::{board_init, GPIO_IF_LedConfigure, GPIO_IF_LedOn, GPIO_IF_LedOff,
                        MAP_UtilsDelay, I2C_IF_Open, I2C_IF_Close, I2C_IF_Write, I2C_IF_Read,
                        I2C_IF_ReadFrom};
-use self::cc3200_sys::simplelink::*;
+use error::{Error, ErrorKind};
+use io;
 use logger::SimpleLogger;
 use rtc::RTC;
-use io::{File, Read, Write};
 
-macro_rules! ignore {
-    ($e:expr) => ({
-        match $e {
-            _ => { },
-        }
-    })
-}
+pub use update::{BootInfo, ImageStatus, Update};
 
 #[allow(non_camel_case_types, dead_code)]
 pub enum LedName {
@@ -142,6 +136,19 @@ impl core::fmt::Write for Console {
     }
 }
 
+// Lets io::copy dump files, eg. the log files, on the console.
+impl io::Write for Console {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        for byte in buf {
+            let ascii_char = if *byte > 0x7f { b'?' } else { *byte };
+            unsafe {
+                cc3200_sys::console_putchar(ascii_char as i8);
+            }
+        }
+        Ok(buf.len())
+    }
+}
+
 pub enum I2COpenMode {
     MasterModeStd = 0,
     MasterModeFst = 1,
@@ -154,15 +161,18 @@ static I2C_SUCCESS: i32 = 0;
 pub struct I2C { }
 
 impl I2C {
-    pub fn open(mode: I2COpenMode) -> Option<Self> {
+    pub fn open(mode: I2COpenMode) -> Result<Self, Error> {
         unsafe {
             // Only allow one instance to be created in a given open mode.
-            if I2C_IS_OPEN || I2C_IF_Open(mode as u32) != I2C_SUCCESS {
-                return None;
+            if I2C_IS_OPEN {
+                return Err(Error::new(ErrorKind::Device, "I2C is already open"));
+            }
+            if I2C_IF_Open(mode as u32) != I2C_SUCCESS {
+                return Err(i2c_error());
             }
             I2C_IS_OPEN = true;
         }
-        return Some(I2C {});
+        return Ok(I2C {});
     }
 
     pub fn close(&self) {
@@ -173,28 +183,28 @@ impl I2C {
         }
     }
 
-    pub fn write(&self, addr: u8, data: &[u8], stop: u8) -> Result<(), ()> {
+    pub fn write(&self, addr: u8, data: &[u8], stop: u8) -> Result<(), Error> {
         if data.len() > 255 {
-            return Err(());
+            return Err(i2c_too_long());
         }
         unsafe {
             if I2C_IF_Write(addr, data.as_ptr() as *mut u8, data.len() as u8, stop) == I2C_SUCCESS {
                 return Ok(());
             }
         }
-        return Err(());
+        return Err(i2c_error());
     }
 
-    pub fn read(&self, addr: u8, data: &mut [u8]) -> Result<(), ()> {
+    pub fn read(&self, addr: u8, data: &mut [u8]) -> Result<(), Error> {
         if data.len() > 255 {
-            return Err(());
+            return Err(i2c_too_long());
         }
         unsafe {
             if I2C_IF_Read(addr, data.as_ptr() as *mut u8, data.len() as u8) == I2C_SUCCESS {
                 return Ok(());
             }
         }
-        return Err(());
+        return Err(i2c_error());
     }
 
     pub fn read_from_with_length(&self,
@@ -202,9 +212,9 @@ impl I2C {
                                  wr_data: &[u8],
                                  rd_data: &mut [u8],
                                  rd_len: u8)
-                                 -> Result<(), ()> {
+                                 -> Result<(), Error> {
         if wr_data.len() > 255 || rd_data.len() > 255 {
-            return Err(());
+            return Err(i2c_too_long());
         }
         unsafe {
             if I2C_IF_ReadFrom(addr,
@@ -215,235 +225,23 @@ impl I2C {
                 return Ok(());
             }
         }
-        return Err(());
+        return Err(i2c_error());
     }
 
-    pub fn read_from(&self, addr: u8, wr_data: &[u8], rd_data: &mut [u8]) -> Result<(), ()> {
+    pub fn read_from(&self, addr: u8, wr_data: &[u8], rd_data: &mut [u8]) -> Result<(), Error> {
         let len = rd_data.len() as u8;
         self.read_from_with_length(addr, wr_data, rd_data, len)
     }
 }
 
-//
-// Image updates
-//
-
-#[derive(Copy, Clone)]
-pub enum ImageStatus {
-    TESTING,
-    TESTREADY,
-    NOTEST
+// The I2C driver only reports failures, eg. a missing acknowledgement.
+fn i2c_error() -> Error {
+    Error::new(ErrorKind::Device, "I2C transfer failed")
 }
 
-impl ImageStatus {
-    pub fn from_u32(value: u32) -> Result<ImageStatus, ()> {
-        let image_status = match value {
-            IMG_STATUS_TESTING   => ImageStatus::TESTING,
-            IMG_STATUS_TESTREADY => ImageStatus::TESTREADY,
-            IMG_STATUS_NOTEST    => ImageStatus::NOTEST,
-            value => {
-                println!("unknwon image status {}", value);
-                return Err(());
-            }
-        };
-        Ok(image_status)
-    }
-    pub fn to_u32(image_status: ImageStatus) -> Result<u32, ()> {
-        let value = match image_status {
-            ImageStatus::NOTEST    => IMG_STATUS_NOTEST,
-            ImageStatus::TESTREADY => IMG_STATUS_TESTREADY,
-            ImageStatus::TESTING   => IMG_STATUS_TESTING
-        };
-        Ok(value)
-    }
-}
-
-pub struct BootInfo {
-    pub active_image: u8,
-    pub image_status: ImageStatus,
-}
-
-impl BootInfo {
-
-    /// Create a BootInfo structure
-    pub fn new(active_image: u8, image_status: ImageStatus) -> BootInfo {
-        BootInfo { active_image: active_image,
-                   image_status: image_status }
-    }
-
-    /// Create a BootInfo structure the represents the factory reset
-    pub fn factory_reset() -> BootInfo {
-        BootInfo::new(0, ImageStatus::NOTEST)
-    }
-
-    /// Returns the currently active image's filename
-    pub fn image_filename(&self) -> &'static str {
-        match self.active_image {
-            0 => IMG_FACTORY_DEFAULT,
-            1 => IMG_USER_1,
-            2 => IMG_USER_2,
-            _ => IMG_FACTORY_DEFAULT
-        }
-    }
-
-    /// Returns the filename of the image to update
-    pub fn next_image_filename(&self) -> &'static str {
-        // If we booted from image 1, we update image 2; in any other case
-        // we update image 1. This mirrors the behavior of the TI SDK.
-        match self.active_image {
-            1 => IMG_USER_2,
-            _ => IMG_USER_1
-        }
-    }
-}
-
-pub struct Update { }
-
-impl Update {
-
-    /// Sets the application to test mode
-    pub fn test() -> bool {
-        let res = unsafe {
-            sl_extlib_FlcTest(FLC_TEST_RESET_MCU |
-                              FLC_TEST_RESET_MCU_WITH_APP)
-        };
-        Update::reset_is_required(res)
-    }
-
-    /// Returns true if the application runs in testing mode, false otherwise
-    pub fn is_testing() -> bool {
-        let res = unsafe {
-            sl_extlib_FlcIsPendingCommit()
-        };
-        res != 0
-    }
-
-    /// Commits all changes, moving the application state from 'testing' to 'stable'
-    pub fn commit() -> bool {
-        let res = unsafe {
-            sl_extlib_FlcCommit(FLC_COMMITED)
-        };
-        Update::reset_is_required(res)
-    }
-
-    /// Aborts the update
-    pub fn abort() -> bool {
-        let res = unsafe {
-            sl_extlib_FlcCommit(FLC_NOT_COMMITED)
-        };
-        Update::reset_is_required(res)
-    }
-
-    /// Reads or creates the device's boot-info structure
-    pub fn get_boot_info() -> Result<BootInfo, ()> {
-        let boot_info = match Update::read_boot_info() {
-            Ok(boot_info) => boot_info,
-            Err(_) => {
-                let boot_info = BootInfo::factory_reset();
-                match Update::write_boot_info(&boot_info) {
-                    Ok(_)  => { },
-                    Err(_) => { return Err(()); }
-                };
-                boot_info
-            }
-        };
-        Ok(boot_info)
-    }
-
-    /// Opens the next image for updating; the filename is selected automatically
-    pub fn next_image(max_len: usize) -> Result<File, SimpleLinkError> {
-        // The TI filesystem doesn't resize files dynamically. Hence, if
-        // the image file already exists and is too small, we cannot use
-        // it for the update.
-        //
-        // Below, we read the boot-info file to get next image's filename and
-        // its size. We remove the existing file if it's too small. The latter
-        // call to File::create() will create a new file.
-        let file_name = match Update::get_boot_info() {
-            Ok(boot_info) => {
-                let file_name = boot_info.next_image_filename();
-                match File::get_info(file_name) {
-                    Ok(file_info) => {
-                        let allocated_length = file_info.allocated_length as usize;
-                        if allocated_length < max_len {
-                            File::remove(file_name)?;
-                        }
-                    },
-                    Err(_) => {
-                        // No file info available; unconditionally remove
-                        // file if it exists.
-                        ignore!(File::remove(file_name));
-                    }
-                };
-                file_name
-            },
-            Err(_) => {
-                return Result::Err(SimpleLinkError::FileSystem(FileSystemError::NOT_SUPPORTED))
-            }
-        };
-        File::create(file_name, max_len, false)
-    }
-
-    fn reset_is_required(flags: i32) -> bool {
-        (flags & (FLC_TEST_RESET_MCU | FLC_TEST_RESET_NWP)) != 0
-    }
-
-    fn encode_boot_info(boot_info: &BootInfo) -> Result<[u8; 8], ()> {
-        let active_image = boot_info.active_image;
-        let image_status = ImageStatus::to_u32(boot_info.image_status)?;
-        let mut buf: [u8; 8] = [0; 8];
-        buf[0] = active_image;
-        buf[4] = 0xff & (image_status) as u8;
-        buf[5] = 0xff & (image_status >> 8) as u8;
-        buf[6] = 0xff & (image_status >> 16) as u8;
-        buf[7] = 0xff & (image_status >> 24) as u8;
-        Ok(buf)
-    }
-
-    fn decode_boot_info(buf: [u8; 8]) -> Result<BootInfo, ()> {
-        let active_image = buf[0];
-        let image_status = ((buf[4] as u32)) |
-                           ((buf[5] as u32) << 8) |
-                           ((buf[6] as u32) << 16) |
-                           ((buf[7] as u32) << 24);
-        Ok(BootInfo::new(active_image, ImageStatus::from_u32(image_status)?))
-    }
-
-    fn write_boot_info(boot_info: &BootInfo) -> Result<(), ()> {
-
-        let buf = Update::encode_boot_info(boot_info)?;
-
-        let mut file = match File::create(IMG_BOOT_INFO, 8, false) {
-            Ok(file) => file,
-            Err(_)   => { return Err(()); }
-        };
-        match file.write(&buf[..]) {
-            Ok(buflen) => {
-                if buflen < buf.len() {
-                    return Err(());
-                }
-            },
-            Err(_) => { return Err(()); }
-        }
-        Ok(())
-    }
-
-    fn read_boot_info() -> Result<BootInfo, ()> {
-        let mut buf: [u8; 8] = [0; 8];
-        let mut file = match File::open(IMG_BOOT_INFO) {
-            Ok(file) => file,
-            Err(_)   => return Err(())
-        };
-        match file.read(&mut buf) {
-            Ok(buflen) => {
-                if buflen != buf.len() {
-                    return Err(());
-                }
-            },
-            Err(_) => { return Err(()); }
-        };
-        Update::decode_boot_info(buf)
-    }
+// A transfer holds at most 255 bytes.
+fn i2c_too_long() -> Error {
+    Error::new(ErrorKind::InvalidInput, "I2C transfer is too long")
 }
 
 pub struct Utils { }
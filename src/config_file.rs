@@ -0,0 +1,367 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Config files, in a subset of INI and TOML:
+//
+//   # Comments start with '#' or ';'.
+//   name = "sensor"
+//
+//   [wifi]
+//   ssid = "OpenWireless.org"
+//   retries = 3
+//   timeout = 2.5
+//   dhcp = true
+//   key = unquoted values are strings
+//
+// Keys hold letters, digits, '_', '-' and '.'. Quoted strings accept the
+// \" \\ \n \t and \r escapes, and may be followed by a comment; unquoted
+// values end at a '#' or a ';' and can't start with a quote. Floats must be
+// finite. The keys before the first section header belong to the section
+// "".
+//
+// The file is read one line at a time, so only the values are kept in
+// memory. Saving it writes the values back, without the comments.
+
+use collections::{String, Vec};
+
+use error::{DataError, Error, ErrorKind};
+use io::{BufRead, BufReader, File, Read, Write};
+use numeric_utils::parse_f64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+pub struct Section {
+    name: String,
+    entries: Vec<(String, Value)>,
+}
+
+impl Section {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the keys and values, in the order of the file.
+    pub fn entries(&self) -> &[(String, Value)] {
+        &self.entries
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|entry| entry.0 == key).map(|entry| &entry.1)
+    }
+}
+
+pub struct ConfigFile {
+    sections: Vec<Section>,
+}
+
+impl ConfigFile {
+    pub fn new() -> ConfigFile {
+        ConfigFile { sections: Vec::new() }
+    }
+
+    /// Parses the config read from `reader`. The errors in the file are
+    /// reported with the number of their line.
+    pub fn parse<R: Read>(reader: R) -> Result<ConfigFile, Error> {
+        let mut config = ConfigFile::new();
+        let mut section = String::new();
+        for (index, line) in BufReader::new(reader).lines().enumerate() {
+            let line_number = index + 1;
+            let line = try!(line);
+            let error = |description| {
+                Error::at_line(ErrorKind::InvalidInput, description, line_number)
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if line.starts_with('[') {
+                let end = try!(line.find(']').ok_or(error("unterminated section header")));
+                if !is_comment(&line[end + 1..]) {
+                    return Err(error("unexpected text after the section header"));
+                }
+                let name = line[1..end].trim();
+                if !is_valid_name(name) {
+                    return Err(error("invalid section name"));
+                }
+                if config.section(name).is_some() {
+                    return Err(error("duplicate section"));
+                }
+                config.sections.push(Section {
+                    name: String::from(name),
+                    entries: Vec::new(),
+                });
+                section = String::from(name);
+                continue;
+            }
+            let equal = try!(line.find('=').ok_or(error("expected key = value")));
+            let key = line[..equal].trim();
+            if !is_valid_name(key) {
+                return Err(error("invalid key"));
+            }
+            if config.get(&section, key).is_some() {
+                return Err(error("duplicate key"));
+            }
+            let value = try!(parse_value(line[equal + 1..].trim()).map_err(&error));
+            try!(config.set(&section, key, value));
+        }
+        Ok(config)
+    }
+
+    /// Parses the config file `file_name`.
+    pub fn open(file_name: &str) -> Result<ConfigFile, Error> {
+        ConfigFile::parse(try!(File::open(file_name)))
+    }
+
+    /// Writes the config to `file_name`, as a failsafe file of `max_len`
+    /// bytes. The file keeps its previous content if the config doesn't
+    /// fit.
+    pub fn save(&self, file_name: &str, max_len: usize) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        try!(self.write_to(&mut buf));
+        let mut file = try!(File::create(file_name, max_len, true));
+        if buf.len() as u64 > file.capacity() {
+            try!(file.abort());
+            return Err(Error::data("config", DataError::TooLarge));
+        }
+        try!(file.write_all(&buf));
+        file.close()
+    }
+
+    /// Writes the config in the format `parse` reads.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        let mut first = true;
+        for section in &self.sections {
+            if section.entries.is_empty() && section.name.is_empty() {
+                continue;
+            }
+            if !section.name.is_empty() {
+                if !first {
+                    try!(out.write_all(b"\n"));
+                }
+                try!(write!(out, "[{}]\n", section.name));
+            }
+            first = false;
+            for &(ref key, ref value) in &section.entries {
+                try!(write!(out, "{} = ", key));
+                try!(write_value(out, value));
+                try!(out.write_all(b"\n"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the sections, in the order of the file.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&Value> {
+        self.section(section).and_then(|section| section.get(key))
+    }
+
+    // The typed getters return None when the key holds another type.
+
+    pub fn get_str(&self, section: &str, key: &str) -> Option<&str> {
+        match self.get(section, key) {
+            Some(&Value::Str(ref value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, section: &str, key: &str) -> Option<i64> {
+        match self.get(section, key) {
+            Some(&Value::Int(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns a float, or an integer converted to a float.
+    pub fn get_float(&self, section: &str, key: &str) -> Option<f64> {
+        match self.get(section, key) {
+            Some(&Value::Float(value)) => Some(value),
+            Some(&Value::Int(value)) => Some(value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, section: &str, key: &str) -> Option<bool> {
+        match self.get(section, key) {
+            Some(&Value::Bool(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Sets `key` of `section`, adding the section if needed. Fails unless
+    /// the names and the value can be read back: `section` is "" or a valid
+    /// name like `key`, and floats are finite.
+    pub fn set(&mut self, section: &str, key: &str, value: Value) -> Result<(), Error> {
+        if !section.is_empty() && !is_valid_name(section) {
+            return Err(Error::new(ErrorKind::InvalidInput, "invalid section name"));
+        }
+        if !is_valid_name(key) {
+            return Err(Error::new(ErrorKind::InvalidInput, "invalid key"));
+        }
+        if let Value::Float(value) = value {
+            if !is_finite(value) {
+                return Err(Error::new(ErrorKind::InvalidInput, "the float isn't finite"));
+            }
+        }
+        let index = match self.sections.iter().position(|s| s.name == section) {
+            Some(index) => index,
+            None => {
+                // The keys without a section are written first.
+                let index = if section.is_empty() { 0 } else { self.sections.len() };
+                self.sections.insert(index, Section {
+                    name: String::from(section),
+                    entries: Vec::new(),
+                });
+                index
+            }
+        };
+        let entries = &mut self.sections[index].entries;
+        match entries.iter().position(|entry| entry.0 == key) {
+            Some(index) => entries[index].1 = value,
+            None => entries.push((String::from(key), value)),
+        }
+        Ok(())
+    }
+
+    /// Removes `key` of `section`, returning its value.
+    pub fn remove(&mut self, section: &str, key: &str) -> Option<Value> {
+        let section = match self.sections.iter_mut().find(|s| s.name == section) {
+            Some(section) => section,
+            None => return None,
+        };
+        match section.entries.iter().position(|entry| entry.0 == key) {
+            Some(index) => Some(section.entries.remove(index).1),
+            None => None,
+        }
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() &&
+    name.bytes().all(|b| {
+        (b >= b'a' && b <= b'z') || (b >= b'A' && b <= b'Z') || (b >= b'0' && b <= b'9') ||
+        b == b'_' || b == b'-' || b == b'.'
+    })
+}
+
+// f64::is_finite needs std. Infinities and NaN give NaN.
+fn is_finite(value: f64) -> bool {
+    value - value == 0.0
+}
+
+// True for what may follow a value or a section header.
+fn is_comment(text: &str) -> bool {
+    let text = text.trim();
+    text.is_empty() || text.starts_with('#') || text.starts_with(';')
+}
+
+fn parse_value(text: &str) -> Result<Value, &'static str> {
+    if text.starts_with('"') {
+        return parse_string(&text[1..]);
+    }
+    let text = match text.find(|c| c == '#' || c == ';') {
+        Some(end) => text[..end].trim(),
+        None => text,
+    };
+    if text.is_empty() {
+        return Err("missing value");
+    }
+    if text.starts_with('\'') {
+        return Err("strings must be quoted with '\"'");
+    }
+    match text {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+
+    // Values starting like a number must be one.
+    let unsigned = if text.starts_with('-') || text.starts_with('+') { &text[1..] } else { text };
+    if !unsigned.starts_with(|c: char| c >= '0' && c <= '9') && !unsigned.starts_with('.') {
+        return Ok(Value::Str(String::from(text)));
+    }
+    if unsigned.bytes().all(|b| b >= b'0' && b <= b'9') {
+        let text = if text.starts_with('+') { unsigned } else { text };
+        return text.parse().map(Value::Int).map_err(|_| "integer out of range");
+    }
+    match parse_f64(text) {
+        Ok(value) if is_finite(value) => Ok(Value::Float(value)),
+        Ok(_) => Err("number out of range"),
+        Err(_) => Err("invalid number"),
+    }
+}
+
+// Parses the rest of a quoted string.
+fn parse_string(text: &str) -> Result<Value, &'static str> {
+    let mut value = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let rest = chars.as_str();
+                if !is_comment(rest) {
+                    return Err("unexpected text after the value");
+                }
+                return Ok(Value::Str(value));
+            }
+            '\\' => {
+                let escaped = match chars.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    _ => return Err("invalid escape"),
+                };
+                value.push(escaped);
+            }
+            c => value.push(c),
+        }
+    }
+    Err("unterminated string")
+}
+
+fn write_value<W: Write>(out: &mut W, value: &Value) -> Result<(), Error> {
+    match *value {
+        Value::Str(ref value) => {
+            try!(out.write_all(b"\""));
+            for c in value.chars() {
+                match c {
+                    '"' => try!(out.write_all(b"\\\"")),
+                    '\\' => try!(out.write_all(b"\\\\")),
+                    '\n' => try!(out.write_all(b"\\n")),
+                    '\t' => try!(out.write_all(b"\\t")),
+                    '\r' => try!(out.write_all(b"\\r")),
+                    c => try!(write!(out, "{}", c)),
+                }
+            }
+            out.write_all(b"\"")
+        }
+        Value::Int(value) => write!(out, "{}", value),
+        Value::Float(value) => {
+            // Keeps a decimal point, so that the value reads back as a
+            // float.
+            let text = format!("{}", value);
+            if text.contains('.') || text.contains('e') {
+                out.write_all(text.as_bytes())
+            } else {
+                write!(out, "{}.0", text)
+            }
+        }
+        Value::Bool(value) => write!(out, "{}", value),
+    }
+}
@@ -0,0 +1,291 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// The error type of the crate.
+//
+// Every public API returns an `Error`, whose `kind` tells what went wrong
+// independently of the SDK. The errors reported by SimpleLink keep the
+// SDK error, returned by `simplelink`, for the callers that need the exact
+// code. The errors found in the data the crate stores, eg. the settings,
+// keep a `DataError`. The other errors carry a static description, and the
+// errors found in text files the number of the line.
+//
+// Only the SimpleLink error types are used, so this module also builds on
+// the host for the tests.
+
+extern crate cc3200_sys;
+
+use core::fmt;
+use core::result;
+
+use self::cc3200_sys::simplelink::{DriverError, FileSystemError, NetAppError, OsiError,
+                                   SimpleLinkError, WlanError};
+use self::cc3200_sys::socket::SocketError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A file, a host name or an entry doesn't exist.
+    NotFound,
+    TimedOut,
+    /// The operation can't complete without blocking; try again later.
+    WouldBlock,
+    /// An argument or the data passed were rejected.
+    InvalidInput,
+    /// The file system or the data it holds failed.
+    Io,
+    /// The data read isn't in the expected form, eg. text that isn't UTF-8.
+    InvalidData,
+    /// The connection to the network or to the peer failed.
+    Network,
+    /// The NWP, the host driver or a peripheral failed, or is not started.
+    Device,
+}
+
+/// What is wrong with the data the crate stores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataError {
+    /// The data isn't in its format, or fails its CRC check.
+    Corrupted,
+    /// The data doesn't fit in its file, or a field is too long.
+    TooLarge,
+    /// The data was saved in a newer version of its format.
+    UnsupportedVersion(u16),
+}
+
+impl DataError {
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            DataError::Corrupted => ErrorKind::Io,
+            DataError::TooLarge |
+            DataError::UnsupportedVersion(_) => ErrorKind::InvalidInput,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match *self {
+            DataError::Corrupted => "corrupted",
+            DataError::TooLarge => "too large",
+            DataError::UnsupportedVersion(_) => "unsupported version",
+        }
+    }
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DataError::UnsupportedVersion(version) => {
+                write!(formatter, "{} {}", self.description(), version)
+            }
+            _ => write!(formatter, "{}", self.description()),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Repr {
+    SimpleLink(SimpleLinkError),
+    Simple(&'static str),
+    Line(usize, &'static str),
+    // What the data is, eg. "settings", and what is wrong with it.
+    Data(&'static str, DataError),
+}
+
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    repr: Repr,
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl Error {
+    pub fn new(kind: ErrorKind, description: &'static str) -> Error {
+        Error {
+            kind: kind,
+            repr: Repr::Simple(description),
+        }
+    }
+
+    /// An error at the line `line`, counted from 1, of a text file.
+    pub fn at_line(kind: ErrorKind, description: &'static str, line: usize) -> Error {
+        Error {
+            kind: kind,
+            repr: Repr::Line(line, description),
+        }
+    }
+
+    /// An error in the data `what`, eg. "settings".
+    pub fn data(what: &'static str, err: DataError) -> Error {
+        Error {
+            kind: err.kind(),
+            repr: Repr::Data(what, err),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns the SDK error, for the errors reported by SimpleLink.
+    pub fn simplelink(&self) -> Option<&SimpleLinkError> {
+        match self.repr {
+            Repr::SimpleLink(ref err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the line of the errors found in text files.
+    pub fn line(&self) -> Option<usize> {
+        match self.repr {
+            Repr::Line(line, _) => Some(line),
+            _ => None,
+        }
+    }
+
+    /// Returns what is wrong with the data, for the errors found in the
+    /// data the crate stores.
+    pub fn data_error(&self) -> Option<DataError> {
+        match self.repr {
+            Repr::Data(_, err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// True for the file system error `err`, eg. FILE_NOT_EXISTS.
+    pub fn is_fs(&self, err: FileSystemError) -> bool {
+        match self.repr {
+            Repr::SimpleLink(SimpleLinkError::FileSystem(e)) => e == err,
+            _ => false,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self.repr {
+            Repr::SimpleLink(ref err) => err.description(),
+            Repr::Simple(description) |
+            Repr::Line(_, description) => description,
+            Repr::Data(_, err) => err.description(),
+        }
+    }
+}
+
+impl From<SimpleLinkError> for Error {
+    fn from(err: SimpleLinkError) -> Error {
+        Error {
+            kind: kind_of(&err),
+            repr: Repr::SimpleLink(err),
+        }
+    }
+}
+
+impl From<FileSystemError> for Error {
+    fn from(err: FileSystemError) -> Error {
+        Error::from(SimpleLinkError::FileSystem(err))
+    }
+}
+
+impl From<SocketError> for Error {
+    fn from(err: SocketError) -> Error {
+        Error::from(SimpleLinkError::Socket(err))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.repr {
+            Repr::SimpleLink(ref err) => write!(formatter, "{}", err),
+            Repr::Simple(description) => write!(formatter, "{:?}: {}", self.kind, description),
+            Repr::Line(line, description) => {
+                write!(formatter, "{:?}: line {}: {}", self.kind, line, description)
+            }
+            Repr::Data(what, err) => write!(formatter, "{:?}: {} {}", self.kind, what, err),
+        }
+    }
+}
+
+fn kind_of(err: &SimpleLinkError) -> ErrorKind {
+    match *err {
+        SimpleLinkError::FileSystem(err) => {
+            match err {
+                FileSystemError::FILE_NOT_EXISTS => ErrorKind::NotFound,
+                FileSystemError::INVALID_ARGS |
+                FileSystemError::BAD_FILE_MODE |
+                FileSystemError::INVALID_ACCESS_TYPE |
+                FileSystemError::WRONG_FILE_NAME |
+                FileSystemError::FILE_UNVALID_FILE_SIZE |
+                FileSystemError::OFFSET_OUT_OF_RANGE |
+                FileSystemError::FILE_ALREADY_EXISTS |
+                FileSystemError::TOKEN_IS_NOT_VALID |
+                FileSystemError::SECURE_FILE_MUST_BE_COMMIT |
+                FileSystemError::WRONG_SIGNATURE_OR_CERTIFIC_NAME_LENGTH |
+                FileSystemError::WRONG_SIGNATURE |
+                FileSystemError::CERT_CHAIN_ERROR => ErrorKind::InvalidInput,
+                _ => ErrorKind::Io,
+            }
+        }
+        SimpleLinkError::Socket(err) => {
+            match err {
+                SocketError::EAGAIN | SocketError::EALREADY | SocketError::INEXE => {
+                    ErrorKind::WouldBlock
+                }
+                SocketError::ETIMEDOUT => ErrorKind::TimedOut,
+                SocketError::EBADF |
+                SocketError::EFAULT |
+                SocketError::EINVAL |
+                SocketError::EDESTADDRREQ |
+                SocketError::EPROTOTYPE |
+                SocketError::ENOPROTOOPT |
+                SocketError::EPROTONOSUPPORT |
+                SocketError::ESOCKTNOSUPPORT |
+                SocketError::EOPNOTSUPP |
+                SocketError::EAFNOSUPPORT => ErrorKind::InvalidInput,
+                SocketError::ENSOCK | SocketError::ENOMEM => ErrorKind::Device,
+                _ => ErrorKind::Network,
+            }
+        }
+        SimpleLinkError::NetApp(err) => {
+            match err {
+                NetAppError::DNS_QUERY_FAILED |
+                NetAppError::DNS_NO_ANSWER |
+                NetAppError::DNS_NO_KNOWN_ANSWER |
+                NetAppError::DNS_NOT_EXISTED_SERVICE_ERROR => ErrorKind::NotFound,
+                NetAppError::DNS_QUERY_NO_RESPONSE => ErrorKind::TimedOut,
+                NetAppError::DEVICE_NAME_LEN_ERR |
+                NetAppError::DEVICE_NAME_INVALID |
+                NetAppError::DOMAIN_NAME_LEN_ERR |
+                NetAppError::DOMAIN_NAME_INVALID |
+                NetAppError::DNS_PARAM_ERROR |
+                NetAppError::DNS_NAME_MISMATCH |
+                NetAppError::DNS_HOST_NAME_ERROR |
+                NetAppError::DNS_ERROR_SERVICE_NAME_ERROR |
+                NetAppError::DNS_NET_APP_SET_ERROR |
+                NetAppError::DNS_GET_SERVICE_LIST_FLAG_ERROR |
+                NetAppError::RX_BUFFER_LENGTH_ERROR => ErrorKind::InvalidInput,
+                _ => ErrorKind::Network,
+            }
+        }
+        SimpleLinkError::Wlan(err) => {
+            match err {
+                WlanError::ROLE_STA_ERR | WlanError::ROLE_AP_ERR | WlanError::INVALID_ROLE => {
+                    ErrorKind::Device
+                }
+                WlanError::WIFI_ALREADY_DISCONNECTED |
+                WlanError::WIFI_NOT_CONNECTED => ErrorKind::Network,
+                _ => ErrorKind::InvalidInput,
+            }
+        }
+        SimpleLinkError::Osi(err) => {
+            match err {
+                OsiError::OSI_TIMEOUT => ErrorKind::TimedOut,
+                OsiError::OSI_INVALID_PARAMS => ErrorKind::InvalidInput,
+                _ => ErrorKind::Device,
+            }
+        }
+        SimpleLinkError::Driver(DriverError::INVALID_INPUT) => ErrorKind::InvalidInput,
+        SimpleLinkError::RxFilter(_) => ErrorKind::InvalidInput,
+        SimpleLinkError::Driver(_) |
+        SimpleLinkError::ValueError(..) |
+        SimpleLinkError::NotStarted => ErrorKind::Device,
+    }
+}
@@ -0,0 +1,363 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Asynchronous SimpleLink events.
+//
+// The event handlers in cc3200-sys/simplelink.c call the simplelink_on_*
+// functions below, which turn the SDK structures into typed events and post
+// them to every subscribed queue. The handlers run in the context of the
+// SimpleLink spawn task, so posting never blocks: if a subscriber's queue is
+// full the event is dropped for that subscriber, and if the subscribers are
+// being changed for longer than `SUBSCRIBERS_TIMEOUT_MS` it is dropped for
+// all of them.
+
+use alloc::arc::Arc;
+use collections::Vec;
+
+use core::slice;
+
+use freertos_rs::{Duration, Mutex, Queue};
+
+use error::Error;
+use net::Ipv6Addr;
+use simplelink::{OsiError, SimpleLinkError};
+
+pub const SSID_LEN_MAX: usize = 32;
+pub const BSSID_LEN: usize = 6;
+pub const P2P_DEV_NAME_LEN_MAX: usize = 32;
+
+// Reason code of a disconnection requested by the application.
+const DISCONNECT_USER_INITIATED: u8 = 200;
+
+// How long an event waits for the subscribers, which are only locked to add
+// or remove one.
+const SUBSCRIBERS_TIMEOUT_MS: u32 = 10;
+
+#[derive(Debug, Clone, Copy)]
+pub enum WlanEvent {
+    /// The station connected to an access point (or P2P group owner).
+    Connected {
+        ssid: [u8; SSID_LEN_MAX],
+        ssid_len: u8,
+        bssid: [u8; BSSID_LEN],
+    },
+
+    /// The station got disconnected. `reason` is the 802.11 reason code, or
+    /// 200 when the disconnection was requested by the application.
+    Disconnected { reason: u8 },
+
+    /// A station connected to the device (AP mode).
+    StationAdded { mac: [u8; BSSID_LEN] },
+
+    /// A station disconnected from the device (AP mode).
+    StationRemoved { mac: [u8; BSSID_LEN] },
+
+    /// A P2P device was found while scanning (P2P mode).
+    P2pDeviceFound {
+        name: [u8; P2P_DEV_NAME_LEN_MAX],
+        name_len: u8,
+        mac: [u8; BSSID_LEN],
+        wps_dev_password_id: u8,
+    },
+
+    /// A P2P device asked to negotiate a connection (P2P mode).
+    P2pNegotiationRequest {
+        name: [u8; P2P_DEV_NAME_LEN_MAX],
+        name_len: u8,
+        mac: [u8; BSSID_LEN],
+        wps_dev_password_id: u8,
+    },
+
+    /// The P2P negotiation or connection failed (P2P mode).
+    ConnectionFailed { status: u16 },
+}
+
+impl WlanEvent {
+    /// Returns true for a disconnection that was requested by the application.
+    pub fn is_user_initiated_disconnect(&self) -> bool {
+        match *self {
+            WlanEvent::Disconnected { reason } => reason == DISCONNECT_USER_INITIATED,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NetAppEvent {
+    /// The device acquired an IPv4 address. All addresses are in host byte order.
+    IpAcquired { ip: u32, gateway: u32, dns: u32 },
+
+    /// The device acquired an IPv6 address.
+    Ipv6Acquired {
+        ip: Ipv6Addr,
+        gateway: Ipv6Addr,
+        dns: Ipv6Addr,
+    },
+
+    /// The DHCP server leased an address to a station (AP mode).
+    IpLeased {
+        ip: u32,
+        lease_time: u32,
+        mac: [u8; BSSID_LEN],
+    },
+
+    /// A leased address was released (AP mode).
+    IpReleased {
+        ip: u32,
+        mac: [u8; BSSID_LEN],
+        reason: u16,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SockEvent {
+    /// Queued data could not be transmitted on `socket`.
+    TxFailed { socket: i16, status: i16 },
+
+    /// Asynchronous socket notification (eg. secure socket alerts).
+    Async { socket: i16, kind: u8, value: i16 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DeviceEvent {
+    /// A general (usually non fatal) error reported by the NWP.
+    GeneralError { status: i8, sender: u32 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    Wlan(WlanEvent),
+    NetApp(NetAppEvent),
+    Sock(SockEvent),
+    Device(DeviceEvent),
+}
+
+struct Subscriber {
+    id: u32,
+    queue: Arc<Queue<Event>>,
+}
+
+struct Subscribers {
+    next_id: u32,
+    list: Vec<Subscriber>,
+}
+
+lazy_static! {
+    static ref SUBSCRIBERS: Mutex<Subscribers> = Mutex::new(Subscribers {
+        next_id: 0,
+        list: Vec::new(),
+    }).unwrap();
+}
+
+/// A queue receiving a copy of every SimpleLink event raised after it was
+/// created. The subscription ends when the `EventQueue` is dropped.
+pub struct EventQueue {
+    id: u32,
+    queue: Arc<Queue<Event>>,
+}
+
+impl EventQueue {
+    /// Subscribes to the SimpleLink events. `capacity` is the number of
+    /// events that can be pending before new ones are dropped.
+    pub fn subscribe(capacity: usize) -> Result<EventQueue, Error> {
+        let queue = match Queue::new(capacity) {
+            Ok(queue) => Arc::new(queue),
+            Err(_) => {
+                return Err(SimpleLinkError::Osi(OsiError::OSI_MEMORY_ALLOCATION_FAILURE).into())
+            }
+        };
+        let mut subscribers = match SUBSCRIBERS.lock(Duration::infinite()) {
+            Ok(subscribers) => subscribers,
+            Err(_) => return Err(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED).into()),
+        };
+        let id = subscribers.next_id;
+        subscribers.next_id = subscribers.next_id.wrapping_add(1);
+        subscribers.list.push(Subscriber {
+            id: id,
+            queue: queue.clone(),
+        });
+        Ok(EventQueue {
+            id: id,
+            queue: queue,
+        })
+    }
+
+    /// Waits up to `timeout_ms` for the next event.
+    pub fn receive(&self, timeout_ms: u32) -> Option<Event> {
+        self.queue.receive(Duration::ms(timeout_ms)).ok()
+    }
+
+    /// Returns the next pending event, if any.
+    pub fn try_receive(&self) -> Option<Event> {
+        self.queue.receive(Duration::zero()).ok()
+    }
+}
+
+impl Drop for EventQueue {
+    fn drop(&mut self) {
+        if let Ok(mut subscribers) = SUBSCRIBERS.lock(Duration::infinite()) {
+            subscribers.list.retain(|s| s.id != self.id);
+        }
+    }
+}
+
+fn dispatch(event: Event) {
+    let subscribers = match SUBSCRIBERS.lock(Duration::ms(SUBSCRIBERS_TIMEOUT_MS)) {
+        Ok(subscribers) => subscribers,
+        Err(_) => {
+            warn!("The event subscribers are busy, dropping {:?}", event);
+            return;
+        }
+    };
+    for subscriber in subscribers.list.iter() {
+        if subscriber.queue.send(event, Duration::zero()).is_err() {
+            warn!("Event queue {} is full, dropping {:?}", subscriber.id, event);
+        }
+    }
+}
+
+// Copies an SSID or a P2P device name, truncated to 32 bytes.
+unsafe fn name_from_ptr(name: *const u8, name_len: u8) -> ([u8; SSID_LEN_MAX], u8) {
+    let len = if (name_len as usize) < SSID_LEN_MAX {
+        name_len as usize
+    } else {
+        SSID_LEN_MAX
+    };
+    let mut res = [0; SSID_LEN_MAX];
+    res[..len].copy_from_slice(slice::from_raw_parts(name, len));
+    (res, len as u8)
+}
+
+unsafe fn mac_from_ptr(mac: *const u8) -> [u8; BSSID_LEN] {
+    let mut res = [0; BSSID_LEN];
+    res.copy_from_slice(slice::from_raw_parts(mac, BSSID_LEN));
+    res
+}
+
+//
+// Called from simplelink.c
+//
+
+#[no_mangle]
+pub unsafe extern "C" fn simplelink_on_wlan_connect(ssid: *const u8,
+                                                    ssid_len: u8,
+                                                    bssid: *const u8) {
+    let (ssid_buf, len) = name_from_ptr(ssid, ssid_len);
+    dispatch(Event::Wlan(WlanEvent::Connected {
+        ssid: ssid_buf,
+        ssid_len: len,
+        bssid: mac_from_ptr(bssid),
+    }));
+}
+
+#[no_mangle]
+pub extern "C" fn simplelink_on_wlan_disconnect(reason_code: u8) {
+    dispatch(Event::Wlan(WlanEvent::Disconnected { reason: reason_code }));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn simplelink_on_wlan_sta_connect(mac: *const u8) {
+    dispatch(Event::Wlan(WlanEvent::StationAdded { mac: mac_from_ptr(mac) }));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn simplelink_on_wlan_sta_disconnect(mac: *const u8) {
+    dispatch(Event::Wlan(WlanEvent::StationRemoved { mac: mac_from_ptr(mac) }));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn simplelink_on_p2p_dev_found(name: *const u8,
+                                                     name_len: u8,
+                                                     mac: *const u8,
+                                                     wps_dev_password_id: u8) {
+    let (name_buf, len) = name_from_ptr(name, name_len);
+    dispatch(Event::Wlan(WlanEvent::P2pDeviceFound {
+        name: name_buf,
+        name_len: len,
+        mac: mac_from_ptr(mac),
+        wps_dev_password_id: wps_dev_password_id,
+    }));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn simplelink_on_p2p_neg_req(name: *const u8,
+                                                   name_len: u8,
+                                                   mac: *const u8,
+                                                   wps_dev_password_id: u8) {
+    let (name_buf, len) = name_from_ptr(name, name_len);
+    dispatch(Event::Wlan(WlanEvent::P2pNegotiationRequest {
+        name: name_buf,
+        name_len: len,
+        mac: mac_from_ptr(mac),
+        wps_dev_password_id: wps_dev_password_id,
+    }));
+}
+
+#[no_mangle]
+pub extern "C" fn simplelink_on_wlan_connection_failed(status: u16) {
+    dispatch(Event::Wlan(WlanEvent::ConnectionFailed { status: status }));
+}
+
+#[no_mangle]
+pub extern "C" fn simplelink_on_ipv4_acquired(ip: u32, gateway: u32, dns: u32) {
+    dispatch(Event::NetApp(NetAppEvent::IpAcquired {
+        ip: ip,
+        gateway: gateway,
+        dns: dns,
+    }));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn simplelink_on_ipv6_acquired(ip: *const u32,
+                                                     gateway: *const u32,
+                                                     dns: *const u32) {
+    dispatch(Event::NetApp(NetAppEvent::Ipv6Acquired {
+        ip: Ipv6Addr::from_words(&*(ip as *const [u32; 4])),
+        gateway: Ipv6Addr::from_words(&*(gateway as *const [u32; 4])),
+        dns: Ipv6Addr::from_words(&*(dns as *const [u32; 4])),
+    }));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn simplelink_on_ip_leased(ip: u32, lease_time: u32, mac: *const u8) {
+    dispatch(Event::NetApp(NetAppEvent::IpLeased {
+        ip: ip,
+        lease_time: lease_time,
+        mac: mac_from_ptr(mac),
+    }));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn simplelink_on_ip_released(ip: u32, mac: *const u8, reason: u16) {
+    dispatch(Event::NetApp(NetAppEvent::IpReleased {
+        ip: ip,
+        mac: mac_from_ptr(mac),
+        reason: reason,
+    }));
+}
+
+#[no_mangle]
+pub extern "C" fn simplelink_on_sock_tx_failed(sd: u8, status: i16) {
+    dispatch(Event::Sock(SockEvent::TxFailed {
+        socket: sd as i16,
+        status: status,
+    }));
+}
+
+#[no_mangle]
+pub extern "C" fn simplelink_on_sock_async(sd: u8, kind: u8, value: i16) {
+    dispatch(Event::Sock(SockEvent::Async {
+        socket: sd as i16,
+        kind: kind,
+        value: value,
+    }));
+}
+
+#[no_mangle]
+pub extern "C" fn simplelink_on_device_error(status: i8, sender: u32) {
+    dispatch(Event::Device(DeviceEvent::GeneralError {
+        status: status,
+        sender: sender,
+    }));
+}
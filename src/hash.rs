@@ -0,0 +1,841 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Hashes and checksums: MD5, SHA-1, SHA-224 and SHA-256 digests, their HMAC,
+// and CRC32.
+//
+// On the board, the SHAMD5 engine computes the digests and the CRC engine
+// the checksums. The SHAMD5 engine needs the length of the message before
+// the first byte, so `Hasher::new` and `Hmac::new` hash in software, while
+// `Hasher::with_len`, `Hmac::with_len` and the one-shot functions use the
+// engine. Both engines are in the DTHE module, which serves one user at a
+// time: a hash started while it is busy falls back to software too. So does
+// every hash once the SHAMD5 engine timed out, and every checksum if the CRC
+// engine disagrees with the software on a known message. The software
+// implementations are also what runs on the host, where this is tested.
+//
+// The hashers implement `io::Write`, so any reader can be hashed with
+// `io::copy`.
+
+use core::fmt;
+
+use error::{Error, ErrorKind};
+use io::{self, File, Write};
+
+// All the algorithms hash blocks of 64 bytes.
+const BLOCK_LEN: usize = 64;
+const MAX_DIGEST_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+}
+
+impl Algorithm {
+    pub fn digest_len(&self) -> usize {
+        match *self {
+            Algorithm::Md5 => 16,
+            Algorithm::Sha1 => 20,
+            Algorithm::Sha224 => 28,
+            Algorithm::Sha256 => 32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Digest {
+    bytes: [u8; MAX_DIGEST_LEN],
+    len: usize,
+}
+
+impl Digest {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+impl fmt::Display for Digest {
+    // Formats the digest in lowercase hexadecimal.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.as_bytes() {
+            try!(write!(formatter, "{:02x}", byte));
+        }
+        Ok(())
+    }
+}
+
+enum State {
+    Soft(SoftHasher),
+    #[cfg(target_arch = "arm")]
+    Engine(engine::Shamd5),
+}
+
+/// A streaming hash.
+pub struct Hasher {
+    algorithm: Algorithm,
+    state: State,
+}
+
+impl Hasher {
+    /// Starts a hash of a message of any length, computed in software.
+    pub fn new(algorithm: Algorithm) -> Hasher {
+        Hasher {
+            algorithm: algorithm,
+            state: State::Soft(SoftHasher::new(algorithm)),
+        }
+    }
+
+    /// Starts a hash of a message of exactly `len` bytes, computed by the
+    /// SHAMD5 engine when it is available. `finish` fails if the message
+    /// has another length, or if the engine times out.
+    pub fn with_len(algorithm: Algorithm, len: usize) -> Hasher {
+        Hasher::start(algorithm, None, len)
+    }
+
+    #[cfg(target_arch = "arm")]
+    fn start(algorithm: Algorithm, key: Option<&[u8; BLOCK_LEN]>, len: usize) -> Hasher {
+        // The engine doesn't hash empty messages.
+        if len > 0 {
+            if let Some(engine) = engine::Shamd5::acquire(algorithm, key, len) {
+                return Hasher {
+                    algorithm: algorithm,
+                    state: State::Engine(engine),
+                };
+            }
+        }
+        let mut hasher = Hasher::new(algorithm);
+        hasher.expect_len(len);
+        hasher
+    }
+
+    #[cfg(not(target_arch = "arm"))]
+    fn start(algorithm: Algorithm, _key: Option<&[u8; BLOCK_LEN]>, len: usize) -> Hasher {
+        let mut hasher = Hasher::new(algorithm);
+        hasher.expect_len(len);
+        hasher
+    }
+
+    fn expect_len(&mut self, len: usize) {
+        if let State::Soft(ref mut hasher) = self.state {
+            hasher.expected_len = Some(len as u64);
+        }
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self.state {
+            State::Soft(ref mut hasher) => hasher.update(data),
+            #[cfg(target_arch = "arm")]
+            State::Engine(ref mut engine) => engine.update(data),
+        }
+    }
+
+    pub fn finish(self) -> Result<Digest, Error> {
+        let mut digest = Digest {
+            bytes: [0; MAX_DIGEST_LEN],
+            len: self.algorithm.digest_len(),
+        };
+        let complete = match self.state {
+            State::Soft(mut hasher) => hasher.finish(&mut digest.bytes),
+            #[cfg(target_arch = "arm")]
+            State::Engine(mut engine) => try!(engine.finish(&mut digest.bytes)),
+        };
+        if !complete {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "the message doesn't have the announced length"));
+        }
+        Ok(digest)
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+}
+
+/// A streaming HMAC.
+pub struct Hmac {
+    hasher: Hasher,
+    // The padded key xored with the outer pad, when hashing in software.
+    outer_key: Option<[u8; BLOCK_LEN]>,
+}
+
+impl Hmac {
+    /// Starts the HMAC of a message of any length, computed in software.
+    pub fn new(algorithm: Algorithm, key: &[u8]) -> Hmac {
+        let key = hmac_key(algorithm, key);
+        let mut hmac = Hmac {
+            hasher: Hasher::new(algorithm),
+            outer_key: None,
+        };
+        hmac.hash_keys(&key);
+        hmac
+    }
+
+    /// Starts the HMAC of a message of exactly `len` bytes, computed by the
+    /// SHAMD5 engine when it is available. `finish` fails if the message
+    /// has another length, or if the engine times out.
+    pub fn with_len(algorithm: Algorithm, key: &[u8], len: usize) -> Hmac {
+        let key = hmac_key(algorithm, key);
+        let mut hmac = Hmac {
+            hasher: Hasher::start(algorithm, Some(&key), len),
+            outer_key: None,
+        };
+        if let State::Soft(_) = hmac.hasher.state {
+            hmac.hash_keys(&key);
+        }
+        hmac
+    }
+
+    // Starts the inner hash in software.
+    fn hash_keys(&mut self, key: &[u8; BLOCK_LEN]) {
+        let mut inner_key = [0; BLOCK_LEN];
+        let mut outer_key = [0; BLOCK_LEN];
+        for i in 0..BLOCK_LEN {
+            inner_key[i] = key[i] ^ 0x36;
+            outer_key[i] = key[i] ^ 0x5c;
+        }
+        if let State::Soft(ref mut hasher) = self.hasher.state {
+            hasher.update(&inner_key);
+            // The announced length is the one of the message.
+            if let Some(len) = hasher.expected_len {
+                hasher.expected_len = Some(len + BLOCK_LEN as u64);
+            }
+        }
+        self.outer_key = Some(outer_key);
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    pub fn finish(self) -> Result<Digest, Error> {
+        let algorithm = self.hasher.algorithm;
+        let inner = try!(self.hasher.finish());
+        match self.outer_key {
+            Some(outer_key) => {
+                let mut hasher = Hasher::new(algorithm);
+                hasher.update(&outer_key);
+                hasher.update(inner.as_bytes());
+                hasher.finish()
+            }
+            None => Ok(inner),
+        }
+    }
+}
+
+impl Write for Hmac {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+}
+
+// Keys longer than a block are hashed, and all are padded with zeroes.
+fn hmac_key(algorithm: Algorithm, key: &[u8]) -> [u8; BLOCK_LEN] {
+    let mut padded = [0; BLOCK_LEN];
+    if key.len() > BLOCK_LEN {
+        let digest = digest(algorithm, key);
+        padded[..digest.len].copy_from_slice(digest.as_bytes());
+    } else {
+        padded[..key.len()].copy_from_slice(key);
+    }
+    padded
+}
+
+/// A streaming CRC32, as used by zlib and Ethernet.
+pub struct Crc32 {
+    // The reflected register, before the final inversion.
+    state: u32,
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32 { state: 0xffffffff }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        if !data.is_empty() {
+            self.state = crc32_update(self.state, data);
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+impl Write for Crc32 {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+}
+
+#[cfg(target_arch = "arm")]
+fn crc32_update(state: u32, data: &[u8]) -> u32 {
+    if engine::crc32_works() {
+        if let Some(state) = engine::crc32_update(state, data) {
+            return state;
+        }
+    }
+    soft_crc32_update(state, data)
+}
+
+#[cfg(not(target_arch = "arm"))]
+fn crc32_update(state: u32, data: &[u8]) -> u32 {
+    soft_crc32_update(state, data)
+}
+
+// Computed bit by bit: a table isn't worth its flash.
+fn soft_crc32_update(mut state: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        state ^= *byte as u32;
+        for _ in 0..8 {
+            state = if state & 1 != 0 { (state >> 1) ^ 0xedb88320 } else { state >> 1 };
+        }
+    }
+    state
+}
+
+pub fn digest(algorithm: Algorithm, data: &[u8]) -> Digest {
+    let mut hasher = Hasher::with_len(algorithm, data.len());
+    hasher.update(data);
+    // The length is the announced one, so only the engine can fail, and
+    // it isn't used again.
+    match hasher.finish() {
+        Ok(digest) => digest,
+        Err(_) => digest(algorithm, data),
+    }
+}
+
+pub fn hmac(algorithm: Algorithm, key: &[u8], data: &[u8]) -> Digest {
+    let mut hmac = Hmac::with_len(algorithm, key, data.len());
+    hmac.update(data);
+    match hmac.finish() {
+        Ok(digest) => digest,
+        Err(_) => self::hmac(algorithm, key, data),
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// Hashes the content of the file `file_name`, reading it by chunks.
+pub fn digest_file(algorithm: Algorithm, file_name: &str) -> Result<Digest, Error> {
+    let mut file = try!(File::open(file_name));
+    let mut hasher = Hasher::with_len(algorithm, try!(file.len()) as usize);
+    try!(io::copy(&mut file, &mut hasher));
+    match hasher.finish() {
+        // Reads the file again, to hash it in software.
+        Err(ref err) if err.kind() == ErrorKind::TimedOut => digest_file(algorithm, file_name),
+        result => result,
+    }
+}
+
+/// Computes the HMAC of the content of the file `file_name`, reading it by
+/// chunks.
+pub fn hmac_file(algorithm: Algorithm, key: &[u8], file_name: &str) -> Result<Digest, Error> {
+    let mut file = try!(File::open(file_name));
+    let mut hmac = Hmac::with_len(algorithm, key, try!(file.len()) as usize);
+    try!(io::copy(&mut file, &mut hmac));
+    match hmac.finish() {
+        Err(ref err) if err.kind() == ErrorKind::TimedOut => hmac_file(algorithm, key, file_name),
+        result => result,
+    }
+}
+
+/// Computes the CRC32 of the content of the file `file_name`, reading it by
+/// chunks.
+pub fn crc32_file(file_name: &str) -> Result<u32, Error> {
+    let mut file = try!(File::open(file_name));
+    let mut crc = Crc32::new();
+    try!(io::copy(&mut file, &mut crc));
+    Ok(crc.finish())
+}
+
+const MD5_INIT: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+const SHA1_INIT: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+const SHA224_INIT: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4,
+];
+
+const SHA256_INIT: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+struct SoftHasher {
+    algorithm: Algorithm,
+    state: [u32; 8],
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    // The number of bytes hashed.
+    len: u64,
+    expected_len: Option<u64>,
+}
+
+impl SoftHasher {
+    fn new(algorithm: Algorithm) -> SoftHasher {
+        let mut state = [0; 8];
+        match algorithm {
+            Algorithm::Md5 => state[..4].copy_from_slice(&MD5_INIT),
+            Algorithm::Sha1 => state[..5].copy_from_slice(&SHA1_INIT),
+            Algorithm::Sha224 => state = SHA224_INIT,
+            Algorithm::Sha256 => state = SHA256_INIT,
+        }
+        SoftHasher {
+            algorithm: algorithm,
+            state: state,
+            block: [0; BLOCK_LEN],
+            block_len: 0,
+            len: 0,
+            expected_len: None,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+        while !data.is_empty() {
+            let len = ::core::cmp::min(BLOCK_LEN - self.block_len, data.len());
+            self.block[self.block_len..self.block_len + len].copy_from_slice(&data[..len]);
+            self.block_len += len;
+            data = &data[len..];
+            if self.block_len == BLOCK_LEN {
+                let block = self.block;
+                self.compress(&block);
+                self.block_len = 0;
+            }
+        }
+    }
+
+    // Writes the digest to `out`, and returns false if the length isn't the
+    // expected one.
+    fn finish(&mut self, out: &mut [u8; MAX_DIGEST_LEN]) -> bool {
+        let bit_len = self.len.wrapping_mul(8);
+        let complete = self.expected_len.map_or(true, |len| len == self.len);
+
+        // Appends a 1 bit, then zeroes up to the length at the end of a
+        // block.
+        let mut padding = [0; BLOCK_LEN + 8];
+        padding[0] = 0x80;
+        let zeroes = (BLOCK_LEN + BLOCK_LEN - 8 - self.block_len - 1) % BLOCK_LEN;
+        let mut bit_len_bytes = [0; 8];
+        for i in 0..8 {
+            bit_len_bytes[i] = match self.algorithm {
+                Algorithm::Md5 => (bit_len >> (8 * i)) as u8,
+                _ => (bit_len >> (56 - 8 * i)) as u8,
+            };
+        }
+        padding[1 + zeroes..1 + zeroes + 8].copy_from_slice(&bit_len_bytes);
+        let len = self.len;
+        self.update(&padding[..1 + zeroes + 8]);
+        self.len = len;
+
+        for i in 0..self.algorithm.digest_len() / 4 {
+            let word = self.state[i];
+            for j in 0..4 {
+                out[4 * i + j] = match self.algorithm {
+                    Algorithm::Md5 => (word >> (8 * j)) as u8,
+                    _ => (word >> (24 - 8 * j)) as u8,
+                };
+            }
+        }
+        complete
+    }
+
+    fn compress(&mut self, block: &[u8; BLOCK_LEN]) {
+        match self.algorithm {
+            Algorithm::Md5 => md5_compress(&mut self.state, block),
+            Algorithm::Sha1 => sha1_compress(&mut self.state, block),
+            Algorithm::Sha224 | Algorithm::Sha256 => sha256_compress(&mut self.state, block),
+        }
+    }
+}
+
+fn md5_compress(state: &mut [u32; 8], block: &[u8; BLOCK_LEN]) {
+    let mut m = [0u32; 16];
+    for i in 0..16 {
+        m[i] = (block[4 * i] as u32) | ((block[4 * i + 1] as u32) << 8) |
+               ((block[4 * i + 2] as u32) << 16) | ((block[4 * i + 3] as u32) << 24);
+    }
+
+    let (mut a, mut b, mut c, mut d) = (state[0], state[1], state[2], state[3]);
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let rotated = a.wrapping_add(f)
+            .wrapping_add(MD5_K[i])
+            .wrapping_add(m[g])
+            .rotate_left(MD5_SHIFTS[(i / 16) * 4 + i % 4]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(rotated);
+    }
+    state[0] = state[0].wrapping_add(a);
+    state[1] = state[1].wrapping_add(b);
+    state[2] = state[2].wrapping_add(c);
+    state[3] = state[3].wrapping_add(d);
+}
+
+fn sha1_compress(state: &mut [u32; 8], block: &[u8; BLOCK_LEN]) {
+    let mut w = [0u32; 80];
+    for i in 0..16 {
+        w[i] = ((block[4 * i] as u32) << 24) | ((block[4 * i + 1] as u32) << 16) |
+               ((block[4 * i + 2] as u32) << 8) | (block[4 * i + 3] as u32);
+    }
+    for i in 16..80 {
+        w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+
+    let (mut a, mut b, mut c, mut d, mut e) = (state[0], state[1], state[2], state[3], state[4]);
+    for i in 0..80 {
+        let (f, k) = match i / 20 {
+            0 => ((b & c) | (!b & d), 0x5a827999),
+            1 => (b ^ c ^ d, 0x6ed9eba1),
+            2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _ => (b ^ c ^ d, 0xca62c1d6),
+        };
+        let temp = a.rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(w[i]);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+    }
+    state[0] = state[0].wrapping_add(a);
+    state[1] = state[1].wrapping_add(b);
+    state[2] = state[2].wrapping_add(c);
+    state[3] = state[3].wrapping_add(d);
+    state[4] = state[4].wrapping_add(e);
+}
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u8; BLOCK_LEN]) {
+    let mut w = [0u32; 64];
+    for i in 0..16 {
+        w[i] = ((block[4 * i] as u32) << 24) | ((block[4 * i + 1] as u32) << 16) |
+               ((block[4 * i + 2] as u32) << 8) | (block[4 * i + 3] as u32);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let mut v = *state;
+    for i in 0..64 {
+        let s1 = v[4].rotate_right(6) ^ v[4].rotate_right(11) ^ v[4].rotate_right(25);
+        let ch = (v[4] & v[5]) ^ (!v[4] & v[6]);
+        let temp1 = v[7]
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(SHA256_K[i])
+            .wrapping_add(w[i]);
+        let s0 = v[0].rotate_right(2) ^ v[0].rotate_right(13) ^ v[0].rotate_right(22);
+        let maj = (v[0] & v[1]) ^ (v[0] & v[2]) ^ (v[1] & v[2]);
+        let temp2 = s0.wrapping_add(maj);
+        v[7] = v[6];
+        v[6] = v[5];
+        v[5] = v[4];
+        v[4] = v[3].wrapping_add(temp1);
+        v[3] = v[2];
+        v[2] = v[1];
+        v[1] = v[0];
+        v[0] = temp1.wrapping_add(temp2);
+    }
+    for i in 0..8 {
+        state[i] = state[i].wrapping_add(v[i]);
+    }
+}
+
+// The DTHE engines. Each use of them starts with a reset, so that a hash
+// dropped before its end doesn't leave the SHAMD5 engine waiting for data.
+#[cfg(target_arch = "arm")]
+mod engine {
+    use core::sync::atomic::{AtomicBool, AtomicUsize, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT,
+                             Ordering};
+
+    use cc3200_sys;
+
+    use error::{Error, ErrorKind};
+
+    use super::{soft_crc32_update, Algorithm, BLOCK_LEN, MAX_DIGEST_LEN};
+
+    const DTHE_BASE: u32 = 0x44030000;
+    const SHAMD5_BASE: u32 = 0x44035000;
+
+    // From sdk/driverlib/prcm.h
+    const PRCM_DTHE: u32 = 0x00000012;
+    const PRCM_RUN_MODE_CLK: u32 = 0x00000001;
+
+    // From sdk/driverlib/shamd5.h
+    const SHAMD5_ALGO_MD5: u32 = 0x00000018;
+    const SHAMD5_ALGO_SHA1: u32 = 0x0000001a;
+    const SHAMD5_ALGO_SHA224: u32 = 0x0000001c;
+    const SHAMD5_ALGO_SHA256: u32 = 0x0000001e;
+    const SHAMD5_ALGO_HMAC_MD5: u32 = 0x00000000;
+    const SHAMD5_ALGO_HMAC_SHA1: u32 = 0x00000002;
+    const SHAMD5_ALGO_HMAC_SHA224: u32 = 0x00000004;
+    const SHAMD5_ALGO_HMAC_SHA256: u32 = 0x00000006;
+    const SHAMD5_INT_CONTEXT_READY: u32 = 0x00000008;
+    const SHAMD5_INT_INPUT_READY: u32 = 0x00000002;
+    const SHAMD5_INT_OUTPUT_READY: u32 = 0x00000001;
+
+    // From sdk/driverlib/crc.h
+    const CRC_CFG_INIT_SEED: u32 = 0x00000000;
+    const CRC_CFG_SIZE_8BIT: u32 = 0x00001000;
+    const CRC_CFG_IBR: u32 = 0x00000080;
+    const CRC_CFG_TYPE_P4C11DB7: u32 = 0x00000002;
+
+    // The polls of the SHAMD5 status before giving up, many times what a
+    // block takes.
+    const WAIT_LOOPS: u32 = 100000;
+
+    // CRC_STATE is 0 until the CRC engine is checked.
+    const CRC_WORKS: usize = 1;
+    const CRC_BROKEN: usize = 2;
+
+    static DTHE_BUSY: AtomicBool = ATOMIC_BOOL_INIT;
+    // Set once the SHAMD5 engine timed out, after which it isn't used.
+    static SHAMD5_FAILED: AtomicBool = ATOMIC_BOOL_INIT;
+    static CRC_STATE: AtomicUsize = ATOMIC_USIZE_INIT;
+
+    // Takes the DTHE module if it is free.
+    fn acquire() -> bool {
+        if DTHE_BUSY.compare_and_swap(false, true, Ordering::Acquire) {
+            return false;
+        }
+        unsafe {
+            cc3200_sys::PRCMPeripheralClkEnable(PRCM_DTHE, PRCM_RUN_MODE_CLK);
+            cc3200_sys::PRCMPeripheralReset(PRCM_DTHE);
+        }
+        true
+    }
+
+    fn release() {
+        DTHE_BUSY.store(false, Ordering::Release);
+    }
+
+    // Returns false if `flag` isn't raised in time.
+    fn wait_for(flag: u32) -> bool {
+        for _ in 0..WAIT_LOOPS {
+            if unsafe { cc3200_sys::SHAMD5IntStatus(SHAMD5_BASE, false) } & flag != 0 {
+                return true;
+            }
+        }
+        SHAMD5_FAILED.store(true, Ordering::Relaxed);
+        false
+    }
+
+    fn timed_out() -> Error {
+        Error::new(ErrorKind::TimedOut, "the SHAMD5 engine timed out")
+    }
+
+    pub struct Shamd5 {
+        // The bytes still to hash.
+        remaining: usize,
+        block: [u8; BLOCK_LEN],
+        block_len: usize,
+        // False once more bytes than announced were given.
+        complete: bool,
+        timed_out: bool,
+    }
+
+    impl Shamd5 {
+        /// Starts hashing a message of `len` bytes, with the HMAC of `key`
+        /// if given. Returns None if the engine is busy or failed.
+        pub fn acquire(algorithm: Algorithm,
+                       key: Option<&[u8; BLOCK_LEN]>,
+                       len: usize)
+                       -> Option<Shamd5> {
+            if SHAMD5_FAILED.load(Ordering::Relaxed) || !acquire() {
+                return None;
+            }
+            let mode = match (algorithm, key.is_some()) {
+                (Algorithm::Md5, false) => SHAMD5_ALGO_MD5,
+                (Algorithm::Sha1, false) => SHAMD5_ALGO_SHA1,
+                (Algorithm::Sha224, false) => SHAMD5_ALGO_SHA224,
+                (Algorithm::Sha256, false) => SHAMD5_ALGO_SHA256,
+                (Algorithm::Md5, true) => SHAMD5_ALGO_HMAC_MD5,
+                (Algorithm::Sha1, true) => SHAMD5_ALGO_HMAC_SHA1,
+                (Algorithm::Sha224, true) => SHAMD5_ALGO_HMAC_SHA224,
+                (Algorithm::Sha256, true) => SHAMD5_ALGO_HMAC_SHA256,
+            };
+            if !wait_for(SHAMD5_INT_CONTEXT_READY) {
+                release();
+                return None;
+            }
+            unsafe {
+                cc3200_sys::SHAMD5ConfigSet(SHAMD5_BASE, mode);
+                if let Some(key) = key {
+                    cc3200_sys::SHAMD5HMACKeySet(SHAMD5_BASE, key.as_ptr() as *mut u8);
+                }
+                cc3200_sys::SHAMD5DataLengthSet(SHAMD5_BASE, len as u32);
+            }
+            Some(Shamd5 {
+                remaining: len,
+                block: [0; BLOCK_LEN],
+                block_len: 0,
+                complete: true,
+                timed_out: false,
+            })
+        }
+
+        pub fn update(&mut self, mut data: &[u8]) {
+            if data.len() > self.remaining {
+                self.complete = false;
+                data = &data[..self.remaining];
+            }
+            self.remaining -= data.len();
+            while !data.is_empty() {
+                let len = ::core::cmp::min(BLOCK_LEN - self.block_len, data.len());
+                self.block[self.block_len..self.block_len + len].copy_from_slice(&data[..len]);
+                self.block_len += len;
+                data = &data[len..];
+                if self.block_len == BLOCK_LEN {
+                    self.write_block();
+                }
+            }
+        }
+
+        // Writes the buffered block. The engine ignores what follows the
+        // announced length in the last one. SHAMD5DataWrite waits for the
+        // input without a bound, so it is only called once it is ready.
+        fn write_block(&mut self) {
+            for byte in self.block[self.block_len..].iter_mut() {
+                *byte = 0;
+            }
+            if !self.timed_out && wait_for(SHAMD5_INT_INPUT_READY) {
+                unsafe {
+                    cc3200_sys::SHAMD5DataWrite(SHAMD5_BASE, self.block.as_mut_ptr());
+                }
+            } else {
+                self.timed_out = true;
+            }
+            self.block_len = 0;
+        }
+
+        /// Writes the digest to `out`, and returns false if the message
+        /// didn't have the announced length.
+        pub fn finish(&mut self, out: &mut [u8; MAX_DIGEST_LEN]) -> Result<bool, Error> {
+            if self.remaining > 0 {
+                return Ok(false);
+            }
+            if self.block_len > 0 {
+                self.write_block();
+            }
+            if self.timed_out || !wait_for(SHAMD5_INT_OUTPUT_READY) {
+                return Err(timed_out());
+            }
+            unsafe {
+                cc3200_sys::SHAMD5ResultRead(SHAMD5_BASE, out.as_mut_ptr());
+            }
+            Ok(self.complete)
+        }
+    }
+
+    impl Drop for Shamd5 {
+        fn drop(&mut self) {
+            release();
+        }
+    }
+
+    /// Returns the reflected CRC32 register after hashing `data`, or None if
+    /// the engine is busy.
+    pub fn crc32_update(state: u32, data: &[u8]) -> Option<u32> {
+        if !acquire() {
+            return None;
+        }
+        // The engine shifts left, so it works on the register reversed, and
+        // reverses the input bits to match.
+        let config = CRC_CFG_INIT_SEED | CRC_CFG_TYPE_P4C11DB7 | CRC_CFG_SIZE_8BIT | CRC_CFG_IBR;
+        let result = unsafe {
+            cc3200_sys::CRCConfigSet(DTHE_BASE, config);
+            cc3200_sys::CRCSeedSet(DTHE_BASE, reverse_bits(state));
+            cc3200_sys::CRCDataProcess(DTHE_BASE,
+                                       data.as_ptr() as *mut u8,
+                                       data.len() as u32,
+                                       config)
+        };
+        release();
+        Some(reverse_bits(result))
+    }
+
+    /// Checks once that the CRC engine gives the software results, from the
+    /// initial register and from another one. Returns false if it doesn't,
+    /// or while the engine is busy for the check.
+    pub fn crc32_works() -> bool {
+        match CRC_STATE.load(Ordering::Relaxed) {
+            CRC_WORKS => return true,
+            CRC_BROKEN => return false,
+            _ => {}
+        }
+        let first = match crc32_update(0xffffffff, b"1234") {
+            Some(state) => state,
+            None => return false,
+        };
+        let second = match crc32_update(first, b"56789") {
+            Some(state) => state,
+            None => return false,
+        };
+        let expected = soft_crc32_update(0xffffffff, b"1234");
+        let works = first == expected && second == soft_crc32_update(expected, b"56789");
+        CRC_STATE.store(if works { CRC_WORKS } else { CRC_BROKEN }, Ordering::Relaxed);
+        works
+    }
+
+    fn reverse_bits(mut value: u32) -> u32 {
+        let mut reversed = 0;
+        for _ in 0..32 {
+            reversed = (reversed << 1) | (value & 1);
+            value >>= 1;
+        }
+        reversed
+    }
+}
@@ -3,6 +3,7 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use cc3200::I2C;
+use error::{Error, ErrorKind};
 
 pub struct I2CDevice {
     pub i2c: I2C,
@@ -68,24 +69,18 @@ impl I2CDevice {
 
     // Retrieves a value from the register at addr.
     // Supported value types are 8 & 16 bits signed or unsigned integers.
-    pub fn get_register_value<T: Converter>(&self, addr: u8) -> Result<T, ()> {
+    pub fn get_register_value<T: Converter>(&self, addr: u8) -> Result<T, Error> {
         use core::intrinsics::size_of;
 
         let inp: [u8; 1] = [addr];
         let mut out: I2cArray = [0; 2];
 
         let type_size = unsafe { size_of::<T>() };
-        match self.i2c.read_from_with_length(self.dev_addr, &inp, &mut out, type_size as u8) {
-            Ok(_) => {
-                match type_size {
-                    1 => return Ok(T::convert8(out)),
-                    2 => return Ok(T::convert16(out)),
-                    _ => return Err(()),
-                }
-            }
-            Err(_) => {
-                return Err(());
-            }
+        try!(self.i2c.read_from_with_length(self.dev_addr, &inp, &mut out, type_size as u8));
+        match type_size {
+            1 => return Ok(T::convert8(out)),
+            2 => return Ok(T::convert16(out)),
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "unsupported register size")),
         }
     }
 }
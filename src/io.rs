@@ -7,16 +7,25 @@ extern crate cc3200_sys;
 use collections::String;
 use collections::Vec;
 
-use core::convert::TryFrom;
+use core::cmp;
+use core::fmt;
 use core::ptr;
+use core::str;
 
 use self::cc3200_sys::simplelink::*;
+use error::{DataError, Error, ErrorKind};
+use numeric_utils::{read_le, write_le};
+use rtc::RTC;
+use simplelink::SimpleLink;
+use time::Seconds;
 
+// The file system is served by the NWP, which has to be running.
 macro_rules! try_fs {
     ($e:expr) => ({
+        try!(SimpleLink::check_started());
         let rc: i32 = unsafe { $e };
         if rc < 0 {
-            return Err(SimpleLinkError::FileSystem(try!(FileSystemError::try_from(rc))));
+            return Err(SimpleLinkError::from_fs_code(rc).into());
         }
         rc
     })
@@ -26,11 +35,27 @@ macro_rules! try_fs {
 // I/O traits
 //
 
+// Size of the buffers used by BufReader, BufWriter and copy. Memory is
+// scarce, so this is much smaller than on a desktop.
+const DEFAULT_BUF_SIZE: usize = 256;
+
 pub trait Read {
-    fn read(&mut self, buf: &mut[u8]) -> Result<usize, SimpleLinkError>;
-    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, SimpleLinkError>;
+    fn read(&mut self, buf: &mut[u8]) -> Result<usize, Error>;
 
-    fn read_to_string(&mut self, str: &mut String) -> Result<usize, SimpleLinkError> {
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let mut chunk = [0; 64];
+        let mut len = 0;
+        loop {
+            let readlen = try!(self.read(&mut chunk));
+            if readlen == 0 {
+                return Ok(len);
+            }
+            buf.extend_from_slice(&chunk[..readlen]);
+            len += readlen;
+        }
+    }
+
+    fn read_to_string(&mut self, str: &mut String) -> Result<usize, Error> {
         let mut buf: Vec<u8> = Vec::new();
         let len = self.read_to_end(&mut buf)?;
 
@@ -40,75 +65,442 @@ pub trait Read {
                 str.push_str(&res);
             },
             Err(_) => {
-                return Result::Err(SimpleLinkError::FileSystem(FileSystemError::NOT_SUPPORTED))
+                return Err(Error::new(ErrorKind::InvalidData, "the data isn't valid UTF-8"))
             },
         }
 
         Ok(len)
     }
+
+    /// Reads exactly `buf.len()` bytes, failing if the end of the data is
+    /// reached first.
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), Error> {
+        while !buf.is_empty() {
+            let len = try!(self.read(buf));
+            if len == 0 {
+                return Err(Error::new(ErrorKind::Io, "unexpected end of data"));
+            }
+            let tmp = buf;
+            buf = &mut tmp[len..];
+        }
+        Ok(())
+    }
 }
 
 pub trait Write {
-    fn write(&mut self, buf: &[u8]) -> Result<usize, SimpleLinkError>;
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error>;
+
+    /// Writes out any buffered data.
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Writes all of `buf`, failing if the writer stops accepting data.
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<(), Error> {
+        while !buf.is_empty() {
+            let len = try!(self.write(buf));
+            if len == 0 {
+                return Err(Error::new(ErrorKind::Io, "failed to write the whole buffer"));
+            }
+            buf = &buf[len..];
+        }
+        Ok(())
+    }
+
+    /// Writes formatted text, so that the `write!` and `writeln!` macros can
+    /// be used on any writer.
+    fn write_fmt(&mut self, args: fmt::Arguments) -> Result<(), Error> {
+        // Keeps the I/O error, which fmt::Error can't carry.
+        struct Adapter<'a, T: ?Sized + 'a> {
+            inner: &'a mut T,
+            error: Option<Error>,
+        }
+
+        impl<'a, T: Write + ?Sized> fmt::Write for Adapter<'a, T> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                match self.inner.write_all(s.as_bytes()) {
+                    Ok(()) => Ok(()),
+                    Err(err) => {
+                        self.error = Some(err);
+                        Err(fmt::Error)
+                    }
+                }
+            }
+        }
+
+        let mut adapter = Adapter {
+            inner: self,
+            error: None,
+        };
+        match fmt::write(&mut adapter, args) {
+            Ok(()) => Ok(()),
+            Err(_) => {
+                Err(adapter.error.unwrap_or_else(|| Error::new(ErrorKind::Io, "formatter error")))
+            }
+        }
+    }
+}
+
+/// A reader with an internal buffer, which allows reading lines.
+pub trait BufRead: Read {
+    /// Returns the buffered data, reading more if the buffer is empty. An
+    /// empty slice means the end of the data was reached.
+    fn fill_buf(&mut self) -> Result<&[u8], Error>;
+
+    /// Marks `amt` bytes of the buffer as read.
+    fn consume(&mut self, amt: usize);
+
+    /// Appends the bytes up to and including the next `byte` to `buf`, and
+    /// returns how many were appended.
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let mut len = 0;
+        loop {
+            let (done, used) = {
+                let available = try!(self.fill_buf());
+                match available.iter().position(|b| *b == byte) {
+                    Some(pos) => {
+                        buf.extend_from_slice(&available[..pos + 1]);
+                        (true, pos + 1)
+                    }
+                    None => {
+                        buf.extend_from_slice(available);
+                        (available.is_empty(), available.len())
+                    }
+                }
+            };
+            self.consume(used);
+            len += used;
+            if done {
+                return Ok(len);
+            }
+        }
+    }
+
+    /// Appends the next line, including its line feed, to `buf`. A line
+    /// that isn't UTF-8 is consumed and fails with `InvalidData`, leaving
+    /// `buf` unchanged.
+    fn read_line(&mut self, buf: &mut String) -> Result<usize, Error> {
+        let mut bytes = Vec::new();
+        let len = try!(self.read_until(b'\n', &mut bytes));
+        match str::from_utf8(&bytes) {
+            Ok(line) => buf.push_str(line),
+            Err(_) => {
+                return Err(Error::new(ErrorKind::InvalidData, "the line isn't valid UTF-8"))
+            }
+        }
+        Ok(len)
+    }
+
+    /// Returns an iterator over the lines, without their "\n" or "\r\n".
+    fn lines(self) -> Lines<Self>
+        where Self: Sized
+    {
+        Lines { reader: self }
+    }
+}
+
+pub struct Lines<B> {
+    reader: B,
+}
+
+impl<B: BufRead> Iterator for Lines<B> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Result<String, Error>> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some(Ok(line))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Reads from memory, advancing the slice past the data read.
+impl<'a> Read for &'a [u8] {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = cmp::min(buf.len(), self.len());
+        let (data, rest) = self.split_at(len);
+        buf[..len].copy_from_slice(data);
+        *self = rest;
+        Ok(len)
+    }
+}
+
+/// Appends the data, eg. to collect the content of files in memory.
+impl Write for Vec<u8> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+}
+
+/// Copies everything `reader` returns into `writer`, and returns the number
+/// of bytes copied.
+pub fn copy<R, W>(reader: &mut R, writer: &mut W) -> Result<u64, Error>
+    where R: Read + ?Sized,
+          W: Write + ?Sized
+{
+    let mut buf = [0; DEFAULT_BUF_SIZE];
+    let mut copied = 0;
+    loop {
+        let len = try!(reader.read(&mut buf));
+        if len == 0 {
+            return Ok(copied);
+        }
+        try!(writer.write_all(&buf[..len]));
+        copied += len as u64;
+    }
+}
+
+/// The position to seek to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekFrom {
+    /// From the start of the file.
+    Start(u64),
+    /// From the current position.
+    Current(i64),
+    /// From the end of the file's content.
+    End(i64),
 }
 
 pub trait Seek {
-    fn seek(&mut self, pos: u64) -> Result<u64, SimpleLinkError>;
+    /// Moves to `pos` and returns the new offset from the start of the file.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error>;
 }
 
 //
 // User files
 //
 
+/// The flags a file is created with.
+///
+/// Secure files are encrypted by the NWP and can't be read back over JTAG.
+/// They must be failsafe, and unless they are public, need the token
+/// returned by `File::token` to be opened or removed. Unless
+/// `no_signature_test` is set, they must be closed with `close_signed`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileFlags {
+    pub failsafe: bool,
+    pub secure: bool,
+    pub no_signature_test: bool,
+    pub static_token: bool,
+    pub vendor_token: bool,
+    pub public_write: bool,
+    pub public_read: bool,
+}
+
+impl FileFlags {
+    pub fn bits(&self) -> u32 {
+        let mut bits = 0;
+        if self.failsafe { bits |= FS_FILE_OPEN_FLAG_COMMIT; }
+        if self.secure { bits |= FS_FILE_OPEN_FLAG_SECURE; }
+        if self.no_signature_test { bits |= FS_FILE_OPEN_FLAG_NO_SIGNATURE_TEST; }
+        if self.static_token { bits |= FS_FILE_OPEN_FLAG_STATIC; }
+        if self.vendor_token { bits |= FS_FILE_OPEN_FLAG_VENDOR; }
+        if self.public_write { bits |= FS_FILE_PUBLIC_WRITE; }
+        if self.public_read { bits |= FS_FILE_PUBLIC_READ; }
+        bits
+    }
+
+    /// Decodes the `flags` of an `SlFsFileInfo`.
+    pub fn from_bits(bits: u16) -> FileFlags {
+        let bits = bits as u32;
+        FileFlags {
+            failsafe: (bits & FS_FILE_OPEN_FLAG_COMMIT) != 0,
+            secure: (bits & FS_FILE_OPEN_FLAG_SECURE) != 0,
+            no_signature_test: (bits & FS_FILE_OPEN_FLAG_NO_SIGNATURE_TEST) != 0,
+            static_token: (bits & FS_FILE_OPEN_FLAG_STATIC) != 0,
+            vendor_token: (bits & FS_FILE_OPEN_FLAG_VENDOR) != 0,
+            public_write: (bits & FS_FILE_PUBLIC_WRITE) != 0,
+            public_read: (bits & FS_FILE_PUBLIC_READ) != 0,
+        }
+    }
+}
+
 pub struct File {
     offset: usize,
+    // -1 once the file was closed explicitly.
     file_handle: i32,
+    name: String,
+    write: bool,
+    // The size allocated when the file was created.
+    capacity: usize,
+    // The end of the data written through this handle. Opening a file for
+    // writing discards its content, so this is its length.
+    written_len: usize,
+    // The token used to open the file, or the one the NWP returned when
+    // creating a secure file.
+    token: u32,
 }
 
 impl File {
     /// Returns a file's info structure from the file system
-    pub fn get_info(file_name: &str) -> Result<SlFsFileInfo, SimpleLinkError> {
+    pub fn get_info(file_name: &str) -> Result<SlFsFileInfo, Error> {
+        File::get_info_with_token(file_name, 0)
+    }
+
+    /// Returns the info structure of a secure file
+    pub fn get_info_with_token(file_name: &str, token: u32) -> Result<SlFsFileInfo, Error> {
         let mut file_info = SlFsFileInfo {
             flags : 0,
             file_length : 0,
             allocated_length : 0,
             token : [0; 4]
         };
-        try_fs!(sl_FsGetInfo(File::to_cstr(file_name).as_ptr(), 0, &mut file_info) as i32);
+        try_fs!(sl_FsGetInfo(File::to_cstr(file_name).as_ptr(), token, &mut file_info) as i32);
         Ok(file_info)
     }
 
     /// Removes a file fro the file system
-    pub fn remove(file_name: &str) -> Result<(), SimpleLinkError> {
-        try_fs!(sl_FsDel(File::to_cstr(file_name).as_ptr(), 0) as i32);
+    pub fn remove(file_name: &str) -> Result<(), Error> {
+        File::remove_with_token(file_name, 0)
+    }
+
+    /// Removes a secure file from the file system
+    pub fn remove_with_token(file_name: &str, token: u32) -> Result<(), Error> {
+        try_fs!(sl_FsDel(File::to_cstr(file_name).as_ptr(), token) as i32);
         Ok(())
     }
 
     /// Opens a file for reading
-    pub fn open(file_name: &str) -> Result<File, SimpleLinkError> {
-        File::open_with_mode(file_name, File::mode(false, false, false, 0))
+    pub fn open(file_name: &str) -> Result<File, Error> {
+        File::open_with_mode(file_name, File::mode(false, false, 0, 0), false, None)
+    }
+
+    /// Opens a secure file for reading
+    pub fn open_with_token(file_name: &str, token: u32) -> Result<File, Error> {
+        File::open_with_mode(file_name, File::mode(false, false, 0, 0), false, Some(token))
     }
 
     /// Opens a file for writing; possibly creating it in the process
-    pub fn create(file_name: &str, max_len: usize, failsafe: bool) -> Result<File, SimpleLinkError> {
-        File::open_with_mode(file_name, File::mode(true, true, failsafe, max_len as u32))
+    pub fn create(file_name: &str, max_len: usize, failsafe: bool) -> Result<File, Error> {
+        let flags = FileFlags { failsafe: failsafe, ..FileFlags::default() };
+        File::create_with_flags(file_name, max_len, flags, None)
+    }
+
+    /// Opens a file for writing, creating it with `flags` if it doesn't
+    /// exist. `token` is needed to rewrite an existing secure file; the
+    /// token of a new secure file is returned by `token`.
+    pub fn create_with_flags(file_name: &str,
+                             max_len: usize,
+                             flags: FileFlags,
+                             token: Option<u32>)
+                             -> Result<File, Error> {
+        let token = match token {
+            None if flags.secure => Some(0),
+            token => token,
+        };
+        File::open_with_mode(file_name,
+                             File::mode(true, true, flags.bits(), max_len as u32),
+                             true,
+                             token)
+    }
+
+    /// Returns the token of a secure file.
+    pub fn token(&self) -> u32 {
+        self.token
+    }
+
+    /// Returns the length of the file's content. For a file opened for
+    /// writing, this is the length of what was written so far.
+    pub fn len(&self) -> Result<u64, Error> {
+        if self.write {
+            return Ok(self.written_len as u64);
+        }
+        let file_info = try!(File::get_info_with_token(&self.name, self.token));
+        Ok(file_info.file_length as u64)
+    }
+
+    /// Returns the size allocated to the file, which its content can't
+    /// grow past.
+    pub fn capacity(&self) -> u64 {
+        self.capacity as u64
+    }
+
+    /// Closes the file, reporting the errors that dropping it ignores.
+    pub fn close(self) -> Result<(), Error> {
+        self.close_with(None, &[])
+    }
+
+    /// Closes a secure file written with the signature test enabled. The NWP
+    /// checks `signature` against the certificate stored in the file
+    /// `certificate_file_name`, and drops the changes if it doesn't match.
+    pub fn close_signed(self,
+                        signature: &[u8],
+                        certificate_file_name: &str)
+                        -> Result<(), Error> {
+        self.close_with(Some(certificate_file_name), signature)
+    }
+
+    /// Closes a failsafe file, dropping the changes made since it was
+    /// opened.
+    pub fn abort(self) -> Result<(), Error> {
+        self.close_with(None, FS_ABORT_SIGNATURE)
     }
 
     // Returns the file-open mode
-    fn mode(write: bool, create: bool, failsafe: bool, max_size: u32) -> u32 {
-        unsafe { sl_FsMode(write, create, failsafe, max_size) }
+    fn mode(write: bool, create: bool, flags: u32, max_size: u32) -> u32 {
+        unsafe { sl_FsModeWithFlags(write, create, flags, max_size) }
     }
 
     // Open file with the specified mode
-    fn open_with_mode(file_name: &str, mode: u32) -> Result<File, SimpleLinkError> {
+    fn open_with_mode(file_name: &str,
+                      mode: u32,
+                      write: bool,
+                      token: Option<u32>)
+                      -> Result<File, Error> {
 
         let mut file_handle = -1 as i32;
-        try_fs!(sl_FsOpen(File::to_cstr(file_name).as_ptr(), mode, ptr::null(), &mut file_handle));
-        Ok(File { offset: 0, file_handle: file_handle })
+        let mut token_value = token.unwrap_or(0);
+        let token_ptr = match token {
+            Some(_) => &mut token_value as *mut u32,
+            None => ptr::null_mut(),
+        };
+        try_fs!(sl_FsOpen(File::to_cstr(file_name).as_ptr(), mode, token_ptr, &mut file_handle));
+        let mut file = File {
+            offset: 0,
+            file_handle: file_handle,
+            name: String::from(file_name),
+            write: write,
+            capacity: 0,
+            written_len: 0,
+            token: token_value,
+        };
+        file.capacity = try!(File::get_info_with_token(file_name, token_value))
+            .allocated_length as usize;
+        Ok(file)
+    }
+
+    fn close_with(mut self,
+                  certificate_file_name: Option<&str>,
+                  signature: &[u8])
+                  -> Result<(), Error> {
+        let file_handle = self.file_handle;
+        self.file_handle = -1;
+        let certificate_file_name = certificate_file_name.map(File::to_cstr);
+        let certificate_ptr = match certificate_file_name {
+            Some(ref name) => name.as_ptr(),
+            None => ptr::null(),
+        };
+        let signature_ptr = if signature.is_empty() { ptr::null() } else { signature.as_ptr() };
+        try_fs!(sl_FsClose(file_handle,
+                           certificate_ptr,
+                           signature_ptr,
+                           signature.len() as u32) as i32);
+        Ok(())
     }
 
     // Read at specific offset
-    fn read_at(&self, buf: &mut[u8], offset: usize) -> Result<usize, SimpleLinkError> {
+    fn read_at(&self, buf: &mut[u8], offset: usize) -> Result<usize, Error> {
         Ok(try_fs!(sl_FsRead(self.file_handle,
                              offset as u32,
                              buf.as_mut_ptr(),
@@ -116,7 +508,7 @@ impl File {
     }
 
     // Write at specific offset
-    fn write_at(&self, buf: &[u8], offset: usize) -> Result<usize, SimpleLinkError> {
+    fn write_at(&self, buf: &[u8], offset: usize) -> Result<usize, Error> {
         Ok(try_fs!(sl_FsWrite(self.file_handle,
                               offset as u32,
                               buf.as_ptr(),
@@ -135,20 +527,22 @@ impl File {
 
 impl Drop for File {
     fn drop(&mut self) {
-        unsafe {
-            sl_FsClose(self.file_handle, ptr::null(), ptr::null(), 0);
+        if self.file_handle >= 0 {
+            unsafe {
+                sl_FsClose(self.file_handle, ptr::null(), ptr::null(), 0);
+            }
         }
     }
 }
 
 impl Read for File {
-    fn read(&mut self, buf: &mut[u8]) -> Result<usize, SimpleLinkError> {
+    fn read(&mut self, buf: &mut[u8]) -> Result<usize, Error> {
         let len = try!(self.read_at(buf, self.offset));
         self.offset += len;
         Ok(len)
     }
 
-    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, SimpleLinkError> {
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
 
         let incr_buffer_size = 64;
 
@@ -179,16 +573,553 @@ impl Read for File {
 }
 
 impl Write for File {
-    fn write(&mut self, buf: &[u8]) -> Result<usize, SimpleLinkError> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
         let len = try!(self.write_at(buf, self.offset));
         self.offset += len;
+        self.written_len = cmp::max(self.written_len, self.offset);
         Ok(len)
     }
 }
 
 impl Seek for File {
-    fn seek(&mut self, pos: u64) -> Result<u64, SimpleLinkError> {
-        self.offset = pos as usize;
-        Ok(self.offset as u64)
+    // Positions past the capacity of the file are refused, since the file
+    // can't grow there.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(delta) => (self.offset as i64, delta),
+            SeekFrom::End(delta) => (try!(self.len()) as i64, delta),
+        };
+        let offset = match base.checked_add(delta) {
+            Some(offset) if offset >= 0 => offset as u64,
+            _ => return Err(FileSystemError::INVALID_ARGS.into()),
+        };
+        if offset > self.capacity() {
+            return Err(FileSystemError::OFFSET_OUT_OF_RANGE.into());
+        }
+        self.offset = offset as usize;
+        Ok(offset)
+    }
+}
+
+//
+// Buffering
+//
+
+/// Buffers the reads of `inner`, so that small reads don't each cost a
+/// round-trip to the NWP.
+pub struct BufReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    filled: usize,
+}
+
+impl<R: Read> BufReader<R> {
+    pub fn new(inner: R) -> BufReader<R> {
+        BufReader::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: R) -> BufReader<R> {
+        BufReader {
+            inner: inner,
+            buf: vec![0; capacity],
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
     }
+
+    /// Returns the inner reader. Reading from it directly loses the data
+    /// already buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for BufReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // Large reads bypass the empty buffer.
+        if self.pos == self.filled && buf.len() >= self.buf.len() {
+            return self.inner.read(buf);
+        }
+        let len = {
+            let available = try!(self.fill_buf());
+            let len = cmp::min(available.len(), buf.len());
+            buf[..len].copy_from_slice(&available[..len]);
+            len
+        };
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: Read> BufRead for BufReader<R> {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        if self.pos == self.filled {
+            self.filled = try!(self.inner.read(&mut self.buf));
+            self.pos = 0;
+        }
+        Ok(&self.buf[self.pos..self.filled])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = cmp::min(self.pos + amt, self.filled);
+    }
+}
+
+impl<R: Read + Seek> Seek for BufReader<R> {
+    // The buffer is dropped, and relative seeks account for the data read
+    // ahead by the buffer.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        let pos = match pos {
+            SeekFrom::Current(delta) => SeekFrom::Current(delta - (self.filled - self.pos) as i64),
+            pos => pos,
+        };
+        let offset = try!(self.inner.seek(pos));
+        self.pos = 0;
+        self.filled = 0;
+        Ok(offset)
+    }
+}
+
+/// Buffers the writes to `inner`. The buffer is flushed when it is full,
+/// on `flush` and when the `BufWriter` is dropped; errors are lost in the
+/// latter case, so call `flush` to check them.
+pub struct BufWriter<W: Write> {
+    inner: Option<W>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> BufWriter<W> {
+    pub fn new(inner: W) -> BufWriter<W> {
+        BufWriter::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: W) -> BufWriter<W> {
+        BufWriter {
+            inner: Some(inner),
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Returns the inner writer. Writing to it directly bypasses the data
+    /// still buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Flushes the buffer and returns the inner writer.
+    pub fn into_inner(mut self) -> Result<W, Error> {
+        try!(self.flush_buf());
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn flush_buf(&mut self) -> Result<(), Error> {
+        if !self.buf.is_empty() {
+            try!(self.inner.as_mut().unwrap().write_all(&self.buf));
+            self.buf.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for BufWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if self.buf.len() + buf.len() > self.buf.capacity() {
+            try!(self.flush_buf());
+        }
+        // Large writes bypass the empty buffer.
+        if buf.len() >= self.buf.capacity() {
+            return self.inner.as_mut().unwrap().write(buf);
+        }
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        try!(self.flush_buf());
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for BufWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.flush_buf();
+        }
+    }
+}
+
+//
+// File catalog
+//
+// The file system can't list its files, so the catalog keeps a record of
+// the files created through it, in a failsafe file of its own:
+//
+//   magic "CAT1" | entry count u16
+//
+// followed by the entries, all little endian:
+//
+//   name length u8 | name | length u32 | capacity u32 | flags u16
+//   | timestamp i64
+//
+// An entry is saved before its file is created, and updated once the file
+// was closed, so a power loss can only leave entries for files that were
+// never created, which `gc` drops, and never a file the catalog doesn't
+// know. The length of a failsafe file is only recorded once its new
+// content is committed.
+//
+// The settings, the log files and the update images are recorded in the
+// default catalog, `CATALOG_FILE`, through `Catalog::with_default`.
+
+/// The file used by `Catalog::open_default`.
+pub const CATALOG_FILE: &'static str = "/sys/catalog.bin";
+
+/// The size allocated to `CATALOG_FILE`.
+pub const CATALOG_MAX_LEN: usize = 4096;
+
+const CATALOG_MAGIC: &'static [u8; 4] = b"CAT1";
+const CATALOG_HEADER_LEN: usize = 6;
+// The fixed size part of an entry, after the name.
+const CATALOG_ENTRY_LEN: usize = 18;
+// The name length is saved in a byte.
+const CATALOG_MAX_NAME_LEN: usize = 0xff;
+
+fn name_too_long() -> Error {
+    Error::new(ErrorKind::InvalidInput, "the file name is too long for the catalog")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogEntry {
+    pub name: String,
+    /// The length of the content when the file was last closed through the
+    /// catalog, or checked by `gc`.
+    pub len: u64,
+    pub capacity: u64,
+    pub flags: FileFlags,
+    /// The RTC time of the last change.
+    pub timestamp: Seconds,
+}
+
+/// The space used by the files of a catalog.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub files: usize,
+    /// The total length of their content.
+    pub len: u64,
+    /// The total size allocated to them, which is what they take in flash.
+    pub capacity: u64,
+}
+
+pub struct Catalog {
+    file_name: String,
+    max_len: usize,
+    entries: Vec<CatalogEntry>,
+}
+
+impl Catalog {
+    /// Loads the catalog saved in `file_name`, or returns an empty one if
+    /// the file doesn't exist.
+    pub fn open(file_name: &str, max_len: usize) -> Result<Catalog, Error> {
+        let mut catalog = Catalog::new(file_name, max_len);
+        let mut buf = Vec::new();
+        match File::open(file_name) {
+            Ok(mut file) => {
+                try!(file.read_to_end(&mut buf));
+            }
+            Err(ref err) if err.is_fs(FileSystemError::FILE_NOT_EXISTS) => return Ok(catalog),
+            Err(err) => return Err(err),
+        }
+        catalog.entries = try!(Catalog::decode(&buf));
+        Ok(catalog)
+    }
+
+    /// Loads the catalog from `CATALOG_FILE`.
+    pub fn open_default() -> Result<Catalog, Error> {
+        Catalog::open(CATALOG_FILE, CATALOG_MAX_LEN)
+    }
+
+    /// Calls `f` with the catalog of `CATALOG_FILE`, locked so that the
+    /// changes of other tasks aren't lost. A corrupted catalog is replaced
+    /// by an empty one.
+    pub fn with_default<F, R>(f: F) -> Result<R, Error>
+        where F: FnOnce(&mut Catalog) -> Result<R, Error>
+    {
+        let _lock = try!(SimpleLink::lock_catalog());
+        let mut catalog = match Catalog::open_default() {
+            Ok(catalog) => catalog,
+            Err(ref err) if err.data_error() == Some(DataError::Corrupted) => {
+                Catalog::new(CATALOG_FILE, CATALOG_MAX_LEN)
+            }
+            Err(err) => return Err(err),
+        };
+        f(&mut catalog)
+    }
+
+    /// Records `file_name` and opens it for writing like
+    /// `File::create_with_flags`. The file must be closed with `close` for
+    /// its length to be recorded; a failsafe file dropped or aborted keeps
+    /// its previous content and length. Names longer than 255 bytes are
+    /// rejected.
+    pub fn create(&mut self,
+                  file_name: &str,
+                  max_len: usize,
+                  flags: FileFlags,
+                  token: Option<u32>)
+                  -> Result<File, Error> {
+        if file_name.len() > CATALOG_MAX_NAME_LEN {
+            return Err(name_too_long());
+        }
+        let changed = match self.position(file_name) {
+            // Opening a file for writing discards its content, unless it is
+            // failsafe.
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                if !flags.failsafe && entry.len != 0 {
+                    entry.len = 0;
+                    entry.timestamp = RTC::get();
+                    true
+                } else {
+                    false
+                }
+            }
+            None => {
+                self.entries.push(CatalogEntry {
+                    name: String::from(file_name),
+                    len: 0,
+                    capacity: max_len as u64,
+                    flags: flags,
+                    timestamp: RTC::get(),
+                });
+                true
+            }
+        };
+        if changed {
+            try!(self.save());
+        }
+        let file = try!(File::create_with_flags(file_name, max_len, flags, token));
+
+        // A file that existed keeps the size and the flags it was created
+        // with.
+        let file_info = try!(File::get_info_with_token(file_name, file.token));
+        let flags = FileFlags::from_bits(file_info.flags);
+        let index = self.position(file_name).unwrap();
+        if self.entries[index].capacity != file.capacity() || self.entries[index].flags != flags {
+            self.entries[index].capacity = file.capacity();
+            self.entries[index].flags = flags;
+            try!(self.save());
+        }
+        Ok(file)
+    }
+
+    /// Closes `file` and records its length. Files opened for writing
+    /// without the catalog are added to it.
+    pub fn close(&mut self, file: File) -> Result<(), Error> {
+        if !file.write {
+            return file.close();
+        }
+        let name = file.name.clone();
+        let len = file.written_len as u64;
+        let capacity = file.capacity();
+        let token = file.token;
+        try!(file.close());
+
+        let index = match self.position(&name) {
+            Some(index) => index,
+            None if name.len() > CATALOG_MAX_NAME_LEN => return Err(name_too_long()),
+            None => {
+                let file_info = try!(File::get_info_with_token(&name, token));
+                self.entries.push(CatalogEntry {
+                    name: name,
+                    len: 0,
+                    capacity: 0,
+                    flags: FileFlags::from_bits(file_info.flags),
+                    timestamp: 0,
+                });
+                self.entries.len() - 1
+            }
+        };
+        {
+            let entry = &mut self.entries[index];
+            entry.len = len;
+            entry.capacity = capacity;
+            entry.timestamp = RTC::get();
+        }
+        self.save()
+    }
+
+    /// Removes a file and its entry.
+    pub fn remove(&mut self, file_name: &str) -> Result<(), Error> {
+        self.remove_with_token(file_name, 0)
+    }
+
+    /// Removes a secure file and its entry.
+    pub fn remove_with_token(&mut self, file_name: &str, token: u32) -> Result<(), Error> {
+        match File::remove_with_token(file_name, token) {
+            Ok(()) => {}
+            Err(ref err) if err.is_fs(FileSystemError::FILE_NOT_EXISTS) => {}
+            Err(err) => return Err(err),
+        }
+        match self.position(file_name) {
+            Some(index) => {
+                self.entries.remove(index);
+                self.save()
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the entries, in the order the files were first created.
+    pub fn entries(&self) -> &[CatalogEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, file_name: &str) -> Option<&CatalogEntry> {
+        self.position(file_name).map(|index| &self.entries[index])
+    }
+
+    pub fn usage(&self) -> DiskUsage {
+        self.entries.iter().fold(DiskUsage::default(), |usage, entry| {
+            DiskUsage {
+                files: usage.files + 1,
+                len: usage.len + entry.len,
+                capacity: usage.capacity + entry.capacity,
+            }
+        })
+    }
+
+    /// Checks the entries against the file system: drops the ones of the
+    /// files that don't exist, eg. after a power loss while creating them,
+    /// and updates the length, capacity and flags of the others. Returns the
+    /// number of entries dropped. The secure files need `gc_with_tokens`.
+    pub fn gc(&mut self) -> Result<usize, Error> {
+        self.gc_with_tokens(|_| 0)
+    }
+
+    /// Like `gc`, checking each file with the token `token` returns for
+    /// its entry, eg. 0 for the files that aren't secure. Secure entries
+    /// checked with a wrong token are kept as they are.
+    pub fn gc_with_tokens<F>(&mut self, token: F) -> Result<usize, Error>
+        where F: Fn(&CatalogEntry) -> u32
+    {
+        let count = self.entries.len();
+        let mut entries = Vec::with_capacity(count);
+        let mut changed = false;
+        for mut entry in self.entries.drain(..) {
+            match File::get_info_with_token(&entry.name, token(&entry)) {
+                Ok(file_info) => {
+                    let len = file_info.file_length as u64;
+                    let capacity = file_info.allocated_length as u64;
+                    let flags = FileFlags::from_bits(file_info.flags);
+                    if entry.len != len || entry.capacity != capacity || entry.flags != flags {
+                        entry.len = len;
+                        entry.capacity = capacity;
+                        entry.flags = flags;
+                        changed = true;
+                    }
+                    entries.push(entry);
+                }
+                Err(ref err) if err.is_fs(FileSystemError::FILE_NOT_EXISTS) => {}
+                // Keep the entries that can't be checked.
+                Err(_) => entries.push(entry),
+            }
+        }
+        let dropped = count - entries.len();
+        self.entries = entries;
+        if dropped > 0 || changed {
+            try!(self.save());
+        }
+        Ok(dropped)
+    }
+
+    /// Writes the catalog. The file keeps its previous content until the new
+    /// one is completely written.
+    pub fn save(&self) -> Result<(), Error> {
+        let buf = self.encode();
+        let mut file = try!(File::create(&self.file_name, self.max_len, true));
+        if buf.len() as u64 > file.capacity() {
+            try!(file.abort());
+            return Err(Error::data("catalog", DataError::TooLarge));
+        }
+        try!(file.write_all(&buf));
+        file.close()
+    }
+
+    fn new(file_name: &str, max_len: usize) -> Catalog {
+        Catalog {
+            file_name: String::from(file_name),
+            max_len: max_len,
+            entries: Vec::new(),
+        }
+    }
+
+    fn position(&self, file_name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == file_name)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(CATALOG_MAGIC);
+        put_le(&mut buf, self.entries.len() as u64, 2);
+        for entry in &self.entries {
+            buf.push(entry.name.len() as u8);
+            buf.extend_from_slice(entry.name.as_bytes());
+            put_le(&mut buf, entry.len, 4);
+            put_le(&mut buf, entry.capacity, 4);
+            put_le(&mut buf, entry.flags.bits() as u64, 2);
+            put_le(&mut buf, entry.timestamp as u64, 8);
+        }
+        buf
+    }
+
+    fn decode(buf: &[u8]) -> Result<Vec<CatalogEntry>, Error> {
+        let corrupted = || Error::data("catalog", DataError::Corrupted);
+        if buf.len() < CATALOG_HEADER_LEN || &buf[..4] != CATALOG_MAGIC {
+            return Err(corrupted());
+        }
+        let count = read_le(&buf[4..6]) as usize;
+        let mut entries = Vec::with_capacity(count);
+        let mut pos = CATALOG_HEADER_LEN;
+        for _ in 0..count {
+            let name_len = *try!(buf.get(pos).ok_or_else(&corrupted)) as usize;
+            pos += 1;
+            if buf.len() < pos + name_len + CATALOG_ENTRY_LEN {
+                return Err(corrupted());
+            }
+            let name = try!(str::from_utf8(&buf[pos..pos + name_len]).map_err(|_| corrupted()));
+            let fields = &buf[pos + name_len..];
+            entries.push(CatalogEntry {
+                name: String::from(name),
+                len: read_le(&fields[..4]),
+                capacity: read_le(&fields[4..8]),
+                flags: FileFlags::from_bits(read_le(&fields[8..10]) as u16),
+                timestamp: read_le(&fields[10..18]) as Seconds,
+            });
+            pos += name_len + CATALOG_ENTRY_LEN;
+        }
+        if pos != buf.len() {
+            return Err(corrupted());
+        }
+        Ok(entries)
+    }
+}
+
+/// Appends the `len` low bytes of `value`, least significant first, as the
+/// catalog and the settings store their integers.
+pub fn put_le(buf: &mut Vec<u8>, value: u64, len: usize) {
+    let start = buf.len();
+    buf.resize(start + len, 0);
+    write_le(&mut buf[start..], value);
 }
@@ -6,6 +6,7 @@
 // those are not available in this platform.
 #![no_std]
 
+#![feature(alloc)]
 #![feature(asm, lang_items)]
 // For i2c_devices pow functions.
 #![feature(core_intrinsics)]
@@ -15,6 +16,7 @@
 // #![feature(compiler_builtins_lib)]
 // extern crate compiler_builtins;
 
+extern crate alloc;
 extern crate cc3200_sys;
 #[macro_use]
 extern crate log;
@@ -37,8 +39,23 @@ pub mod time;
 
 pub mod rtc;
 pub mod simplelink;
+mod chacha;
+pub mod config_file;
+pub mod error;
+pub mod events;
+pub mod hash;
 pub mod io;
+pub mod link_stats;
+pub mod log_file;
+pub mod net;
+pub mod p2p;
+pub mod provisioning;
+pub mod rng;
+pub mod rx_filter;
+pub mod settings;
 pub mod socket_channel;
+pub mod update;
+pub mod wifi_manager;
 
 // We need to make sure that we pull in soft float versions of libm.a, libc.a
 // and libgcc.a. The build.rs sets up the paths needed for these.
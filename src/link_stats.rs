@@ -0,0 +1,235 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Signal quality of the WLAN connection.
+//
+// `LinkStats` reads the NWP RX statistics, which are cleared on each read,
+// so every sample covers the time since the previous one. `RssiMonitor`
+// samples them periodically and reports when the average RSSI crosses a
+// threshold, so the application can roam or raise an alert.
+
+use core::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+
+use freertos_rs::{CurrentTask, Duration, Task};
+
+use error::Error;
+use simplelink::{OsiError, SimpleLink, SimpleLinkError, SlGetRxStatResponse,
+                 NUM_OF_RATE_INDEXES, SIZE_OF_RSSI_HISTOGRAM};
+
+const POLL_INTERVAL_MS: u32 = 100;
+
+/// A sample of the RX statistics.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkStats {
+    pub valid_packets: u32,
+    pub fcs_errors: u32,
+    pub address_mismatches: u32,
+    /// Average RSSI of the data and control frames, in dBm.
+    pub avg_data_rssi: i16,
+    /// Average RSSI of the management frames (eg. beacons), in dBm.
+    pub avg_mgmt_rssi: i16,
+    /// Number of valid frames received at each rate index.
+    pub rate_histogram: [u16; NUM_OF_RATE_INDEXES],
+    /// RSSI histogram from -40 to -87 dBm.
+    pub rssi_histogram: [u16; SIZE_OF_RSSI_HISTOGRAM],
+    /// How long the statistics were collected for.
+    pub duration_us: u32,
+}
+
+impl LinkStats {
+    /// Starts collecting the statistics.
+    pub fn start() -> Result<(), Error> {
+        SimpleLink::wlan_rx_stat_start()
+    }
+
+    pub fn stop() -> Result<(), Error> {
+        SimpleLink::wlan_rx_stat_stop()
+    }
+
+    /// Reads the statistics collected since the start or the previous read.
+    pub fn read() -> Result<LinkStats, Error> {
+        let stat = try!(SimpleLink::wlan_rx_stat_get());
+        Ok(LinkStats::from_response(&stat))
+    }
+
+    fn from_response(stat: &SlGetRxStatResponse) -> Self {
+        LinkStats {
+            valid_packets: stat.received_valid_packets,
+            fcs_errors: stat.received_fcs_error_packets,
+            address_mismatches: stat.received_address_mismatch_packets,
+            avg_data_rssi: stat.avg_data_ctrl_rssi,
+            avg_mgmt_rssi: stat.avg_mgmt_rssi,
+            rate_histogram: stat.rate_histogram,
+            rssi_histogram: stat.rssi_histogram,
+            duration_us: stat.get_timestamp.wrapping_sub(stat.start_timestamp),
+        }
+    }
+
+    /// The best RSSI estimate of the sample: the data frames average, or
+    /// the management frames one when no data was received. `None` if
+    /// nothing was received.
+    pub fn rssi(&self) -> Option<i16> {
+        if self.avg_data_rssi != 0 {
+            Some(self.avg_data_rssi)
+        } else if self.avg_mgmt_rssi != 0 {
+            Some(self.avg_mgmt_rssi)
+        } else {
+            None
+        }
+    }
+
+    /// Frames dropped because of an FCS error, per thousand frames received.
+    pub fn error_rate_per_mille(&self) -> u32 {
+        // In u64, as the counters of the NWP may be close to overflowing.
+        let total = self.valid_packets as u64 + self.fcs_errors as u64;
+        if total == 0 {
+            0
+        } else {
+            (self.fcs_errors as u64 * 1000 / total) as u32
+        }
+    }
+
+    /// The rate index most frames were received at.
+    pub fn most_used_rate_index(&self) -> Option<usize> {
+        let mut best = None;
+        let mut best_count = 0;
+        for (index, &count) in self.rate_histogram.iter().enumerate() {
+            if count > best_count {
+                best = Some(index);
+                best_count = count;
+            }
+        }
+        best
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RssiAlert {
+    /// The RSSI stayed below the threshold for the configured number of
+    /// samples.
+    Low { rssi: i16 },
+    /// The RSSI went back above the threshold plus the hysteresis.
+    Recovered { rssi: i16 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RssiMonitorConfig {
+    pub interval_ms: u32,
+    /// RSSI, in dBm, below which the link is considered weak.
+    pub threshold: i16,
+    /// How far above the threshold the RSSI has to go back to report a
+    /// recovery.
+    pub hysteresis: i16,
+    /// Number of consecutive weak samples before reporting.
+    pub samples: u32,
+}
+
+impl Default for RssiMonitorConfig {
+    fn default() -> Self {
+        RssiMonitorConfig {
+            interval_ms: 5000,
+            threshold: -75,
+            hysteresis: 5,
+            samples: 3,
+        }
+    }
+}
+
+static RUNNING: AtomicBool = ATOMIC_BOOL_INIT;
+static STOP_REQUESTED: AtomicBool = ATOMIC_BOOL_INIT;
+
+pub struct RssiMonitor { }
+
+impl RssiMonitor {
+    /// Starts a task collecting the statistics every `config.interval_ms`
+    /// and calling `on_alert` when the RSSI crosses the threshold. Only one
+    /// monitor can run at a time.
+    pub fn start<F>(config: RssiMonitorConfig, on_alert: F) -> Result<(), Error>
+        where F: Fn(RssiAlert) + Send + 'static
+    {
+        if RUNNING.swap(true, Ordering::SeqCst) {
+            return Err(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED).into());
+        }
+        STOP_REQUESTED.store(false, Ordering::SeqCst);
+
+        if let Err(e) = LinkStats::start() {
+            RUNNING.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+
+        let task = Task::new()
+            .name("rssi")
+            .stack_size(1024) // 32-bit words
+            .start(move || {
+                RssiMonitor::run(&config, &on_alert);
+                let _ = LinkStats::stop();
+                RUNNING.store(false, Ordering::SeqCst);
+            });
+        if task.is_err() {
+            let _ = LinkStats::stop();
+            RUNNING.store(false, Ordering::SeqCst);
+            return Err(SimpleLinkError::Osi(OsiError::OSI_MEMORY_ALLOCATION_FAILURE).into());
+        }
+        Ok(())
+    }
+
+    pub fn stop() {
+        STOP_REQUESTED.store(true, Ordering::SeqCst);
+    }
+
+    fn run<F: Fn(RssiAlert)>(config: &RssiMonitorConfig, on_alert: &F) {
+        let mut weak_samples = 0;
+        let mut alerted = false;
+
+        // Start from a clean sample.
+        let _ = LinkStats::read();
+
+        while RssiMonitor::sleep(config.interval_ms) {
+            if !SimpleLink::is_connected() {
+                weak_samples = 0;
+                continue;
+            }
+            let rssi = match LinkStats::read() {
+                Ok(stats) => {
+                    match stats.rssi() {
+                        Some(rssi) => rssi,
+                        None => continue,
+                    }
+                }
+                Err(e) => {
+                    warn!("RssiMonitor: reading the statistics failed: {}", e);
+                    continue;
+                }
+            };
+            debug!("RssiMonitor: rssi {} dBm", rssi);
+
+            if rssi < config.threshold {
+                weak_samples += 1;
+                if !alerted && weak_samples >= config.samples {
+                    alerted = true;
+                    on_alert(RssiAlert::Low { rssi: rssi });
+                }
+            } else {
+                weak_samples = 0;
+                if alerted && rssi >= config.threshold + config.hysteresis {
+                    alerted = false;
+                    on_alert(RssiAlert::Recovered { rssi: rssi });
+                }
+            }
+        }
+    }
+
+    // Sleeps for `duration_ms`. Returns false if a stop was requested.
+    fn sleep(duration_ms: u32) -> bool {
+        let mut elapsed_ms = 0;
+        while elapsed_ms < duration_ms {
+            if STOP_REQUESTED.load(Ordering::SeqCst) {
+                return false;
+            }
+            CurrentTask::delay(Duration::ms(POLL_INTERVAL_MS));
+            elapsed_ms += POLL_INTERVAL_MS;
+        }
+        !STOP_REQUESTED.load(Ordering::SeqCst)
+    }
+}
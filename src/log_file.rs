@@ -0,0 +1,201 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Log records kept on the SimpleLink file system.
+//
+// The records go to a fixed set of files, `<base>.0` to `<base>.<count - 1>`,
+// used in turn. The file system can't grow or append to a file (opening it
+// for writing discards its content), so each file is created with `max_len`
+// bytes, written until it is full, and then the next one is reused. A new
+// file is also started after every reboot, and before the files are read
+// back, so the file being written is never reopened.
+//
+// The files are not failsafe: every record is in flash as soon as it was
+// written, and a power loss only loses the record being written. Each file
+// starts with a "#log <sequence>" line, which tells the newest file after a
+// reboot. The files are recorded in the default catalog when they are
+// started and closed.
+
+use collections::{String, Vec};
+
+use core::cmp;
+use core::str;
+
+use error::Error;
+use io::{self, Catalog, File, FileFlags, Read, Write};
+use simplelink::FileSystemError;
+
+// "#log " followed by 8 hex digits and a new line.
+const HEADER_PREFIX: &'static [u8] = b"#log ";
+const HEADER_LEN: usize = 14;
+
+pub struct LogFiles {
+    base: String,
+    count: usize,
+    max_len: usize,
+    // The file being written, None until the first record and after the
+    // files were read.
+    current: Option<File>,
+    // The index and sequence number of the next file to start.
+    next_index: usize,
+    next_sequence: u32,
+    // The length and the capacity of the current file. A file created
+    // before `max_len` was changed keeps its previous size.
+    len: usize,
+    capacity: usize,
+}
+
+impl LogFiles {
+    /// Uses the files `<base>.0` to `<base>.<count - 1>`, of `max_len`
+    /// bytes each, keeping the records they hold. The next record starts a
+    /// new file.
+    pub fn open(base: &str, count: usize, max_len: usize) -> Result<LogFiles, Error> {
+        if count == 0 || max_len <= HEADER_LEN {
+            return Err(FileSystemError::INVALID_ARGS.into());
+        }
+        let mut log_files = LogFiles {
+            base: String::from(base),
+            count: count,
+            max_len: max_len,
+            current: None,
+            next_index: 0,
+            next_sequence: 0,
+            len: 0,
+            capacity: 0,
+        };
+        if let Some(&(sequence, index)) = try!(log_files.scan()).last() {
+            log_files.next_index = (index + 1) % count;
+            log_files.next_sequence = sequence.wrapping_add(1);
+        }
+        Ok(log_files)
+    }
+
+    /// Appends `record` to the current file, starting the next file when it
+    /// doesn't fit, so that records are not split. Records longer than a
+    /// file are truncated.
+    pub fn write_record(&mut self, record: &[u8]) -> Result<(), Error> {
+        if self.current.is_none() || self.len + record.len() > self.capacity {
+            try!(self.rotate());
+        }
+        try!(self.append(record));
+        Ok(())
+    }
+
+    /// Closes the current file, so that the next record goes to a new one.
+    pub fn rotate(&mut self) -> Result<(), Error> {
+        let current = self.current.take();
+        let name = self.file_name(self.next_index);
+        let max_len = self.max_len;
+        let mut file = try!(Catalog::with_default(|catalog| {
+            if let Some(file) = current {
+                try!(catalog.close(file));
+            }
+            catalog.create(&name, max_len, FileFlags::default(), None)
+        }));
+        let header = format!("#log {:08x}\n", self.next_sequence);
+        try!(file.write_all(header.as_bytes()));
+        self.capacity = cmp::min(self.max_len as u64, file.capacity()) as usize;
+        self.current = Some(file);
+        self.len = header.len();
+        self.next_index = (self.next_index + 1) % self.count;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Returns the names of the files holding records, oldest first.
+    pub fn file_names(&mut self) -> Result<Vec<String>, Error> {
+        let files = try!(self.scan());
+        Ok(files.iter().map(|&(_, index)| self.file_name(index)).collect())
+    }
+
+    /// Copies the records of all the files to `out`, oldest first, eg. to
+    /// dump them on the `Console` or upload them through a `SocketChannel`.
+    /// Returns the number of bytes copied.
+    pub fn copy_to<W: Write>(&mut self, out: &mut W) -> Result<u64, Error> {
+        let mut len = 0;
+        for name in try!(self.file_names()) {
+            let mut file = try!(File::open(&name));
+            len += try!(io::copy(&mut file, out));
+        }
+        Ok(len)
+    }
+
+    /// Removes all the files.
+    pub fn clear(&mut self) -> Result<(), Error> {
+        let current = self.current.take();
+        let names: Vec<String> = (0..self.count).map(|index| self.file_name(index)).collect();
+        try!(Catalog::with_default(|catalog| {
+            if let Some(file) = current {
+                try!(catalog.close(file));
+            }
+            for name in &names {
+                try!(catalog.remove(name));
+            }
+            Ok(())
+        }));
+        self.next_index = 0;
+        Ok(())
+    }
+
+    // Writes as much of `buf` as fits in the current file.
+    fn append(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let len = cmp::min(buf.len(), self.capacity - self.len);
+        let file = self.current.as_mut().unwrap();
+        try!(file.write_all(&buf[..len]));
+        self.len += len;
+        Ok(len)
+    }
+
+    fn file_name(&self, index: usize) -> String {
+        format!("{}.{}", self.base, index)
+    }
+
+    // Closes the current file, which can't be read while it is open, and
+    // returns the sequence number and index of the existing files, oldest
+    // first.
+    fn scan(&mut self) -> Result<Vec<(u32, usize)>, Error> {
+        if let Some(file) = self.current.take() {
+            try!(Catalog::with_default(|catalog| catalog.close(file)));
+        }
+        let mut files = Vec::new();
+        for index in 0..self.count {
+            let mut file = match File::open(&self.file_name(index)) {
+                Ok(file) => file,
+                Err(ref err) if err.is_fs(FileSystemError::FILE_NOT_EXISTS) => continue,
+                Err(err) => return Err(err),
+            };
+            let mut header = [0; HEADER_LEN];
+            // Files without a complete header are left alone.
+            if file.read_exact(&mut header).is_ok() {
+                if let Some(sequence) = parse_header(&header) {
+                    files.push((sequence, index));
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+// Unlike write_record, data that doesn't fit in the current file continues
+// in the next one.
+impl Write for LogFiles {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.current.is_none() || self.len == self.capacity {
+            try!(self.rotate());
+        }
+        self.append(buf)
+    }
+}
+
+fn parse_header(header: &[u8; HEADER_LEN]) -> Option<u32> {
+    if &header[..HEADER_PREFIX.len()] != HEADER_PREFIX || header[HEADER_LEN - 1] != b'\n' {
+        return None;
+    }
+    let digits = &header[HEADER_PREFIX.len()..HEADER_LEN - 1];
+    str::from_utf8(digits).ok().and_then(|digits| u32::from_str_radix(digits, 16).ok())
+}
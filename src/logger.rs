@@ -3,9 +3,26 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 // A simple logger that sets the max log level to Trace in debug builds and to Info in release ones.
+//
+// The records are printed on the console, and also appended to log files
+// once `SimpleLogger::set_log_files` was called. Records can be logged from
+// the SimpleLink event handlers and from the file system code, so `log`
+// never touches the files: it queues the records in RAM, and a task writes
+// them every `FLUSH_INTERVAL_MS`. Records that don't fit in the queue are
+// counted and dropped.
 
+use collections::{String, Vec};
+
+use core::mem;
+use core::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+
+use freertos_rs::{CurrentTask, Duration, Mutex, Task};
 use log::{self, LogRecord, LogLevelFilter, LogMetadata, SetLoggerError};
 
+use error::Error;
+use log_file::LogFiles;
+use simplelink::{OsiError, SimpleLinkError};
+
 #[macro_export]
 macro_rules! print {
     ($($args:tt)*) => {{
@@ -26,6 +43,39 @@ macro_rules! println {
 
 pub struct SimpleLogger;
 
+// The records waiting for the flush task.
+struct LogQueue {
+    // Whether log files are set, so that records are queued at all.
+    enabled: bool,
+    records: Vec<String>,
+    // The total length of `records`.
+    len: usize,
+    // The records dropped since the last flush.
+    dropped: usize,
+}
+
+lazy_static! {
+    static ref LOG_QUEUE: Mutex<LogQueue> = Mutex::new(LogQueue {
+        enabled: false,
+        records: Vec::new(),
+        len: 0,
+        dropped: 0,
+    }).unwrap();
+    static ref LOG_FILES: Mutex<Option<LogFiles>> = Mutex::new(None).unwrap();
+}
+
+// Whether the flush task runs. It is only changed with LOG_FILES locked.
+static FLUSH_TASK_RUNNING: AtomicBool = ATOMIC_BOOL_INIT;
+
+// The most bytes of records waiting for the flush task.
+const LOG_QUEUE_MAX_LEN: usize = 2048;
+
+// How long a record waits for the queue, which is only held to add or take
+// records.
+const LOG_QUEUE_TIMEOUT_MS: u32 = 10;
+
+const FLUSH_INTERVAL_MS: u32 = 500;
+
 #[cfg(debug_assertions)]
 static MAX_LOG_LEVEL: LogLevelFilter = LogLevelFilter::Trace;
 
@@ -44,6 +94,7 @@ impl log::Log for SimpleLogger {
                      record.target(),
                      record.location().line(),
                      record.args());
+            SimpleLogger::queue(record);
         }
     }
 }
@@ -57,4 +108,98 @@ impl SimpleLogger {
             })
         }
     }
+
+    /// Also appends the records to `log_files`, or stops if None. Returns
+    /// the log files used before. Records logged while the NWP is stopped
+    /// only go to the console.
+    pub fn set_log_files(log_files: Option<LogFiles>) -> Result<Option<LogFiles>, Error> {
+        let mut current = LOG_FILES.lock(Duration::infinite()).unwrap();
+        if log_files.is_some() && !FLUSH_TASK_RUNNING.load(Ordering::SeqCst) {
+            let task = Task::new()
+                .name("log")
+                .stack_size(1024) // 32-bit words
+                .start(SimpleLogger::run);
+            if task.is_err() {
+                return Err(SimpleLinkError::Osi(OsiError::OSI_MEMORY_ALLOCATION_FAILURE).into());
+            }
+            FLUSH_TASK_RUNNING.store(true, Ordering::SeqCst);
+        }
+        {
+            let mut queue = LOG_QUEUE.lock(Duration::infinite()).unwrap();
+            queue.enabled = log_files.is_some();
+        }
+        // The queued records go to the files they were logged for.
+        if let Some(ref mut previous) = *current {
+            SimpleLogger::flush(previous);
+        }
+        let previous = current.take();
+        *current = log_files;
+        Ok(previous)
+    }
+
+    /// Calls `f` with the log files, eg. to copy or clear them, while no
+    /// record is written. The queued records are written first.
+    pub fn with_log_files<F, R>(f: F) -> Option<R>
+        where F: FnOnce(&mut LogFiles) -> R
+    {
+        let mut log_files = LOG_FILES.lock(Duration::infinite()).unwrap();
+        log_files.as_mut().map(|log_files| {
+            SimpleLogger::flush(log_files);
+            f(log_files)
+        })
+    }
+
+    // Adds the record to the queue, without waiting for the flush task.
+    fn queue(record: &LogRecord) {
+        let mut queue = match LOG_QUEUE.lock(Duration::ms(LOG_QUEUE_TIMEOUT_MS)) {
+            Ok(queue) => queue,
+            Err(_) => return,
+        };
+        if !queue.enabled {
+            return;
+        }
+        let text = format!("{:5} [{}@{}] {}\n",
+                           record.level(),
+                           record.target(),
+                           record.location().line(),
+                           record.args());
+        if queue.len + text.len() > LOG_QUEUE_MAX_LEN {
+            queue.dropped += 1;
+            return;
+        }
+        queue.len += text.len();
+        queue.records.push(text);
+    }
+
+    // Writes the queued records to the files. Like the console output,
+    // errors are ignored.
+    fn flush(log_files: &mut LogFiles) {
+        let (records, dropped) = {
+            let mut queue = LOG_QUEUE.lock(Duration::infinite()).unwrap();
+            queue.len = 0;
+            (mem::replace(&mut queue.records, Vec::new()), mem::replace(&mut queue.dropped, 0))
+        };
+        if dropped > 0 {
+            let text = format!("WARN  [logger] {} records were dropped\n", dropped);
+            let _ = log_files.write_record(text.as_bytes());
+        }
+        for record in &records {
+            let _ = log_files.write_record(record.as_bytes());
+        }
+    }
+
+    // The flush task, which stops once the log files are unset.
+    fn run() {
+        loop {
+            CurrentTask::delay(Duration::ms(FLUSH_INTERVAL_MS));
+            let mut log_files = LOG_FILES.lock(Duration::infinite()).unwrap();
+            match *log_files {
+                Some(ref mut log_files) => SimpleLogger::flush(log_files),
+                None => {
+                    FLUSH_TASK_RUNNING.store(false, Ordering::SeqCst);
+                    return;
+                }
+            }
+        }
+    }
 }
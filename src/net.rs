@@ -0,0 +1,394 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Network address types.
+
+use core::fmt;
+use core::mem;
+use core::ptr;
+
+use cc3200_sys::socket::{Family, SlIn6Addr_t, SlSockAddrIn6_t, SlSockAddrIn_t, SlSockAddrStorage_t,
+                        SlSocklen_t, sl_Htonl, sl_Htons};
+
+/// An IPv4 address, stored in host byte order like the SimpleLink APIs expect.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Addr(pub u32);
+
+impl Ipv4Addr {
+    pub fn new(a: u8, b: u8, c: u8, d: u8) -> Self {
+        Ipv4Addr(((a as u32) << 24) | ((b as u32) << 16) | ((c as u32) << 8) | (d as u32))
+    }
+
+    pub fn unspecified() -> Self {
+        Ipv4Addr(0)
+    }
+
+    pub fn octets(&self) -> [u8; 4] {
+        [(self.0 >> 24) as u8, (self.0 >> 16) as u8, (self.0 >> 8) as u8, self.0 as u8]
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Parses a dotted address, eg. "192.168.1.1".
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut octets = [0u8; 4];
+        let mut count = 0;
+        for part in s.split('.') {
+            if count == 4 || part.is_empty() || part.len() > 3 ||
+               !part.chars().all(|c| c.is_digit(10)) {
+                return None;
+            }
+            octets[count] = match part.parse() {
+                Ok(value) => value,
+                Err(_) => return None,
+            };
+            count += 1;
+        }
+        if count != 4 {
+            return None;
+        }
+        Some(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+    }
+}
+
+impl From<u32> for Ipv4Addr {
+    fn from(ip: u32) -> Ipv4Addr {
+        Ipv4Addr(ip)
+    }
+}
+
+impl fmt::Display for Ipv4Addr {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let octets = self.octets();
+        write!(formatter, "{}.{}.{}.{}", octets[0], octets[1], octets[2], octets[3])
+    }
+}
+
+/// An IPv6 address, stored as its 16 octets in network order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv6Addr(pub [u8; 16]);
+
+impl Ipv6Addr {
+    pub fn new(a: u16, b: u16, c: u16, d: u16, e: u16, f: u16, g: u16, h: u16) -> Self {
+        let segments = [a, b, c, d, e, f, g, h];
+        let mut octets = [0; 16];
+        for (i, segment) in segments.iter().enumerate() {
+            octets[2 * i] = (segment >> 8) as u8;
+            octets[2 * i + 1] = *segment as u8;
+        }
+        Ipv6Addr(octets)
+    }
+
+    pub fn unspecified() -> Self {
+        Ipv6Addr([0; 16])
+    }
+
+    /// Builds an address from the four 32 bit words used by the SimpleLink
+    /// APIs, most significant word first and each word in host byte order.
+    pub fn from_words(words: &[u32; 4]) -> Self {
+        let mut octets = [0; 16];
+        for (i, word) in words.iter().enumerate() {
+            octets[4 * i] = (word >> 24) as u8;
+            octets[4 * i + 1] = (word >> 16) as u8;
+            octets[4 * i + 2] = (word >> 8) as u8;
+            octets[4 * i + 3] = *word as u8;
+        }
+        Ipv6Addr(octets)
+    }
+
+    /// The reverse of `from_words`.
+    pub fn words(&self) -> [u32; 4] {
+        let mut words = [0; 4];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = ((self.0[4 * i] as u32) << 24) | ((self.0[4 * i + 1] as u32) << 16) |
+                    ((self.0[4 * i + 2] as u32) << 8) |
+                    (self.0[4 * i + 3] as u32);
+        }
+        words
+    }
+
+    pub fn octets(&self) -> [u8; 16] {
+        self.0
+    }
+
+    pub fn segments(&self) -> [u16; 8] {
+        let mut segments = [0; 8];
+        for (i, segment) in segments.iter_mut().enumerate() {
+            *segment = ((self.0[2 * i] as u16) << 8) | (self.0[2 * i + 1] as u16);
+        }
+        segments
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        self.0 == [0; 16]
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.segments() == [0, 0, 0, 0, 0, 0, 0, 1]
+    }
+
+    /// True for the fe80::/10 addresses the interface configures by itself.
+    pub fn is_link_local(&self) -> bool {
+        self.0[0] == 0xfe && (self.0[1] & 0xc0) == 0x80
+    }
+
+    /// Returns the IPv4 address of a ::ffff:a.b.c.d mapped address.
+    pub fn to_ipv4_mapped(&self) -> Option<Ipv4Addr> {
+        let segments = self.segments();
+        if segments[..5] == [0, 0, 0, 0, 0] && segments[5] == 0xffff {
+            Some(Ipv4Addr::new(self.0[12], self.0[13], self.0[14], self.0[15]))
+        } else {
+            None
+        }
+    }
+
+    /// Parses the textual form of an address, eg. "2001:db8::1" or
+    /// "::ffff:192.168.1.1". Zone indices are not supported.
+    pub fn parse(s: &str) -> Option<Self> {
+        // Splits a run of colon separated groups, at most `max` of them.
+        fn groups(s: &str, out: &mut [u16; 8], max: usize) -> Option<usize> {
+            if s.is_empty() {
+                return Some(0);
+            }
+            let mut count = 0;
+            for group in s.split(':') {
+                if count == max || group.is_empty() || group.len() > 4 ||
+                   !group.chars().all(|c| c.is_digit(16)) {
+                    return None;
+                }
+                out[count] = match u16::from_str_radix(group, 16) {
+                    Ok(value) => value,
+                    Err(_) => return None,
+                };
+                count += 1;
+            }
+            Some(count)
+        }
+
+        // An embedded IPv4 address stands for the last two groups.
+        let (s, ipv4) = match s.rfind(':') {
+            Some(pos) if s[pos + 1..].contains('.') => {
+                let ipv4 = match Ipv4Addr::parse(&s[pos + 1..]) {
+                    Some(ipv4) => ipv4,
+                    None => return None,
+                };
+                // Keep the colon of a "::" before the IPv4 address.
+                let end = if s[..pos].ends_with(':') { pos + 1 } else { pos };
+                (&s[..end], Some(ipv4))
+            }
+            _ => (s, None),
+        };
+        let len = if ipv4.is_some() { 6 } else { 8 };
+
+        let mut head = [0; 8];
+        let mut tail = [0; 8];
+        let (head_len, tail_len) = match s.find("::") {
+            Some(pos) => {
+                let head_len = match groups(&s[..pos], &mut head, len - 1) {
+                    Some(head_len) => head_len,
+                    None => return None,
+                };
+                match groups(&s[pos + 2..], &mut tail, len - 1 - head_len) {
+                    Some(tail_len) => (head_len, tail_len),
+                    None => return None,
+                }
+            }
+            None => {
+                if groups(s, &mut head, len) != Some(len) {
+                    return None;
+                }
+                (len, 0)
+            }
+        };
+
+        let mut segments = head;
+        for i in 0..tail_len {
+            segments[len - tail_len + i] = tail[i];
+        }
+        for segment in segments[head_len..len - tail_len].iter_mut() {
+            *segment = 0;
+        }
+        if let Some(ipv4) = ipv4 {
+            segments[6] = (ipv4.0 >> 16) as u16;
+            segments[7] = ipv4.0 as u16;
+        }
+        Some(Ipv6Addr::new(segments[0],
+                           segments[1],
+                           segments[2],
+                           segments[3],
+                           segments[4],
+                           segments[5],
+                           segments[6],
+                           segments[7]))
+    }
+}
+
+impl From<[u8; 16]> for Ipv6Addr {
+    fn from(octets: [u8; 16]) -> Ipv6Addr {
+        Ipv6Addr(octets)
+    }
+}
+
+impl fmt::Display for Ipv6Addr {
+    // Uses the RFC 5952 form: the longest run of two or more zero groups is
+    // replaced by "::".
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let segments = self.segments();
+
+        let mut best = (0, 0);
+        let mut start = 0;
+        for i in 0..9 {
+            if i < 8 && segments[i] == 0 {
+                continue;
+            }
+            if i - start > best.1 {
+                best = (start, i - start);
+            }
+            start = i + 1;
+        }
+
+        if best.1 < 2 {
+            for (i, segment) in segments.iter().enumerate() {
+                if i > 0 {
+                    try!(write!(formatter, ":"));
+                }
+                try!(write!(formatter, "{:x}", segment));
+            }
+            return Ok(());
+        }
+
+        for (i, segment) in segments[..best.0].iter().enumerate() {
+            if i > 0 {
+                try!(write!(formatter, ":"));
+            }
+            try!(write!(formatter, "{:x}", segment));
+        }
+        try!(write!(formatter, "::"));
+        for (i, segment) in segments[best.0 + best.1..].iter().enumerate() {
+            if i > 0 {
+                try!(write!(formatter, ":"));
+            }
+            try!(write!(formatter, "{:x}", segment));
+        }
+        Ok(())
+    }
+}
+
+/// Either an IPv4 or an IPv6 address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpAddr {
+    V4(Ipv4Addr),
+    V6(Ipv6Addr),
+}
+
+impl IpAddr {
+    /// Parses a dotted IPv4 address or an IPv6 address.
+    pub fn parse(s: &str) -> Option<Self> {
+        if s.contains(':') {
+            Ipv6Addr::parse(s).map(IpAddr::V6)
+        } else {
+            Ipv4Addr::parse(s).map(IpAddr::V4)
+        }
+    }
+
+    pub fn family(&self) -> Family {
+        match *self {
+            IpAddr::V4(_) => Family::AF_INET,
+            IpAddr::V6(_) => Family::AF_INET6,
+        }
+    }
+}
+
+impl From<Ipv4Addr> for IpAddr {
+    fn from(ip: Ipv4Addr) -> IpAddr {
+        IpAddr::V4(ip)
+    }
+}
+
+impl From<Ipv6Addr> for IpAddr {
+    fn from(ip: Ipv6Addr) -> IpAddr {
+        IpAddr::V6(ip)
+    }
+}
+
+impl fmt::Display for IpAddr {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IpAddr::V4(ref ip) => ip.fmt(formatter),
+            IpAddr::V6(ref ip) => ip.fmt(formatter),
+        }
+    }
+}
+
+/// An IP address and a port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketAddr {
+    pub ip: IpAddr,
+    pub port: u16,
+}
+
+impl SocketAddr {
+    pub fn new(ip: IpAddr, port: u16) -> Self {
+        SocketAddr { ip: ip, port: port }
+    }
+
+    /// Encodes the address as a SlSockAddrIn_t or a SlSockAddrIn6_t, the
+    /// layout expected by sl_Connect, sl_Bind and sl_SendTo. The returned
+    /// length has to be passed along with the pointer to the storage.
+    pub fn to_raw(&self) -> (SlSockAddrStorage_t, SlSocklen_t) {
+        let mut storage: SlSockAddrStorage_t = Default::default();
+        let len = match self.ip {
+            IpAddr::V4(ref ip) => {
+                let inaddr = SlSockAddrIn_t {
+                    sin_family: Family::AF_INET,
+                    sin_port: unsafe { sl_Htons(self.port) },
+                    sin_addr: unsafe { sl_Htonl(ip.0) },
+                    sin_zero: [0; 8],
+                };
+                unsafe { ptr::write(storage.as_mut_ptr() as *mut SlSockAddrIn_t, inaddr) };
+                mem::size_of::<SlSockAddrIn_t>()
+            }
+            IpAddr::V6(ref ip) => {
+                let in6addr = SlSockAddrIn6_t {
+                    sin6_family: Family::AF_INET6,
+                    sin6_port: unsafe { sl_Htons(self.port) },
+                    sin6_flowinfo: 0,
+                    sin6_addr: SlIn6Addr_t::from_octets(ip.0),
+                    sin6_scope_id: 0,
+                };
+                unsafe { ptr::write(storage.as_mut_ptr() as *mut SlSockAddrIn6_t, in6addr) };
+                mem::size_of::<SlSockAddrIn6_t>()
+            }
+        };
+        (storage, len as SlSocklen_t)
+    }
+
+    /// Decodes an address filled by sl_Accept or sl_RecvFrom.
+    pub fn from_raw(storage: &SlSockAddrStorage_t) -> Option<Self> {
+        match storage.family {
+            family if family == Family::AF_INET as u16 => {
+                let inaddr = unsafe { &*(storage.as_ptr() as *const SlSockAddrIn_t) };
+                let ip = Ipv4Addr(unsafe { sl_Htonl(inaddr.sin_addr) });
+                Some(SocketAddr::new(IpAddr::V4(ip), unsafe { sl_Htons(inaddr.sin_port) }))
+            }
+            family if family == Family::AF_INET6 as u16 => {
+                let in6addr = unsafe { &*(storage.as_ptr() as *const SlSockAddrIn6_t) };
+                let ip = Ipv6Addr(in6addr.sin6_addr.octets());
+                Some(SocketAddr::new(IpAddr::V6(ip), unsafe { sl_Htons(in6addr.sin6_port) }))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SocketAddr {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.ip {
+            IpAddr::V4(ref ip) => write!(formatter, "{}:{}", ip, self.port),
+            IpAddr::V6(ref ip) => write!(formatter, "[{}]:{}", ip, self.port),
+        }
+    }
+}
@@ -0,0 +1,300 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Wi-Fi Direct (P2P).
+//
+// `P2p::start` puts the NWP in the P2P role. Peers are then found with
+// `P2p::discover`, and a group is formed either by initiating the
+// negotiation with `P2p::connect` or by answering a peer with `P2p::accept`.
+// The negotiation decides which side becomes the group owner, which runs
+// the DHCP server, and which side becomes the client.
+
+use collections::{String, Vec};
+
+use core::str;
+
+use error::Error;
+use events::{Event, EventQueue, NetAppEvent, WlanEvent, BSSID_LEN, P2P_DEV_NAME_LEN_MAX};
+use net::Ipv4Addr;
+use simplelink::{self, Ipv4Config, OsiError, P2pNegInitiator, P2pPolicy, ScanPolicy, SimpleLink,
+                 SimpleLinkError, SimpleLinkSession, SlSecParams, WlanConfig, WlanMode,
+                 WlanPolicy};
+use time::now_ms;
+
+const EVENT_QUEUE_LEN: usize = 8;
+const POLL_INTERVAL_MS: u32 = 100;
+
+// Regulatory class of the 2.4 GHz channels 1-13.
+const REG_CLASS_2_4_GHZ: u8 = 81;
+
+/// The group owner intent used in the negotiation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum P2pRole {
+    /// Prefer to be a client (intent 0).
+    Client,
+    /// Let the negotiation decide (intent 3).
+    Negotiate,
+    /// Prefer to be the group owner (intent 15).
+    GroupOwner,
+}
+
+/// Which side starts the negotiation when the NWP connects on its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum P2pInitiator {
+    Active,
+    Passive,
+    RandomBackoff,
+}
+
+/// The WPS method used to authenticate the peer.
+#[derive(Debug, Clone, Copy)]
+pub enum P2pMethod<'a> {
+    PushButton,
+    /// The PIN is entered on this device.
+    PinKeypad(&'a str),
+    /// The PIN is displayed by this device and entered on the peer.
+    PinDisplay(&'a str),
+}
+
+/// Role of the device in the group formed by the negotiation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum P2pGroupRole {
+    GroupOwner,
+    Client,
+}
+
+pub struct P2pConfig {
+    /// Name advertised to the peers, up to 32 characters.
+    pub dev_name: String,
+    /// WPS primary device type, eg. "1-0050F204-1" (computer).
+    pub dev_type: String,
+    pub role: P2pRole,
+    pub initiator: P2pInitiator,
+    pub listen_channel: u8,
+    pub operating_channel: u8,
+    /// Interval between the scans looking for peers.
+    pub scan_interval_secs: u32,
+}
+
+impl P2pConfig {
+    pub fn new(dev_name: &str) -> Self {
+        P2pConfig {
+            dev_name: String::from(dev_name),
+            dev_type: String::from("1-0050F204-1"),
+            role: P2pRole::Negotiate,
+            initiator: P2pInitiator::RandomBackoff,
+            listen_channel: 11,
+            operating_channel: 6,
+            scan_interval_secs: 10,
+        }
+    }
+
+    fn policy(&self) -> P2pPolicy {
+        P2pPolicy {
+            go_intent: match self.role {
+                P2pRole::Client => simplelink::P2P_GO_INTENT_CLIENT,
+                P2pRole::Negotiate => simplelink::P2P_GO_INTENT_NEGOTIATE,
+                P2pRole::GroupOwner => simplelink::P2P_GO_INTENT_GROUP_OWNER,
+            },
+            initiator: match self.initiator {
+                P2pInitiator::Active => P2pNegInitiator::Active,
+                P2pInitiator::Passive => P2pNegInitiator::Passive,
+                P2pInitiator::RandomBackoff => P2pNegInitiator::RandBackoff,
+            },
+        }
+    }
+}
+
+/// A P2P device found by `P2p::discover` or asking to connect.
+#[derive(Debug, Clone, Copy)]
+pub struct P2pPeer {
+    name: [u8; P2P_DEV_NAME_LEN_MAX],
+    name_len: u8,
+    pub mac: [u8; BSSID_LEN],
+    /// The WPS device password ID advertised by the peer (0 for a PIN,
+    /// 4 for push-button).
+    pub wps_dev_password_id: u8,
+}
+
+impl P2pPeer {
+    /// Returns the name, or None if it isn't UTF-8.
+    pub fn name(&self) -> Option<&str> {
+        str::from_utf8(self.name_bytes()).ok()
+    }
+
+    pub fn name_bytes(&self) -> &[u8] {
+        &self.name[..self.name_len as usize]
+    }
+}
+
+/// The outcome of a successful negotiation.
+#[derive(Debug, Clone, Copy)]
+pub struct P2pGroup {
+    pub role: P2pGroupRole,
+    /// MAC address of the peer.
+    pub peer_mac: [u8; BSSID_LEN],
+    /// Addressing of this device: the group owner interface configuration,
+    /// or the one acquired from the group owner by the client.
+    pub ipv4: Ipv4Config,
+    /// Address of the peer: the address leased to the client, or the group
+    /// owner address.
+    pub peer_ip: Ipv4Addr,
+}
+
+pub struct P2p { }
+
+impl P2p {
+    /// Starts the NWP in the P2P role and configures it. The SimpleLink
+    /// spawn task must be running and no session open; the NWP runs until
+    /// the returned session is passed to `P2p::stop` or dropped.
+    pub fn start(config: &P2pConfig) -> Result<SimpleLinkSession, Error> {
+        let session = try!(SimpleLinkSession::start_in_mode(WlanMode::ROLE_P2P));
+
+        try!(SimpleLink::wlan_set(WlanConfig::P2pDevName, config.dev_name.as_bytes()));
+        try!(SimpleLink::wlan_set(WlanConfig::P2pDevType, config.dev_type.as_bytes()));
+        let channels = [config.listen_channel,
+                        REG_CLASS_2_4_GHZ,
+                        config.operating_channel,
+                        REG_CLASS_2_4_GHZ];
+        try!(SimpleLink::wlan_set(WlanConfig::P2pChannelRegs, &channels));
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::P2p(config.policy())));
+
+        // Connect only when asked to, not to the first peer found.
+        let connection = simplelink::CONNECTION_POLICY_AUTO;
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Connection(connection)));
+
+        let scan = ScanPolicy::Enabled { interval_secs: config.scan_interval_secs };
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Scan(scan)));
+        Ok(session)
+    }
+
+    /// Leaves the group and stops the NWP.
+    pub fn stop(session: SimpleLinkSession) -> Result<(), Error> {
+        let _ = SimpleLink::wlan_disconnect();
+        session.stop()
+    }
+
+    /// Collects the peers found during `timeout_ms`.
+    pub fn discover(timeout_ms: u32) -> Result<Vec<P2pPeer>, Error> {
+        let events = try!(EventQueue::subscribe(EVENT_QUEUE_LEN));
+        let mut peers: Vec<P2pPeer> = Vec::new();
+        let start_ms = now_ms();
+        while now_ms().wrapping_sub(start_ms) < timeout_ms {
+            if let Some(Event::Wlan(WlanEvent::P2pDeviceFound { name,
+                                                               name_len,
+                                                               mac,
+                                                               wps_dev_password_id })) =
+                   events.receive(POLL_INTERVAL_MS) {
+                if !peers.iter().any(|p| p.mac == mac) {
+                    peers.push(P2pPeer {
+                        name: name,
+                        name_len: name_len,
+                        mac: mac,
+                        wps_dev_password_id: wps_dev_password_id,
+                    });
+                }
+            }
+        }
+        Ok(peers)
+    }
+
+    /// Starts the negotiation with `peer` and waits up to `timeout_ms` for
+    /// the group to be formed.
+    pub fn connect(peer: &P2pPeer,
+                   method: P2pMethod,
+                   timeout_ms: u32)
+                   -> Result<P2pGroup, Error> {
+        let events = try!(EventQueue::subscribe(EVENT_QUEUE_LEN));
+        let start_ms = now_ms();
+        try!(P2p::negotiate(peer, method));
+        P2p::wait_for_group(&events, start_ms, timeout_ms)
+    }
+
+    /// Waits for a peer to ask for a negotiation, answers it and waits for
+    /// the group to be formed, all within `timeout_ms`.
+    pub fn accept(method: P2pMethod, timeout_ms: u32) -> Result<P2pGroup, Error> {
+        let events = try!(EventQueue::subscribe(EVENT_QUEUE_LEN));
+        let start_ms = now_ms();
+        let mut request = None;
+        while request.is_none() {
+            if now_ms().wrapping_sub(start_ms) >= timeout_ms {
+                return Err(SimpleLinkError::Osi(OsiError::OSI_TIMEOUT).into());
+            }
+            if let Some(Event::Wlan(WlanEvent::P2pNegotiationRequest { name,
+                                                                      name_len,
+                                                                      mac,
+                                                                      wps_dev_password_id })) =
+                   events.receive(POLL_INTERVAL_MS) {
+                request = Some(P2pPeer {
+                    name: name,
+                    name_len: name_len,
+                    mac: mac,
+                    wps_dev_password_id: wps_dev_password_id,
+                });
+            }
+        }
+        let peer = request.unwrap();
+        info!("P2P negotiation request from {}",
+              String::from_utf8_lossy(peer.name_bytes()));
+        try!(P2p::negotiate(&peer, method));
+        P2p::wait_for_group(&events, start_ms, timeout_ms)
+    }
+
+    fn negotiate(peer: &P2pPeer, method: P2pMethod) -> Result<(), Error> {
+        let sec_params = match method {
+            P2pMethod::PushButton => SlSecParams::p2p_push_button(),
+            P2pMethod::PinKeypad(pin) => SlSecParams::p2p_pin_keypad(pin),
+            P2pMethod::PinDisplay(pin) => SlSecParams::p2p_pin_display(pin),
+        };
+        SimpleLink::wlan_connect_bytes(peer.name_bytes(), &[], Some(sec_params), None)
+    }
+
+    // Waits until `timeout_ms` after `start_ms` for the connection and the
+    // addressing. Which events arrive tells the outcome of the negotiation:
+    // a client connects to the group owner and acquires an address, a group
+    // owner sees the client join and leases it an address.
+    fn wait_for_group(events: &EventQueue,
+                      start_ms: u32,
+                      timeout_ms: u32)
+                      -> Result<P2pGroup, Error> {
+        let mut role = None;
+        let mut peer_mac = [0; BSSID_LEN];
+        while now_ms().wrapping_sub(start_ms) < timeout_ms {
+            match events.receive(POLL_INTERVAL_MS) {
+                Some(Event::Wlan(WlanEvent::Connected { bssid, .. })) => {
+                    role = Some(P2pGroupRole::Client);
+                    peer_mac = bssid;
+                }
+                Some(Event::Wlan(WlanEvent::StationAdded { mac })) => {
+                    role = Some(P2pGroupRole::GroupOwner);
+                    peer_mac = mac;
+                }
+                Some(Event::Wlan(WlanEvent::ConnectionFailed { status })) => {
+                    warn!("P2P connection failed, status {}", status);
+                    return Err(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED).into());
+                }
+                Some(Event::NetApp(NetAppEvent::IpAcquired { gateway, .. })) => {
+                    let info = try!(SimpleLink::netcfg_get_ipv4_info());
+                    return Ok(P2pGroup {
+                        role: role.unwrap_or(P2pGroupRole::Client),
+                        peer_mac: peer_mac,
+                        ipv4: info.config,
+                        peer_ip: Ipv4Addr(gateway),
+                    });
+                }
+                Some(Event::NetApp(NetAppEvent::IpLeased { ip, mac, .. })) => {
+                    return Ok(P2pGroup {
+                        role: role.unwrap_or(P2pGroupRole::GroupOwner),
+                        peer_mac: mac,
+                        ipv4: try!(SimpleLink::netcfg_get_ipv4_ap_info()),
+                        peer_ip: Ipv4Addr(ip),
+                    });
+                }
+                _ => {}
+            }
+        }
+        let _ = SimpleLink::wlan_disconnect();
+        Err(SimpleLinkError::Osi(OsiError::OSI_TIMEOUT).into())
+    }
+}
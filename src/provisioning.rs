@@ -0,0 +1,439 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Access point provisioning.
+//
+// The device scans for the access points around, then brings up its own
+// open access point and serves a form listing them. Every DNS query is
+// answered with the device address, so phones joining the access point
+// detect a captive portal and open the form. Once the user submitted an
+// SSID and a key, a profile is stored, the NWP is switched back to station
+// mode and the board is reset.
+
+use collections::{String, Vec};
+
+use core::str;
+use core::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+
+use cc3200::Board;
+use cc3200_sys::socket::SocketError;
+use error::Error;
+use freertos_rs::{CurrentTask, Duration, Task};
+use io::{Read, Write};
+use net::Ipv4Addr;
+use simplelink::{self, ConnectionPolicy, OsiError, ScanPolicy, SecurityType, SimpleLink,
+                 SimpleLinkError, SimpleLinkSession, SlSecParams, SlWlanNetworkEntry,
+                 WlanConfig, WlanMode, WlanPolicy};
+use socket_channel::{SocketChannel, UdpSocket};
+
+const MAX_NETWORKS: usize = 20;
+const DNS_PORT: u16 = 53;
+const DNS_TTL_SECS: u32 = 60;
+const REQUEST_LEN_MAX: usize = 2048;
+const RECV_TIMEOUT_SECS: u32 = 1;
+
+pub struct ProvisioningConfig {
+    /// SSID of the provisioning access point.
+    pub ap_ssid: String,
+    pub ap_channel: u8,
+    pub http_port: u16,
+    /// How long to let the scan run before reading the network list.
+    pub scan_time_ms: u32,
+}
+
+impl ProvisioningConfig {
+    pub fn new(ap_ssid: &str) -> Self {
+        ProvisioningConfig {
+            ap_ssid: String::from(ap_ssid),
+            ap_channel: 6,
+            http_port: 80,
+            scan_time_ms: 3000,
+        }
+    }
+}
+
+// The credentials submitted through the form.
+struct Credentials {
+    ssid: String,
+    key: String,
+}
+
+static DNS_STOP_REQUESTED: AtomicBool = ATOMIC_BOOL_INIT;
+// Set while the DNS task runs, and holds its socket.
+static DNS_RUNNING: AtomicBool = ATOMIC_BOOL_INIT;
+
+pub struct Provisioning { }
+
+impl Provisioning {
+    /// Runs the provisioning flow. On success the board is reset and this
+    /// function doesn't return. The SimpleLink spawn task must be running
+    /// and no `SimpleLinkSession` open.
+    pub fn run(config: &ProvisioningConfig) -> Result<(), Error> {
+        let mut session = try!(SimpleLinkSession::start_in_mode(WlanMode::ROLE_STA));
+        let networks = try!(Provisioning::scan(config.scan_time_ms));
+        info!("Provisioning: found {} networks", networks.len());
+
+        try!(Provisioning::start_ap(&mut session, config));
+        let ap_ip = try!(SimpleLink::netcfg_get_ipv4_ap_info()).ip;
+        info!("Provisioning: access point {} up at {}", config.ap_ssid, ap_ip);
+
+        try!(Provisioning::start_dns(ap_ip));
+        let credentials = Provisioning::serve(config.http_port, &networks);
+        Provisioning::stop_dns();
+        let credentials = try!(credentials);
+
+        try!(Provisioning::store_profile(&mut session, &credentials, &networks));
+        try!(session.stop());
+        info!("Provisioning: profile for {} stored, rebooting", credentials.ssid);
+        Board::reset();
+        Ok(())
+    }
+
+    fn scan(scan_time_ms: u32) -> Result<Vec<SlWlanNetworkEntry>, Error> {
+        // Don't let the NWP connect on its own while scanning.
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Connection(ConnectionPolicy::empty())));
+        let _ = SimpleLink::wlan_disconnect();
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Scan(ScanPolicy::Enabled {
+            interval_secs: 10,
+        })));
+        CurrentTask::delay(Duration::ms(scan_time_ms));
+
+        let mut entries = [SlWlanNetworkEntry::default(); MAX_NETWORKS];
+        let mut networks = Vec::new();
+        for entry in try!(SimpleLink::wlan_get_network_list(&mut entries)).iter() {
+            // Hidden networks have no SSID, keep one entry per SSID.
+            if entry.ssid_len > 0 && !networks.iter().any(|n: &SlWlanNetworkEntry| {
+                n.ssid() == entry.ssid()
+            }) {
+                networks.push(*entry);
+            }
+        }
+
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Scan(ScanPolicy::Disabled)));
+        Ok(networks)
+    }
+
+    fn start_ap(session: &mut SimpleLinkSession,
+                config: &ProvisioningConfig)
+                -> Result<(), Error> {
+        try!(session.switch_mode(WlanMode::ROLE_AP));
+        try!(SimpleLink::wlan_set(WlanConfig::ApSsid, config.ap_ssid.as_bytes()));
+        try!(SimpleLink::wlan_set(WlanConfig::ApSecurityType, &[SecurityType::Open as u8]));
+        try!(SimpleLink::wlan_set(WlanConfig::ApChannel, &[config.ap_channel]));
+
+        // The AP settings take effect after a restart.
+        session.restart()
+    }
+
+    fn store_profile(session: &mut SimpleLinkSession,
+                     credentials: &Credentials,
+                     networks: &[SlWlanNetworkEntry])
+                     -> Result<(), Error> {
+        try!(session.switch_mode(WlanMode::ROLE_STA));
+
+        let scanned_sec_type = networks.iter()
+            .find(|n| n.ssid() == credentials.ssid)
+            .map(|n| n.sec_type);
+        let sec_type = match scanned_sec_type {
+            Some(simplelink::SCAN_SEC_TYPE_OPEN) => SecurityType::Open,
+            Some(simplelink::SCAN_SEC_TYPE_WEP) => SecurityType::Wep,
+            // The WPA/WPA2 security type connects to both.
+            Some(_) => SecurityType::Wpa2,
+            None if credentials.key.is_empty() => SecurityType::Open,
+            None => SecurityType::Wpa2,
+        };
+        let sec_params = match sec_type {
+            SecurityType::Open => None,
+            sec_type => {
+                Some(SlSecParams {
+                    sec_type: sec_type as u8,
+                    key: credentials.key.as_ptr(),
+                    key_len: credentials.key.len() as u8,
+                })
+            }
+        };
+        try!(SimpleLink::wlan_add_profile(&credentials.ssid, &[], sec_params, None, 0));
+        let connection = simplelink::CONNECTION_POLICY_AUTO;
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Connection(connection)));
+        Ok(())
+    }
+
+    // DNS
+
+    fn start_dns(ap_ip: Ipv4Addr) -> Result<(), Error> {
+        let socket = try!(UdpSocket::bind(DNS_PORT));
+        try!(socket.set_recv_timeout(RECV_TIMEOUT_SECS));
+        DNS_STOP_REQUESTED.store(false, Ordering::SeqCst);
+        DNS_RUNNING.store(true, Ordering::SeqCst);
+        let task = Task::new()
+            .name("dns")
+            .stack_size(1024) // 32-bit words
+            .start(move || {
+                let mut buf = [0; 512];
+                while !DNS_STOP_REQUESTED.load(Ordering::SeqCst) {
+                    let (len, from) = match socket.recv_from(&mut buf) {
+                        Ok(received) => received,
+                        Err(_) => continue,
+                    };
+                    if let Some(len) = dns_answer(&mut buf, len, ap_ip) {
+                        let _ = socket.send_to(&buf[..len], &from);
+                    }
+                }
+                drop(socket);
+                DNS_RUNNING.store(false, Ordering::SeqCst);
+            });
+        if task.is_err() {
+            DNS_RUNNING.store(false, Ordering::SeqCst);
+            return Err(SimpleLinkError::Osi(OsiError::OSI_MEMORY_ALLOCATION_FAILURE).into());
+        }
+        Ok(())
+    }
+
+    // Stops the DNS task and waits for it to close its socket, which has to
+    // happen before the NWP is stopped. The task notices the request within
+    // a receive timeout.
+    fn stop_dns() {
+        DNS_STOP_REQUESTED.store(true, Ordering::SeqCst);
+        let mut elapsed_ms = 0;
+        while DNS_RUNNING.load(Ordering::SeqCst) && elapsed_ms < 3 * RECV_TIMEOUT_SECS * 1000 {
+            CurrentTask::delay(Duration::ms(100));
+            elapsed_ms += 100;
+        }
+    }
+
+    // HTTP
+
+    // Serves the form until credentials are submitted.
+    fn serve(port: u16, networks: &[SlWlanNetworkEntry]) -> Result<Credentials, Error> {
+        let listener = try!(SocketChannel::listen(port, 1));
+        try!(listener.set_recv_timeout(RECV_TIMEOUT_SECS));
+        loop {
+            let mut client = match listener.accept() {
+                Ok(client) => client,
+                Err(_) => continue,
+            };
+            let _ = client.set_recv_timeout(RECV_TIMEOUT_SECS);
+            let mut request = Vec::with_capacity(REQUEST_LEN_MAX);
+            if read_request(&mut client, &mut request).is_err() {
+                continue;
+            }
+            match parse_request(&request) {
+                Some(("POST", _, body)) => {
+                    if let Some(credentials) = parse_form(body) {
+                        let _ = send_response(&mut client, "200 OK", SAVED_PAGE);
+                        return Ok(credentials);
+                    }
+                    let _ = send_response(&mut client, "400 Bad Request", &form_page(networks));
+                }
+                // Every other request gets the form, which is what makes
+                // the captive portal detection of the phones pop it up.
+                Some(_) => {
+                    let _ = send_response(&mut client, "200 OK", &form_page(networks));
+                }
+                None => {
+                    let _ = send_response(&mut client, "400 Bad Request", "");
+                }
+            }
+        }
+    }
+}
+
+const SAVED_PAGE: &'static str = "<html><body><h1>Saved</h1>\
+                                  <p>The device will now restart and connect.</p></body></html>";
+
+fn form_page(networks: &[SlWlanNetworkEntry]) -> String {
+    let mut page = String::new();
+    page.push_str("<html><head><meta name=\"viewport\" content=\"width=device-width\">\
+                   <title>Wi-Fi setup</title></head><body><h1>Wi-Fi setup</h1>\
+                   <form method=\"post\" action=\"/\"><select name=\"ssid\">");
+    for network in networks {
+        page.push_str("<option value=\"");
+        push_escaped(&mut page, network.ssid());
+        page.push_str("\">");
+        push_escaped(&mut page, network.ssid());
+        page.push_str(&format!(" ({} dBm)</option>", network.rssi));
+    }
+    page.push_str("</select><p>Key: <input type=\"password\" name=\"key\"></p>\
+                   <p><input type=\"submit\" value=\"Connect\"></p></form></body></html>");
+    page
+}
+
+fn push_escaped(page: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => page.push_str("&lt;"),
+            '>' => page.push_str("&gt;"),
+            '&' => page.push_str("&amp;"),
+            '"' => page.push_str("&quot;"),
+            '\'' => page.push_str("&#39;"),
+            c => page.push(c),
+        }
+    }
+}
+
+// Splits a request into its method, path and body.
+fn parse_request(request: &[u8]) -> Option<(&str, &str, &[u8])> {
+    let header_end = match find(request, b"\r\n\r\n") {
+        Some(pos) => pos,
+        None => return None,
+    };
+    let head = match str::from_utf8(&request[..header_end]) {
+        Ok(head) => head,
+        Err(_) => return None,
+    };
+    let mut parts = head.lines().next().unwrap_or("").split(' ');
+    match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => Some((method, path, &request[header_end + 4..])),
+        _ => None,
+    }
+}
+
+fn content_length(head: &[u8]) -> usize {
+    let head = str::from_utf8(head).unwrap_or("");
+    for line in head.lines() {
+        let mut parts = line.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.trim().to_lowercase() == "content-length" {
+                return value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    0
+}
+
+// Reads the request headers and the body announced by Content-Length.
+fn read_request(client: &mut SocketChannel, request: &mut Vec<u8>) -> Result<(), Error> {
+    let mut buf = [0; 256];
+    let mut expected_len = None;
+    loop {
+        let len = try!(client.read(&mut buf));
+        if len == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..len]);
+        if expected_len.is_none() {
+            if let Some(header_end) = find(request, b"\r\n\r\n") {
+                expected_len = Some(header_end + 4 + content_length(&request[..header_end]));
+            }
+        }
+        match expected_len {
+            Some(len) if request.len() >= len => return Ok(()),
+            _ if request.len() >= REQUEST_LEN_MAX => return Err(SocketError::ENOBUFS.into()),
+            _ => {}
+        }
+    }
+}
+
+fn send_response(client: &mut SocketChannel, status: &str, body: &str) -> Result<(), Error> {
+    try!(write!(client,
+                "HTTP/1.1 {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n",
+                status,
+                body.len()));
+    client.write_all(body.as_bytes())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+// Parses an application/x-www-form-urlencoded body with ssid and key fields.
+fn parse_form(body: &[u8]) -> Option<Credentials> {
+    let body = match str::from_utf8(body) {
+        Ok(body) => body,
+        Err(_) => return None,
+    };
+    let mut ssid = None;
+    let mut key = String::new();
+    for pair in body.split('&') {
+        let mut parts = pair.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("ssid"), Some(value)) => ssid = url_decode(value),
+            (Some("key"), Some(value)) => key = url_decode(value).unwrap_or(key),
+            _ => {}
+        }
+    }
+    match ssid {
+        Some(ssid) if !ssid.is_empty() && ssid.len() <= simplelink::MAXIMAL_SSID_LENGTH => {
+            Some(Credentials {
+                ssid: ssid,
+                key: key,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn url_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let high = iter.next().and_then(hex_value);
+                let low = iter.next().and_then(hex_value);
+                match (high, low) {
+                    (Some(high), Some(low)) => bytes.push((high << 4) | low),
+                    _ => return None,
+                }
+            }
+            b => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'...b'9' => Some(c - b'0'),
+        b'a'...b'f' => Some(c - b'a' + 10),
+        b'A'...b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+// Turns the DNS query in `buf` into a response pointing to `ip`, and returns
+// its length. Queries other than A get an empty answer.
+fn dns_answer(buf: &mut [u8], len: usize, ip: Ipv4Addr) -> Option<usize> {
+    const HEADER_LEN: usize = 12;
+    const ANSWER_LEN: usize = 16;
+
+    // Only answer standard queries with a single question.
+    if len < HEADER_LEN || buf[2] & 0xf8 != 0 || buf[4] != 0 || buf[5] != 1 {
+        return None;
+    }
+    let mut pos = HEADER_LEN;
+    while pos < len && buf[pos] != 0 {
+        pos += buf[pos] as usize + 1;
+    }
+    // Null label, QTYPE and QCLASS.
+    let question_end = pos + 5;
+    if question_end > len || question_end + ANSWER_LEN > buf.len() {
+        return None;
+    }
+    let is_a = buf[pos + 1] == 0 && buf[pos + 2] == 1;
+
+    buf[2] = 0x84 | (buf[2] & 0x01); // Response, authoritative, keep RD.
+    buf[3] = 0x00;
+    buf[6] = 0;
+    buf[7] = if is_a { 1 } else { 0 };
+    for b in buf[8..HEADER_LEN].iter_mut() {
+        *b = 0;
+    }
+    if !is_a {
+        return Some(question_end);
+    }
+
+    let octets = ip.octets();
+    let answer = [0xc0, HEADER_LEN as u8, // Pointer to the question name
+                  0, 1, // Type A
+                  0, 1, // Class IN
+                  (DNS_TTL_SECS >> 24) as u8, (DNS_TTL_SECS >> 16) as u8,
+                  (DNS_TTL_SECS >> 8) as u8, DNS_TTL_SECS as u8,
+                  0, 4,
+                  octets[0], octets[1], octets[2], octets[3]];
+    buf[question_end..question_end + ANSWER_LEN].copy_from_slice(&answer);
+    Some(question_end + ANSWER_LEN)
+}
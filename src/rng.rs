@@ -0,0 +1,287 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Random numbers.
+//
+// `Rng` reads the NWP true random number generator while the NWP is running,
+// and falls back to a ChaCha20 based generator otherwise. The software
+// generator is reseeded from every TRNG read.
+//
+// The TRNG is read with the net utilities of the 2.x SDKs. The 1.x SDKs
+// don't have them: nothing is read from the TRNG, and the device has no
+// other source of entropy. `fill_bytes` then fails until the application
+// seeded the generator with `Rng::seed`, eg. from a seed received from a
+// server or stored when the device was provisioned. `fill_bytes_weak`
+// never fails: without a seed, it uses the RTC, the SysTick counter and
+// the MAC address, which an attacker can guess, so its bytes must not be
+// used for keys, nonces or tokens. `add_entropy` mixes in extra data (eg.
+// RSSI samples) without making the generator count as seeded.
+//
+// The software generator rekeys itself after every request, so a later
+// compromise of its state doesn't reveal the bytes already returned.
+
+use core::cmp;
+use core::fmt;
+use core::ptr;
+
+use cc3200_sys;
+use freertos_rs::{Duration, Mutex};
+
+use chacha;
+use error::{Error, ErrorKind};
+use simplelink::{OsiError, SimpleLink, SimpleLinkError};
+
+// The TRNG is read by chunks of this size.
+const TRNG_CHUNK_LEN: usize = 64;
+
+/// The minimum length of the seed passed to `Rng::seed`.
+pub const SEED_LEN: usize = 32;
+
+// SysTick current value register.
+const SYSTICK_CURRENT: *const u32 = 0xe000e018 as *const u32;
+
+struct ChaChaRng {
+    key: [u32; 8],
+    counter: u64,
+    // Something was mixed into the key.
+    seeded: bool,
+    // The key was mixed with a TRNG read or a seed of the application.
+    strong: bool,
+}
+
+impl ChaChaRng {
+    // The 64 bit counter extends into the first nonce word.
+    fn block(&mut self, out: &mut [u8; 64]) {
+        let nonce = [(self.counter >> 32) as u32, 0, 0];
+        chacha::block(&self.key, self.counter as u32, &nonce, out);
+        self.counter = self.counter.wrapping_add(1);
+    }
+
+    // Replaces the key with the next output block.
+    fn rekey(&mut self) {
+        let mut block = [0; 64];
+        self.block(&mut block);
+        for i in 0..8 {
+            self.key[i] = (block[4 * i] as u32) | ((block[4 * i + 1] as u32) << 8) |
+                          ((block[4 * i + 2] as u32) << 16) |
+                          ((block[4 * i + 3] as u32) << 24);
+        }
+        wipe(&mut block);
+    }
+
+    // Xors `data` into the key, 32 bytes at a time, and rekeys after each
+    // chunk so every input byte affects all the following output.
+    fn mix(&mut self, data: &[u8]) {
+        for chunk in data.chunks(32) {
+            for (i, byte) in chunk.iter().enumerate() {
+                self.key[i / 4] ^= (*byte as u32) << (8 * (i % 4));
+            }
+            self.rekey();
+        }
+        self.seeded = true;
+    }
+
+    // Reseeds the generator from the TRNG and fills `buf` from it. False
+    // when the NWP is stopped or the TRNG isn't available.
+    fn read_trng(&mut self, buf: &mut [u8]) -> bool {
+        if !SimpleLink::is_started() {
+            return false;
+        }
+        let mut seed = [0; SEED_LEN];
+        let read = Rng::fill_bytes_true(&mut seed).is_ok();
+        if read {
+            self.mix(&seed);
+            self.strong = true;
+        }
+        wipe(&mut seed);
+        read && Rng::fill_bytes_true(buf).is_ok()
+    }
+
+    fn fill_bytes(&mut self, buf: &mut [u8]) {
+        let mut block = [0; 64];
+        for chunk in buf.chunks_mut(64) {
+            self.block(&mut block);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        wipe(&mut block);
+        self.rekey();
+    }
+}
+
+fn wipe(buf: &mut [u8]) {
+    for byte in buf.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+}
+
+lazy_static! {
+    static ref SOFT_RNG: Mutex<ChaChaRng> = Mutex::new(ChaChaRng {
+        key: [0; 8],
+        counter: 0,
+        seeded: false,
+        strong: false,
+    }).unwrap();
+}
+
+// Gathers what little entropy is available without the TRNG. None of it is
+// secret.
+fn weak_seed(rng: &mut ChaChaRng) {
+    let mut seconds: u32 = 0;
+    let mut msecs: u16 = 0;
+    unsafe {
+        cc3200_sys::PRCMRTCGet(&mut seconds, &mut msecs);
+    }
+    let systick = unsafe { ptr::read_volatile(SYSTICK_CURRENT) };
+    // The MAC address can only be read while the NWP runs.
+    let id = if SimpleLink::is_started() { SimpleLink::unique_id() } else { 0 };
+
+    let mut seed = [0u8; 18];
+    for i in 0..4 {
+        seed[i] = (seconds >> (8 * i)) as u8;
+        seed[4 + i] = (systick >> (8 * i)) as u8;
+    }
+    seed[8] = msecs as u8;
+    seed[9] = (msecs >> 8) as u8;
+    for i in 0..8 {
+        seed[10 + i] = (id >> (8 * i)) as u8;
+    }
+    rng.mix(&seed);
+}
+
+pub struct Rng {}
+
+impl Rng {
+    /// Fills `buf` from the NWP true random number generator. Fails if the
+    /// NWP is stopped, or if the TRNG is not available, as with the 1.x
+    /// SDKs.
+    pub fn fill_bytes_true(buf: &mut [u8]) -> Result<(), Error> {
+        let mut pos = 0;
+        while pos < buf.len() {
+            let end = cmp::min(pos + TRNG_CHUNK_LEN, buf.len());
+            let len = try!(SimpleLink::netutil_get_true_random(&mut buf[pos..end]));
+            if len == 0 {
+                return Err(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED).into());
+            }
+            pos += len;
+        }
+        Ok(())
+    }
+
+    /// Fills `buf` with random bytes, from the TRNG when possible and from
+    /// the software generator otherwise. Fails if the TRNG was never read
+    /// and the application didn't call `Rng::seed`, which is always the
+    /// case with the 1.x SDKs until it does.
+    pub fn fill_bytes(buf: &mut [u8]) -> Result<(), Error> {
+        let mut rng = SOFT_RNG.lock(Duration::infinite()).unwrap();
+        if rng.read_trng(buf) {
+            return Ok(());
+        }
+        if !rng.strong {
+            return Err(Error::new(ErrorKind::Device, "the random generator isn't seeded"));
+        }
+        rng.fill_bytes(buf);
+        Ok(())
+    }
+
+    /// Like `fill_bytes`, but falls back to seeding the software generator
+    /// from the RTC, the SysTick counter and the MAC address. The bytes
+    /// are then predictable: only use them where that is harmless, eg. to
+    /// spread retries.
+    pub fn fill_bytes_weak(buf: &mut [u8]) {
+        let mut rng = SOFT_RNG.lock(Duration::infinite()).unwrap();
+        if rng.read_trng(buf) {
+            return;
+        }
+        if !rng.seeded {
+            weak_seed(&mut rng);
+        }
+        rng.fill_bytes(buf);
+    }
+
+    /// Seeds the software generator with `seed`, which must be at least
+    /// `SEED_LEN` bytes that nobody else can know, eg. generated by a
+    /// server for this device. `fill_bytes` works from then on.
+    pub fn seed(seed: &[u8]) -> Result<(), Error> {
+        if seed.len() < SEED_LEN {
+            return Err(Error::new(ErrorKind::InvalidInput, "the seed is too short"));
+        }
+        let mut rng = SOFT_RNG.lock(Duration::infinite()).unwrap();
+        rng.mix(seed);
+        rng.strong = true;
+        Ok(())
+    }
+
+    /// Mixes `data` into the state of the software generator. This doesn't
+    /// seed it for `fill_bytes`, as the quality of `data` is unknown.
+    pub fn add_entropy(data: &[u8]) {
+        let mut rng = SOFT_RNG.lock(Duration::infinite()).unwrap();
+        if !rng.seeded {
+            weak_seed(&mut rng);
+        }
+        rng.mix(data);
+    }
+
+    /// True once the software generator was seeded from the TRNG or with
+    /// `Rng::seed`, ie. when `fill_bytes` works.
+    pub fn is_strongly_seeded() -> bool {
+        SOFT_RNG.lock(Duration::infinite()).unwrap().strong
+    }
+
+    pub fn next_u32() -> Result<u32, Error> {
+        let mut buf = [0; 4];
+        try!(Rng::fill_bytes(&mut buf));
+        Ok((buf[0] as u32) | ((buf[1] as u32) << 8) | ((buf[2] as u32) << 16) |
+           ((buf[3] as u32) << 24))
+    }
+
+    pub fn next_u64() -> Result<u64, Error> {
+        Ok(((try!(Rng::next_u32()) as u64) << 32) | (try!(Rng::next_u32()) as u64))
+    }
+
+    /// Returns a uniformly distributed value in `0..bound`.
+    pub fn below(bound: u32) -> Result<u32, Error> {
+        if bound == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "the bound is 0"));
+        }
+        // Rejects the values of the last incomplete range to avoid a bias.
+        let limit = u32::max_value() - u32::max_value() % bound;
+        loop {
+            let value = try!(Rng::next_u32());
+            if value < limit {
+                return Ok(value % bound);
+            }
+        }
+    }
+}
+
+/// A random (version 4) UUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uuid(pub [u8; 16]);
+
+impl Uuid {
+    pub fn new_v4() -> Result<Uuid, Error> {
+        let mut bytes = [0; 16];
+        try!(Rng::fill_bytes(&mut bytes));
+        bytes[6] = (bytes[6] & 0x0f) | 0x40; // Version 4.
+        bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant.
+        Ok(Uuid(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Uuid {
+    // Formats the UUID as xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i == 4 || i == 6 || i == 8 || i == 10 {
+                try!(write!(formatter, "-"));
+            }
+            try!(write!(formatter, "{:02x}", byte));
+        }
+        Ok(())
+    }
+}
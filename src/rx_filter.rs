@@ -0,0 +1,288 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// A builder for the NWP RX filters.
+//
+// A filter matches one header field of the received frames and applies an
+// action to the frames it matches. Filters can be chained: a filter with a
+// parent is only evaluated on the frames matched by its parent, and the
+// frames matched by no filter go to the host. The IDs returned by `add` are
+// the ones used by `SimpleLink::wlan_rx_filter` (through
+// `WlanRxFilterOpBuf::from_filters`) to enable, store and remove filters.
+//
+// Example, dropping everything but the UDP frames for port 5353:
+//
+//     let udp = try!(RxFilter::matching(Match::IpProtocol(IP_PROTOCOL_UDP)).add());
+//     let port = try!(RxFilter::not_matching(Match::DstPort(5353))
+//                         .after(udp)
+//                         .action(Action::Drop)
+//                         .add());
+//     try!(RxFilter::enable(&[udp, port]));
+
+use error::Error;
+use net::Ipv4Addr;
+use simplelink::{RxFilterCombinationOp, RxFilterCompareFunc, RxFilterHeaderField, RxFilterId,
+                 RxFilterRuleType, SimpleLink, SlRxFilterAction,
+                 SlRxFilterRule, SlRxFilterTrigger, WlanRxFilterOp, WlanRxFilterOpBuf};
+use simplelink::{RX_FILTER_ACTION_DROP, RX_FILTER_ACTION_EVENT_TO_HOST, RX_FILTER_ACTION_NULL,
+                 RX_FILTER_BINARY, RX_FILTER_ENABLE, RX_FILTER_NO_PARENT, RX_FILTER_PERSISTENT};
+
+pub use simplelink::{RX_FILTER_CONNECTION_STATE_STA_CONNECTED,
+                     RX_FILTER_CONNECTION_STATE_STA_HAS_IP,
+                     RX_FILTER_CONNECTION_STATE_STA_HAS_NO_IP,
+                     RX_FILTER_CONNECTION_STATE_STA_NOT_CONNECTED, RX_FILTER_ROLE_AP,
+                     RX_FILTER_ROLE_PROMISCUOUS, RX_FILTER_ROLE_STA};
+
+pub const IP_PROTOCOL_ICMP: u8 = 1;
+pub const IP_PROTOCOL_TCP: u8 = 6;
+pub const IP_PROTOCOL_UDP: u8 = 17;
+
+// Location of the event ID in the action arguments.
+const ACTION_ARG_EVENT: usize = 2;
+
+/// 802.11 frame types.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameType {
+    Management = 0,
+    Control = 1,
+    Data = 2,
+}
+
+/// The header field a filter compares, with the value it is compared to.
+#[derive(Debug, Clone, Copy)]
+pub enum Match {
+    FrameType(FrameType),
+    Bssid([u8; 6]),
+    MacSrc([u8; 6]),
+    MacDst([u8; 6]),
+    /// IP protocol number, see the IP_PROTOCOL_* constants.
+    IpProtocol(u8),
+    Ipv4Src(Ipv4Addr),
+    Ipv4Dst(Ipv4Addr),
+    SrcPort(u16),
+    DstPort(u16),
+    /// Source ports in the inclusive range.
+    SrcPortRange(u16, u16),
+    /// Destination ports in the inclusive range.
+    DstPortRange(u16, u16),
+}
+
+/// What happens to the frames matched by a filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// The frame is passed on, to the child filters if there are any,
+    /// otherwise to the host.
+    Pass,
+    /// The frame is dropped. A dropping filter can't have children.
+    Drop,
+    /// An event with the given ID is sent to the host. Not supported by the
+    /// current NWP firmware, which rejects it with
+    /// RULE_HEADER_ACTION_TYPE_NOT_SUPPORTED.
+    Event(u8),
+}
+
+pub struct RxFilter {
+    rule_type: RxFilterRuleType,
+    rule: SlRxFilterRule,
+    trigger: SlRxFilterTrigger,
+    action: SlRxFilterAction,
+    flags: u8,
+}
+
+impl RxFilter {
+    fn new(rule_type: RxFilterRuleType, rule: SlRxFilterRule) -> Self {
+        let mut trigger = SlRxFilterTrigger::default();
+        trigger.parent_filter_id = RX_FILTER_NO_PARENT;
+        trigger.connection_state = RX_FILTER_CONNECTION_STATE_STA_CONNECTED;
+        trigger.roles = RX_FILTER_ROLE_STA;
+        RxFilter {
+            rule_type: rule_type,
+            rule: rule,
+            trigger: trigger,
+            action: SlRxFilterAction::default(),
+            flags: RX_FILTER_BINARY | RX_FILTER_ENABLE,
+        }
+    }
+
+    fn header(m: Match, equal: bool) -> Self {
+        let (compare_func, not_compare_func) = match m {
+            Match::SrcPortRange(..) |
+            Match::DstPortRange(..) => {
+                (RxFilterCompareFunc::InBetween, RxFilterCompareFunc::NotInBetween)
+            }
+            _ => (RxFilterCompareFunc::Equal, RxFilterCompareFunc::NotEqual),
+        };
+        let compare_func = if equal { compare_func } else { not_compare_func };
+
+        // Each argument takes the size of the field, ports use the 4 bytes
+        // arguments.
+        let mut args = [0; 8];
+        let (field, len, mask_len) = match m {
+            Match::FrameType(frame_type) => {
+                args[0] = frame_type as u8;
+                (RxFilterHeaderField::FrameType, 1, 1)
+            }
+            Match::Bssid(mac) => {
+                args[..6].copy_from_slice(&mac);
+                (RxFilterHeaderField::Bssid, 6, 6)
+            }
+            Match::MacSrc(mac) => {
+                args[..6].copy_from_slice(&mac);
+                (RxFilterHeaderField::MacSrcAddr, 6, 6)
+            }
+            Match::MacDst(mac) => {
+                args[..6].copy_from_slice(&mac);
+                (RxFilterHeaderField::MacDstAddr, 6, 6)
+            }
+            Match::IpProtocol(protocol) => {
+                args[0] = protocol;
+                (RxFilterHeaderField::IpProtocol, 1, 1)
+            }
+            Match::Ipv4Src(ip) => {
+                args[..4].copy_from_slice(&ip.octets());
+                (RxFilterHeaderField::Ipv4SrcAddr, 4, 4)
+            }
+            Match::Ipv4Dst(ip) => {
+                args[..4].copy_from_slice(&ip.octets());
+                (RxFilterHeaderField::Ipv4DstAddr, 4, 4)
+            }
+            Match::SrcPort(port) => {
+                put_port(&mut args[..2], port);
+                (RxFilterHeaderField::SrcPort, 4, 2)
+            }
+            Match::DstPort(port) => {
+                put_port(&mut args[..2], port);
+                (RxFilterHeaderField::DstPort, 4, 2)
+            }
+            Match::SrcPortRange(low, high) => {
+                put_port(&mut args[..2], low);
+                put_port(&mut args[4..6], high);
+                (RxFilterHeaderField::SrcPort, 8, 2)
+            }
+            Match::DstPortRange(low, high) => {
+                put_port(&mut args[..2], low);
+                put_port(&mut args[4..6], high);
+                (RxFilterHeaderField::DstPort, 8, 2)
+            }
+        };
+        let mask = [0xff; 6];
+        let rule = SlRxFilterRule::header(field, compare_func, &args[..len], &mask[..mask_len]);
+        RxFilter::new(RxFilterRuleType::Header, rule)
+    }
+
+    /// A filter matching the frames whose field equals the value (or is in
+    /// the range).
+    pub fn matching(m: Match) -> Self {
+        RxFilter::header(m, true)
+    }
+
+    /// A filter matching the frames whose field differs from the value (or
+    /// is outside the range).
+    pub fn not_matching(m: Match) -> Self {
+        RxFilter::header(m, false)
+    }
+
+    /// A filter matching every frame.
+    pub fn always() -> Self {
+        RxFilter::new(RxFilterRuleType::AlwaysTrue, SlRxFilterRule::default())
+    }
+
+    /// A filter matching the frames matched by both `a` and `b`.
+    pub fn all(a: RxFilterId, b: RxFilterId) -> Self {
+        let rule = SlRxFilterRule::combination(RxFilterCombinationOp::And, [a, b]);
+        RxFilter::new(RxFilterRuleType::Combination, rule)
+    }
+
+    /// A filter matching the frames matched by `a` or `b`.
+    pub fn any(a: RxFilterId, b: RxFilterId) -> Self {
+        let rule = SlRxFilterRule::combination(RxFilterCombinationOp::Or, [a, b]);
+        RxFilter::new(RxFilterRuleType::Combination, rule)
+    }
+
+    /// A filter matching the frames not matched by `a`.
+    pub fn not(a: RxFilterId) -> Self {
+        let rule = SlRxFilterRule::combination(RxFilterCombinationOp::Not,
+                                               [a, RxFilterId(RX_FILTER_NO_PARENT)]);
+        RxFilter::new(RxFilterRuleType::Combination, rule)
+    }
+
+    /// Only evaluates the filter on the frames matched by `parent`.
+    pub fn after(mut self, parent: RxFilterId) -> Self {
+        self.trigger.parent_filter_id = parent.0;
+        self
+    }
+
+    /// The roles (RX_FILTER_ROLE_* bitmask) in which the filter is active.
+    /// Defaults to station.
+    pub fn roles(mut self, roles: u8) -> Self {
+        self.trigger.roles = roles;
+        self
+    }
+
+    /// The connection states (RX_FILTER_CONNECTION_STATE_* bitmask) in which
+    /// the filter is active. Defaults to connected.
+    pub fn connection_state(mut self, state: u8) -> Self {
+        self.trigger.connection_state = state;
+        self
+    }
+
+    pub fn action(mut self, action: Action) -> Self {
+        self.action = SlRxFilterAction::default();
+        match action {
+            Action::Pass => self.action.action_type = RX_FILTER_ACTION_NULL,
+            Action::Drop => self.action.action_type = RX_FILTER_ACTION_DROP,
+            Action::Event(id) => {
+                self.action.action_type = RX_FILTER_ACTION_EVENT_TO_HOST;
+                self.action.action_arg[ACTION_ARG_EVENT] = id;
+            }
+        }
+        self
+    }
+
+    /// Keeps the filter across NWP restarts once stored with `RxFilter::store`.
+    pub fn persistent(mut self) -> Self {
+        self.flags |= RX_FILTER_PERSISTENT;
+        self
+    }
+
+    /// Adds the filter disabled. Filters are enabled when added by default.
+    pub fn disabled(mut self) -> Self {
+        self.flags &= !RX_FILTER_ENABLE;
+        self
+    }
+
+    /// Adds the filter to the NWP and returns its ID.
+    pub fn add(&self) -> Result<RxFilterId, Error> {
+        SimpleLink::wlan_rx_filter_add(self.rule_type,
+                                       self.flags,
+                                       &self.rule,
+                                       &self.trigger,
+                                       &self.action)
+    }
+
+    /// Enables the filters in `ids` and disables all the others.
+    pub fn enable(ids: &[RxFilterId]) -> Result<(), Error> {
+        let filters = try!(WlanRxFilterOpBuf::from_filters(ids));
+        SimpleLink::wlan_rx_filter(WlanRxFilterOp::EnableDisable, &filters)
+    }
+
+    /// Removes the filters in `ids`. Children have to be removed before
+    /// their parent.
+    pub fn remove(ids: &[RxFilterId]) -> Result<(), Error> {
+        let filters = try!(WlanRxFilterOpBuf::from_filters(ids));
+        SimpleLink::wlan_rx_filter(WlanRxFilterOp::Remove, &filters)
+    }
+
+    /// Saves the persistent filters in `ids` to the serial flash.
+    pub fn store(ids: &[RxFilterId]) -> Result<(), Error> {
+        let filters = try!(WlanRxFilterOpBuf::from_filters(ids));
+        SimpleLink::wlan_rx_filter(WlanRxFilterOp::Store, &filters)
+    }
+}
+
+// Ports are compared in network byte order.
+fn put_port(buf: &mut [u8], port: u16) {
+    buf[0] = (port >> 8) as u8;
+    buf[1] = port as u8;
+}
@@ -0,0 +1,433 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Persistent settings.
+//
+// `Settings` is a typed key-value store kept in a failsafe file, so that a
+// power loss while saving leaves the previous settings in place. Changes
+// are made in memory and written by `save`.
+//
+// The file starts with a 16 bytes header, all little endian:
+//
+//   magic "KVS1" | schema version u16 | entry count u16 | payload length u32
+//   | CRC-32 of the payload u32
+//
+// followed by the entries:
+//
+//   key length u8 | key | value type u8 | value length u16 | value
+//
+// The Wi-Fi key isn't kept with the other settings but in a secure file,
+// which the NWP encrypts. It is public, so that it is read and rewritten
+// without a token.
+
+use collections::{String, Vec};
+
+use core::str;
+
+use error::{DataError, Error, ErrorKind};
+use hash::crc32;
+use io::{put_le, Catalog, File, FileFlags, Read, Write};
+use numeric_utils::read_le;
+use simplelink::FileSystemError;
+
+/// The file used by `Settings::open_default`.
+pub const SETTINGS_FILE: &'static str = "/sys/settings.bin";
+
+/// The size allocated to `SETTINGS_FILE`.
+pub const SETTINGS_MAX_LEN: usize = 4096;
+
+/// The secure file holding the WPA2 key, see `Settings::set_wifi_key`.
+pub const WIFI_KEY_FILE: &'static str = "/sys/wifi_key.bin";
+
+/// The longest WPA2 key: a passphrase has up to 63 characters, and a raw
+/// key 64 hex digits.
+pub const WIFI_KEY_MAX_LEN: usize = 64;
+
+const MAGIC: &'static [u8; 4] = b"KVS1";
+const HEADER_LEN: usize = 16;
+
+const TYPE_STR: u8 = 1;
+const TYPE_INT: u8 = 2;
+const TYPE_BOOL: u8 = 3;
+const TYPE_BLOB: u8 = 4;
+
+// The file doesn't hold settings, or fails its CRC check.
+fn corrupted() -> Error {
+    Error::data("settings", DataError::Corrupted)
+}
+
+// The key is empty or longer than 255 bytes, the value is longer than 65535
+// bytes, or the settings don't fit in their file.
+fn too_large() -> Error {
+    Error::data("settings", DataError::TooLarge)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    Blob(Vec<u8>),
+}
+
+/// Upgrades the settings from one schema version to the next.
+pub type Migration = fn(&mut Settings) -> Result<(), Error>;
+
+#[derive(Clone)]
+pub struct Settings {
+    file_name: String,
+    max_len: usize,
+    version: u16,
+    entries: Vec<(String, Value)>,
+}
+
+impl Settings {
+    /// Returns empty settings, which `save` writes to `file_name`.
+    pub fn new(file_name: &str, max_len: usize) -> Settings {
+        Settings {
+            file_name: String::from(file_name),
+            max_len: max_len,
+            version: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Loads the settings saved in `file_name`, or returns empty settings
+    /// if the file doesn't exist. `max_len` is the size allocated to the
+    /// file when it is first saved; it can't change afterwards.
+    pub fn open(file_name: &str, max_len: usize) -> Result<Settings, Error> {
+        let mut settings = Settings::new(file_name, max_len);
+        let mut buf = Vec::new();
+        match File::open(file_name) {
+            Ok(mut file) => {
+                try!(file.read_to_end(&mut buf));
+            }
+            Err(ref err) if err.is_fs(FileSystemError::FILE_NOT_EXISTS) => {
+                return Ok(settings);
+            }
+            Err(err) => return Err(err),
+        }
+        try!(settings.decode(&buf));
+        Ok(settings)
+    }
+
+    /// Loads the settings from `SETTINGS_FILE`.
+    pub fn open_default() -> Result<Settings, Error> {
+        Settings::open(SETTINGS_FILE, SETTINGS_MAX_LEN)
+    }
+
+    /// Loads the settings from `SETTINGS_FILE`, or returns empty settings if
+    /// they can't be loaded. The file is replaced when they are next saved.
+    pub fn open_or_default() -> Settings {
+        Settings::open_default().unwrap_or_else(|_| Settings::new(SETTINGS_FILE, SETTINGS_MAX_LEN))
+    }
+
+    /// Writes the settings, and records their file in the default catalog.
+    /// The file keeps its previous content until the new one is completely
+    /// written.
+    pub fn save(&self) -> Result<(), Error> {
+        let buf = self.encode();
+        if buf.len() > self.max_len {
+            return Err(too_large());
+        }
+        Catalog::with_default(|catalog| {
+            let flags = FileFlags { failsafe: true, ..FileFlags::default() };
+            let mut file = try!(catalog.create(&self.file_name, self.max_len, flags, None));
+            if buf.len() as u64 > file.capacity() {
+                try!(file.abort());
+                return Err(too_large());
+            }
+            try!(file.write_all(&buf));
+            catalog.close(file)
+        })
+    }
+
+    /// Returns the schema version, 0 for settings that were never migrated.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Brings the settings to version `migrations.len()`, running
+    /// `migrations[n]` to go from version `n` to `n + 1`, and saves them.
+    /// Returns whether any migration ran.
+    pub fn migrate(&mut self, migrations: &[Migration]) -> Result<bool, Error> {
+        let target = migrations.len();
+        if self.version as usize > target {
+            // The settings were saved by a newer schema version than the
+            // migrations know about.
+            return Err(Error::data("settings", DataError::UnsupportedVersion(self.version)));
+        }
+        if self.version as usize == target {
+            return Ok(false);
+        }
+        // The steps run on a copy, so that the settings are left at their
+        // version if any of them or the save fails.
+        let mut migrated = self.clone();
+        while (migrated.version as usize) < target {
+            try!(migrations[migrated.version as usize](&mut migrated));
+            migrated.version += 1;
+        }
+        try!(migrated.save());
+        *self = migrated;
+        Ok(true)
+    }
+
+    /// Returns the SSID set by "wifi.ssid", or `default_ssid`, and the key
+    /// set by `set_wifi_key`, if any.
+    pub fn wifi_credentials<'a>(&'a self,
+                                default_ssid: &'a str)
+                                -> Result<(&'a str, Option<String>), Error> {
+        let ssid = self.get_str_or("wifi.ssid", default_ssid);
+        let key = try!(Settings::wifi_key());
+        Ok((ssid, key))
+    }
+
+    /// Returns the WPA2 key saved in `WIFI_KEY_FILE`, if any.
+    pub fn wifi_key() -> Result<Option<String>, Error> {
+        let mut file = match File::open(WIFI_KEY_FILE) {
+            Ok(file) => file,
+            Err(ref err) if err.is_fs(FileSystemError::FILE_NOT_EXISTS) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut buf = Vec::new();
+        try!(file.read_to_end(&mut buf));
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let key = try!(String::from_utf8(buf).map_err(|_| corrupted()));
+        Ok(Some(key))
+    }
+
+    /// Saves the WPA2 key in `WIFI_KEY_FILE`, or clears it if `key` is None.
+    pub fn set_wifi_key(key: Option<&str>) -> Result<(), Error> {
+        let key = key.unwrap_or("");
+        if key.len() > WIFI_KEY_MAX_LEN {
+            return Err(Error::new(ErrorKind::InvalidInput, "the Wi-Fi key is too long"));
+        }
+        let flags = FileFlags {
+            failsafe: true,
+            secure: true,
+            no_signature_test: true,
+            public_write: true,
+            public_read: true,
+            ..FileFlags::default()
+        };
+        Catalog::with_default(|catalog| {
+            let mut file = try!(catalog.create(WIFI_KEY_FILE, WIFI_KEY_MAX_LEN, flags, None));
+            try!(file.write_all(key.as_bytes()));
+            catalog.close(file)
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|entry| entry.0 == key).map(|entry| &entry.1)
+    }
+
+    // The typed getters return None when the key holds another type.
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(&Value::Str(ref value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_str_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.get_str(key).unwrap_or(default)
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        match self.get(key) {
+            Some(&Value::Int(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_int_or(&self, key: &str, default: i64) -> i64 {
+        self.get_int(key).unwrap_or(default)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key) {
+            Some(&Value::Bool(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool_or(&self, key: &str, default: bool) -> bool {
+        self.get_bool(key).unwrap_or(default)
+    }
+
+    pub fn get_blob(&self, key: &str) -> Option<&[u8]> {
+        match self.get(key) {
+            Some(&Value::Blob(ref value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_blob_or<'a>(&'a self, key: &str, default: &'a [u8]) -> &'a [u8] {
+        self.get_blob(key).unwrap_or(default)
+    }
+
+    /// Sets `key`, replacing its previous value whatever its type.
+    pub fn set(&mut self, key: &str, value: Value) -> Result<(), Error> {
+        let value_len = match value {
+            Value::Str(ref value) => value.len(),
+            Value::Blob(ref value) => value.len(),
+            _ => 0,
+        };
+        if key.is_empty() || key.len() > 0xff || value_len > 0xffff {
+            return Err(too_large());
+        }
+        match self.entries.iter().position(|entry| entry.0 == key) {
+            Some(index) => self.entries[index].1 = value,
+            None => self.entries.push((String::from(key), value)),
+        }
+        Ok(())
+    }
+
+    pub fn set_str(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.set(key, Value::Str(String::from(value)))
+    }
+
+    pub fn set_int(&mut self, key: &str, value: i64) -> Result<(), Error> {
+        self.set(key, Value::Int(value))
+    }
+
+    pub fn set_bool(&mut self, key: &str, value: bool) -> Result<(), Error> {
+        self.set(key, Value::Bool(value))
+    }
+
+    pub fn set_blob(&mut self, key: &str, value: &[u8]) -> Result<(), Error> {
+        let mut blob = Vec::with_capacity(value.len());
+        blob.extend_from_slice(value);
+        self.set(key, Value::Blob(blob))
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        match self.entries.iter().position(|entry| entry.0 == key) {
+            Some(index) => Some(self.entries.remove(index).1),
+            None => None,
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the keys, in the order they were first set.
+    pub fn keys(&self) -> Keys {
+        Keys {
+            entries: &self.entries,
+            index: 0,
+        }
+    }
+
+    /// Removes all the keys. The schema version is kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for &(ref key, ref value) in &self.entries {
+            payload.push(key.len() as u8);
+            payload.extend_from_slice(key.as_bytes());
+            let (value_type, bytes) = match *value {
+                Value::Str(ref value) => (TYPE_STR, Vec::from(value.as_bytes())),
+                Value::Int(value) => {
+                    let mut bytes = Vec::with_capacity(8);
+                    put_le(&mut bytes, value as u64, 8);
+                    (TYPE_INT, bytes)
+                }
+                Value::Bool(value) => (TYPE_BOOL, vec![value as u8]),
+                Value::Blob(ref value) => (TYPE_BLOB, value.clone()),
+            };
+            payload.push(value_type);
+            put_le(&mut payload, bytes.len() as u64, 2);
+            payload.extend_from_slice(&bytes);
+        }
+
+        let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+        buf.extend_from_slice(MAGIC);
+        put_le(&mut buf, self.version as u64, 2);
+        put_le(&mut buf, self.entries.len() as u64, 2);
+        put_le(&mut buf, payload.len() as u64, 4);
+        put_le(&mut buf, crc32(&payload) as u64, 4);
+        buf.extend_from_slice(&payload);
+        buf
+    }
+
+    fn decode(&mut self, buf: &[u8]) -> Result<(), Error> {
+        if buf.len() < HEADER_LEN || &buf[..4] != MAGIC {
+            return Err(corrupted());
+        }
+        let version = read_le(&buf[4..6]) as u16;
+        let count = read_le(&buf[6..8]) as usize;
+        let payload_len = read_le(&buf[8..12]) as usize;
+        let crc = read_le(&buf[12..16]) as u32;
+        let payload = &buf[HEADER_LEN..];
+        if payload.len() != payload_len || crc32(payload) != crc {
+            return Err(corrupted());
+        }
+
+        let mut entries = Vec::with_capacity(count);
+        let mut pos = 0;
+        for _ in 0..count {
+            let key_len = *try!(payload.get(pos).ok_or_else(corrupted)) as usize;
+            let key = try!(slice(payload, pos + 1, key_len));
+            let key = try!(str::from_utf8(key).map_err(|_| corrupted()));
+            pos += 1 + key_len;
+
+            let header = try!(slice(payload, pos, 3));
+            let value_len = read_le(&header[1..3]) as usize;
+            let bytes = try!(slice(payload, pos + 3, value_len));
+            pos += 3 + value_len;
+
+            let value = match (header[0], value_len) {
+                (TYPE_STR, _) => {
+                    let value = try!(str::from_utf8(bytes).map_err(|_| corrupted()));
+                    Value::Str(String::from(value))
+                }
+                (TYPE_INT, 8) => Value::Int(read_le(bytes) as i64),
+                (TYPE_BOOL, 1) if bytes[0] <= 1 => Value::Bool(bytes[0] == 1),
+                (TYPE_BLOB, _) => Value::Blob(Vec::from(bytes)),
+                _ => return Err(corrupted()),
+            };
+            entries.push((String::from(key), value));
+        }
+        if pos != payload.len() {
+            return Err(corrupted());
+        }
+
+        self.version = version;
+        self.entries = entries;
+        Ok(())
+    }
+}
+
+pub struct Keys<'a> {
+    entries: &'a [(String, Value)],
+    index: usize,
+}
+
+impl<'a> Iterator for Keys<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let entry = self.entries.get(self.index);
+        self.index += 1;
+        entry.map(|entry| &entry.0[..])
+    }
+}
+
+// Returns `len` bytes of `buf` from `pos`, failing if the entry overruns
+// the payload.
+fn slice(buf: &[u8], pos: usize, len: usize) -> Result<&[u8], Error> {
+    if pos + len > buf.len() {
+        return Err(corrupted());
+    }
+    Ok(&buf[pos..pos + len])
+}
@@ -4,53 +4,272 @@
 
 extern crate cc3200_sys;
 
+use core::cmp;
 use core::convert::TryFrom;
+use core::fmt;
 use core::mem;
 use core::ptr;
 use core::slice;
 use core::str;
+use core::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+
+use freertos_rs::{CurrentTask, Duration, Mutex, MutexGuard};
+
+use error::{Error, ErrorKind};
+use events::{BSSID_LEN, SSID_LEN_MAX};
+use net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use rtc::RTC;
+use time::Tm;
 
 pub use self::cc3200_sys::simplelink::*;
 
+// Every SimpleLink call but sl_Start needs the NWP to be running. `$decode`
+// turns the negative return codes into the error of the API called.
+macro_rules! try_sl {
+    ($decode:path, $e:expr) => ({
+        try!(SimpleLink::check_started());
+        let rc = unsafe { $e };
+        if rc < 0 {
+            return Err($decode(rc as i32).into());
+        }
+        rc
+    })
+}
+
 macro_rules! try_wlan {
+    ($e:expr) => (try_sl!(SimpleLinkError::from_wlan_code, $e) as i16)
+}
+
+macro_rules! try_device {
+    ($e:expr) => (try_sl!(SimpleLinkError::from_device_code, $e))
+}
+
+macro_rules! try_netapp {
+    ($e:expr) => (try_sl!(SimpleLinkError::from_netapp_code, $e))
+}
+
+// The RX filter functions return positive RxFilterError codes.
+macro_rules! try_rx_filter {
     ($e:expr) => ({
+        try!(SimpleLink::check_started());
         let rc: i16 = unsafe { $e };
         if rc < 0 {
-            return Err(SimpleLinkError::Wlan(try!(WlanError::try_from(rc))));
+            return Err(SimpleLinkError::from_wlan_code(rc as i32).into());
+        }
+        if rc > 0 {
+            return Err(SimpleLinkError::RxFilter(try!(RxFilterError::try_from(rc))).into());
         }
         rc
     })
 }
 
 lazy_static! {
-    static ref UNIQUE_ID: u64 = {
-        let mut mac_addr: [u8; SL_MAC_ADDR_LEN] = [0; SL_MAC_ADDR_LEN];
-        if SimpleLink::netcfg_get_mac_addr(&mut mac_addr).is_ok() {
-            ((mac_addr[0] as u64) << 40) | ((mac_addr[1] as u64) << 32) |
-            ((mac_addr[2] as u64) << 24) | ((mac_addr[3] as u64) << 16) |
-            ((mac_addr[4] as u64) << 8) | (mac_addr[5] as u64)
-        } else {
-            0
+    // The MAC address once it was read, 0 before.
+    static ref UNIQUE_ID: Mutex<u64> = Mutex::new(0).unwrap();
+}
+
+lazy_static! {
+    // Serialises the starts and stops done by the sessions, and tells
+    // whether a session is open.
+    static ref SESSION: Mutex<bool> = Mutex::new(false).unwrap();
+}
+
+lazy_static! {
+    // Serialises the changes to the default file catalog, see
+    // `io::Catalog::with_default`.
+    static ref CATALOG: Mutex<()> = Mutex::new(()).unwrap();
+}
+
+// Set while the NWP is running.
+static STARTED: AtomicBool = ATOMIC_BOOL_INIT;
+
+// Interval used when polling the status bits while waiting for an event.
+const EVENT_POLL_INTERVAL_MS: u32 = 100;
+
+/// The enrollment methods supported by WPS.
+#[derive(Debug, Clone, Copy)]
+pub enum WpsMethod<'a> {
+    /// Push-button configuration: the button has to be pressed on the
+    /// access point during the enrollment window.
+    PushButton,
+
+    /// PIN enrollment: the 4 or 8 digit PIN has to be entered on the
+    /// access point.
+    Pin(&'a str),
+}
+
+/// The access point the station is connected to.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionInfo {
+    ssid: [u8; SSID_LEN_MAX],
+    ssid_len: u8,
+    pub bssid: [u8; BSSID_LEN],
+}
+
+impl ConnectionInfo {
+    pub fn ssid(&self) -> &str {
+        str::from_utf8(&self.ssid[..self.ssid_len as usize]).unwrap_or("")
+    }
+}
+
+/// IPv4 configuration of the station (or P2P client) interface.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ipv4Config {
+    pub ip: Ipv4Addr,
+    pub mask: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub dns: Ipv4Addr,
+}
+
+impl Ipv4Config {
+    fn to_args(&self) -> SlNetCfgIpV4Args {
+        SlNetCfgIpV4Args {
+            ip: self.ip.0,
+            mask: self.mask.0,
+            gateway: self.gateway.0,
+            dns_server: self.dns.0,
         }
-    };
+    }
+
+    fn from_args(args: &SlNetCfgIpV4Args) -> Self {
+        Ipv4Config {
+            ip: Ipv4Addr(args.ip),
+            mask: Ipv4Addr(args.mask),
+            gateway: Ipv4Addr(args.gateway),
+            dns: Ipv4Addr(args.dns_server),
+        }
+    }
 }
 
+/// The current addressing of the station interface.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ipv4Info {
+    pub config: Ipv4Config,
+    /// True when the addressing was obtained through DHCP.
+    pub dhcp: bool,
+}
+
+/// The IPv6 addressing of the station interface.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ipv6Info {
+    pub ip: Ipv6Addr,
+    pub gateway: Ipv6Addr,
+    pub dns: Ipv6Addr,
+}
+
+/// Selects what `SimpleLink::restore_defaults` resets. Everything is reset
+/// by default.
+#[derive(Debug, Clone, Copy)]
+pub struct RestoreOptions {
+    /// Delete all the stored WLAN profiles.
+    pub delete_profiles: bool,
+    /// Enable the DHCP client, dropping any static IPv4 configuration.
+    pub enable_dhcp: bool,
+    /// Disable the periodic scan.
+    pub disable_scan: bool,
+    /// Station TX power, as a 0-15 dB offset from the maximum power.
+    pub sta_tx_power: u8,
+    /// Unregister all the mDNS services.
+    pub unregister_mdns: bool,
+    /// Remove all the RX filters.
+    pub remove_rx_filters: bool,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        RestoreOptions {
+            delete_profiles: true,
+            enable_dhcp: true,
+            disable_scan: true,
+            sta_tx_power: 0,
+            unregister_mdns: true,
+            remove_rx_filters: true,
+        }
+    }
+}
+
+/// A snapshot of the NWP configuration, as returned by
+/// `SimpleLink::dump_config`. Its `Display` output is meant to be attached to
+/// support tickets.
+pub struct NwpConfig {
+    pub version: SlVersionFull,
+    pub mac_addr: [u8; SL_MAC_ADDR_LEN],
+    pub ipv4: Ipv4Info,
+    pub connection_policy: ConnectionPolicy,
+    pub power_policy: PowerPolicy,
+    pub sta_tx_power: u8,
+    pub country_code: [u8; 2],
+}
+
+impl fmt::Display for NwpConfig {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let ver = &self.version;
+        let mac = &self.mac_addr;
+        try!(writeln!(formatter, "Host driver: {}", SimpleLink::get_driver_version()));
+        try!(writeln!(formatter,
+                      "NWP: {}.{}.{}.{} FW: {}.{}.{}.{} PHY: {}.{}.{}.{} ROM: {} Chip: {:#x}",
+                      ver.nwp_version[0],
+                      ver.nwp_version[1],
+                      ver.nwp_version[2],
+                      ver.nwp_version[3],
+                      ver.fw_version[0],
+                      ver.fw_version[1],
+                      ver.fw_version[2],
+                      ver.fw_version[3],
+                      ver.phy_version[0],
+                      ver.phy_version[1],
+                      ver.phy_version[2],
+                      ver.phy_version[3],
+                      ver.rom_version,
+                      ver.chip_id));
+        try!(writeln!(formatter,
+                      "MAC: {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                      mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]));
+        try!(writeln!(formatter,
+                      "IPv4: {} mask {} gateway {} dns {} ({})",
+                      self.ipv4.config.ip,
+                      self.ipv4.config.mask,
+                      self.ipv4.config.gateway,
+                      self.ipv4.config.dns,
+                      if self.ipv4.dhcp { "dhcp" } else { "static" }));
+        try!(writeln!(formatter,
+                      "Connection policy: {} Power policy: {:?}",
+                      self.connection_policy,
+                      self.power_policy));
+        write!(formatter,
+               "STA TX power: -{} dB Country: {}{}",
+               self.sta_tx_power,
+               self.country_code[0] as char,
+               self.country_code[1] as char)
+    }
+}
+
+// How long to wait for the NWP to report a state change while restoring
+// the defaults.
+const RESTORE_EVENT_TIMEOUT_MS: u32 = 5000;
+
 pub struct SimpleLink { }
 
 impl SimpleLink {
-    pub fn start_spawn_task() -> Result<(), SimpleLinkError> {
+    pub fn start_spawn_task() -> Result<(), Error> {
         let rc = unsafe { VStartSimpleLinkSpawnTask(SPAWN_TASK_PRIORITY) };
         if rc < 0 {
-            Err(SimpleLinkError::Osi(try!(OsiError::try_from(rc))))
+            Err(SimpleLinkError::Osi(try!(OsiError::try_from(rc))).into())
         } else {
             Ok(())
         }
     }
 
-    pub fn get_version() -> SlVersionFull {
+    pub fn get_version() -> Result<SlVersionFull, Error> {
         let mut version: SlVersionFull = Default::default();
-        unsafe { simplelink_get_version(&mut version) };
-        version
+        {
+            let val = unsafe {
+                slice::from_raw_parts_mut(&mut version as *mut _ as *mut u8,
+                                          mem::size_of::<SlVersionFull>())
+            };
+            try!(SimpleLink::dev_get(DeviceConfig::GeneralVersion, val));
+        }
+        Ok(version)
     }
 
     pub fn get_driver_version() -> &'static str {
@@ -61,6 +280,76 @@ impl SimpleLink {
         }
     }
 
+    // Device
+
+    pub fn dev_get(config: DeviceConfig, val: &mut [u8]) -> Result<&mut [u8], Error> {
+        let config_id = ((config as u32 & 0xff00) >> 8) as u8;
+        let mut config_opt = (config as u32 & 0x00ff) as u8;
+        let mut len = val.len() as u8;
+        try_device!(sl_DevGet(config_id, &mut config_opt, &mut len, val.as_mut_ptr()));
+        Ok(&mut val[0..len as usize])
+    }
+
+    pub fn dev_set(config: DeviceConfig, val: &[u8]) -> Result<(), Error> {
+        let config_id = ((config as u32 & 0xff00) >> 8) as u8;
+        let config_opt = (config as u32 & 0x00ff) as u8;
+        try_device!(sl_DevSet(config_id, config_opt, val.len() as u8, val.as_ptr()));
+        Ok(())
+    }
+
+    /// Reads the status of an event class (one of the DeviceConfig::Status*
+    /// options), see the STATUS_* and EVENT_DROPPED_* bits.
+    pub fn dev_get_status(class: DeviceConfig) -> Result<u32, Error> {
+        let mut status = [0; 4];
+        try!(SimpleLink::dev_get(class, &mut status));
+        Ok((status[0] as u32) | (status[1] as u32) << 8 | (status[2] as u32) << 16 |
+           (status[3] as u32) << 24)
+    }
+
+    /// Reads the NWP date and time. Only the date and time of day fields of
+    /// the result are set.
+    pub fn dev_get_date_time() -> Result<Tm, Error> {
+        let mut date_time: SlDateTime = Default::default();
+        {
+            let val = unsafe {
+                slice::from_raw_parts_mut(&mut date_time as *mut _ as *mut u8,
+                                          mem::size_of::<SlDateTime>())
+            };
+            try!(SimpleLink::dev_get(DeviceConfig::GeneralDateTime, val));
+        }
+        let mut tm = Tm::new();
+        tm.tm_sec = date_time.sec as i32;
+        tm.tm_min = date_time.min as i32;
+        tm.tm_hour = date_time.hour as i32;
+        tm.tm_mday = date_time.day as i32;
+        tm.tm_mon = date_time.mon as i32 - 1;
+        tm.tm_year = date_time.year as i32 - 1900;
+        Ok(tm)
+    }
+
+    /// Sets the NWP date and time, which is used to check the validity dates
+    /// of the certificates. The NWP keeps it until it is reset.
+    pub fn dev_set_date_time(tm: &Tm) -> Result<(), Error> {
+        let mut date_time: SlDateTime = Default::default();
+        date_time.sec = tm.tm_sec as u32;
+        date_time.min = tm.tm_min as u32;
+        date_time.hour = tm.tm_hour as u32;
+        date_time.day = tm.tm_mday as u32;
+        date_time.mon = (tm.tm_mon + 1) as u32;
+        date_time.year = (tm.tm_year + 1900) as u32;
+        let val = unsafe {
+            slice::from_raw_parts(&date_time as *const _ as *const u8,
+                                  mem::size_of::<SlDateTime>())
+        };
+        SimpleLink::dev_set(DeviceConfig::GeneralDateTime, val)
+    }
+
+    /// Copies the RTC time into the NWP. Call it after each start of the NWP
+    /// and each time the RTC is set.
+    pub fn dev_set_date_time_from_rtc() -> Result<(), Error> {
+        SimpleLink::dev_set_date_time(&Tm::gmtime(RTC::get()))
+    }
+
     // App Variables
 
     pub fn init_app_variables() {
@@ -89,38 +378,269 @@ impl SimpleLink {
         unsafe { simplelink_gateway_ip() }
     }
 
+    /// Returns the access point the station is connected to, if any.
+    pub fn connection_info() -> Option<ConnectionInfo> {
+        let mut info = ConnectionInfo {
+            ssid: [0; SSID_LEN_MAX],
+            ssid_len: 0,
+            bssid: [0; BSSID_LEN],
+        };
+        info.ssid_len = unsafe {
+            simplelink_get_connection_info(info.ssid.as_mut_ptr(), info.bssid.as_mut_ptr())
+        };
+        if info.ssid_len == 0 {
+            None
+        } else {
+            Some(info)
+        }
+    }
+
     pub fn ping_packets_received() -> u32 {
         unsafe { simplelink_ping_packets_received() }
     }
 
     // Device
 
-    pub fn start() -> Result<WlanMode, SimpleLinkError> {
-        let rc = try_wlan!(sl_Start(ptr::null(), ptr::null(), None));
+    // The NWP is started and stopped through `SimpleLinkSession`, which
+    // serialises it between the tasks.
+
+    fn start() -> Result<WlanMode, Error> {
+        let rc = unsafe { sl_Start(ptr::null(), ptr::null(), None) };
+        if rc < 0 {
+            return Err(SimpleLinkError::Wlan(try!(WlanError::try_from(rc))).into());
+        }
+        STARTED.store(true, Ordering::SeqCst);
         Ok(try!(WlanMode::try_from(rc)))
     }
 
-    pub fn stop(timeout_msecs: u16) -> Result<WlanMode, SimpleLinkError> {
+    fn stop(timeout_msecs: u16) -> Result<WlanMode, Error> {
         let rc = try_wlan!(sl_Stop(timeout_msecs));
+        STARTED.store(false, Ordering::SeqCst);
         Ok(try!(WlanMode::try_from(rc)))
     }
 
+    /// Whether the NWP is running, ie. a `SimpleLinkSession` is open.
+    pub fn is_started() -> bool {
+        STARTED.load(Ordering::SeqCst)
+    }
+
+    /// Fails with `SimpleLinkError::NotStarted`, of the `Device` kind, if the
+    /// NWP isn't running.
+    pub fn check_started() -> Result<(), Error> {
+        if SimpleLink::is_started() {
+            Ok(())
+        } else {
+            Err(SimpleLinkError::NotStarted.into())
+        }
+    }
+
+    /// Locks the default file catalog, so that the tasks creating files
+    /// don't lose each other's changes to it.
+    pub fn lock_catalog() -> Result<MutexGuard<'static, ()>, Error> {
+        CATALOG.lock(Duration::infinite())
+            .map_err(|_| Error::from(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED)))
+    }
+
+    // Starts the NWP in `mode`, restarting it if it came up in another
+    // role. The NWP must be stopped.
+    fn start_in_mode(mode: WlanMode) -> Result<(), Error> {
+        SimpleLink::init_app_variables();
+        if try!(SimpleLink::start()) == mode {
+            return Ok(());
+        }
+        try!(SimpleLink::wlan_set_mode(mode));
+        try!(SimpleLink::stop(SL_STOP_TIMEOUT));
+        SimpleLink::init_app_variables();
+        if try!(SimpleLink::start()) != mode {
+            return Err(SimpleLinkError::Wlan(WlanError::INVALID_ROLE).into());
+        }
+        Ok(())
+    }
+
+    /// The MAC address as a number. It is read from the NWP the first time,
+    /// so this is 0 until it was called while the NWP runs.
     pub fn unique_id() -> u64 {
-        *UNIQUE_ID
+        let mut id = UNIQUE_ID.lock(Duration::infinite()).unwrap();
+        if *id == 0 {
+            let mut mac_addr: [u8; SL_MAC_ADDR_LEN] = [0; SL_MAC_ADDR_LEN];
+            if SimpleLink::netcfg_get_mac_addr(&mut mac_addr).is_ok() {
+                *id = ((mac_addr[0] as u64) << 40) | ((mac_addr[1] as u64) << 32) |
+                      ((mac_addr[2] as u64) << 24) | ((mac_addr[3] as u64) << 16) |
+                      ((mac_addr[4] as u64) << 8) | (mac_addr[5] as u64);
+            }
+        }
+        *id
+    }
+
+    /// Puts the NWP back into its default state: station role, auto +
+    /// SmartConfig connection policy, normal power policy and whatever
+    /// `options` selects. No session can be open; the NWP is left stopped,
+    /// and has to be started again with a `SimpleLinkSession`.
+    pub fn restore_defaults(options: &RestoreOptions) -> Result<(), Error> {
+        let open = try!(SimpleLinkSession::lock());
+        if *open {
+            return Err(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED).into());
+        }
+        let result = SimpleLink::restore_defaults_locked(options);
+        if result.is_err() && SimpleLink::is_started() {
+            let _ = SimpleLink::stop(SL_STOP_TIMEOUT);
+        }
+        SimpleLink::init_app_variables();
+        result
+    }
+
+    fn restore_defaults_locked(options: &RestoreOptions) -> Result<(), Error> {
+        let mode = try!(SimpleLink::start());
+        if mode != WlanMode::ROLE_STA {
+            if mode == WlanMode::ROLE_AP {
+                // If the device is in AP mode, then we need to wait for the
+                // acquired event before doing anything.
+                SimpleLink::wait_for(|| SimpleLink::is_ip_acquired(), RESTORE_EVENT_TIMEOUT_MS);
+            }
+
+            // Switch to STA mode and restart
+            try!(SimpleLink::wlan_set_mode(WlanMode::ROLE_STA));
+            try!(SimpleLink::stop(SL_STOP_TIMEOUT));
+            let mode = try!(SimpleLink::start());
+            if mode != WlanMode::ROLE_STA {
+                return Err(Error::new(ErrorKind::Device,
+                                      "the NWP did not switch to station mode"));
+            }
+        }
+
+        let ver = try!(SimpleLink::get_version());
+        info!("Host Driver Version: {}", SimpleLink::get_driver_version());
+        info!("Build Version {}.{}.{}.{}.31.{}.{}.{}.{}.{}.{}.{}.{}",
+              ver.nwp_version[0],
+              ver.nwp_version[1],
+              ver.nwp_version[2],
+              ver.nwp_version[3],
+              ver.fw_version[0],
+              ver.fw_version[1],
+              ver.fw_version[2],
+              ver.fw_version[3],
+              ver.phy_version[0],
+              ver.phy_version[1],
+              ver.phy_version[2],
+              ver.phy_version[3]);
+
+        // Set connection policy to Auto + SmartConfig
+        //      (Device's default connection policy)
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Connection(ConnectionPolicy::default())));
+
+        if options.delete_profiles {
+            try!(SimpleLink::wlan_delete_profile(0xff));
+        }
+
+        // Device is in station mode. Disconnect previous connection, if any.
+        if SimpleLink::wlan_disconnect().is_ok() {
+            // This means that we were previously connected. Wait for the
+            // disconnection event.
+            SimpleLink::wait_for(|| !SimpleLink::is_connected(), RESTORE_EVENT_TIMEOUT_MS);
+        }
+
+        if options.enable_dhcp {
+            try!(SimpleLink::netcfg_set_ipv4_dhcp());
+        }
+
+        if options.disable_scan {
+            try!(SimpleLink::wlan_set_policy(WlanPolicy::Scan(ScanPolicy::Disabled)));
+        }
+
+        // Number between 0-15, as dB offset from max power - 0 will set max power
+        try!(SimpleLink::wlan_set(WlanConfig::GeneralStaTxPower, &[options.sta_tx_power]));
+
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Power(PowerPolicy::Normal)));
+
+        if options.unregister_mdns {
+            try!(SimpleLink::netapp_mdns_unregister_service(""));
+        }
+
+        if options.remove_rx_filters {
+            // Remove  all 64 filters (8*8)
+            let all_filters = WlanRxFilterOpBuf::all_filters();
+            try!(SimpleLink::wlan_rx_filter(WlanRxFilterOp::Remove, &all_filters));
+        }
+
+        try!(SimpleLink::stop(SL_STOP_TIMEOUT));
+        Ok(())
+    }
+
+    /// Reads the current NWP configuration. The NWP must be started.
+    pub fn dump_config() -> Result<NwpConfig, Error> {
+        let mut mac_addr = [0; SL_MAC_ADDR_LEN];
+        try!(SimpleLink::netcfg_get_mac_addr(&mut mac_addr));
+
+        let mut sta_tx_power = [0; 1];
+        try!(SimpleLink::wlan_get(WlanConfig::GeneralStaTxPower, &mut sta_tx_power));
+        let mut country_code = [0; 2];
+        try!(SimpleLink::wlan_get(WlanConfig::GeneralCountryCode, &mut country_code));
+
+        Ok(NwpConfig {
+            version: try!(SimpleLink::get_version()),
+            mac_addr: mac_addr,
+            ipv4: try!(SimpleLink::netcfg_get_ipv4_info()),
+            connection_policy: try!(SimpleLink::wlan_get_connection_policy()),
+            power_policy: try!(SimpleLink::wlan_get_power_policy()),
+            sta_tx_power: sta_tx_power[0],
+            country_code: country_code,
+        })
+    }
+
+    // Polls `condition` until it's true or `timeout_ms` elapsed. Returns the
+    // last value of `condition`.
+    fn wait_for<F: Fn() -> bool>(condition: F, timeout_ms: u32) -> bool {
+        let mut elapsed_ms = 0;
+        while !condition() {
+            if elapsed_ms >= timeout_ms {
+                return false;
+            }
+            CurrentTask::delay(Duration::ms(EVENT_POLL_INTERVAL_MS));
+            elapsed_ms += EVENT_POLL_INTERVAL_MS;
+        }
+        true
     }
 
     // Net App
 
-    pub fn netapp_get_host_by_name(name: &str) -> Result<u32, SimpleLinkError> {
+    pub fn netapp_get_host_by_name(name: &str) -> Result<u32, Error> {
         let mut out_ip_addr: u32 = 0;
-        try_wlan!(sl_NetAppDnsGetHostByName(name.as_ptr(),
-                                            name.len() as u16,
-                                            &mut out_ip_addr as *mut u32,
-                                            SocketFamily::AF_INET as u8));
+        try_netapp!(sl_NetAppDnsGetHostByName(name.as_ptr(),
+                                              name.len() as u16,
+                                              &mut out_ip_addr as *mut u32,
+                                              SocketFamily::AF_INET as u8));
         Ok(out_ip_addr)
     }
 
-    pub fn netapp_mdns_unregister_service(name: &str) -> Result<(), SimpleLinkError> {
+    /// Resolves the AAAA record of `name`.
+    pub fn netapp_get_host_by_name_v6(name: &str) -> Result<Ipv6Addr, Error> {
+        let mut out_ip_addr: [u32; 4] = [0; 4];
+        try_netapp!(sl_NetAppDnsGetHostByName(name.as_ptr(),
+                                              name.len() as u16,
+                                              out_ip_addr.as_mut_ptr(),
+                                              SocketFamily::AF_INET6 as u8));
+        Ok(Ipv6Addr::from_words(&out_ip_addr))
+    }
+
+    /// Resolves `name` to an address, trying the A record first and then
+    /// the AAAA one so that IPv6 only hosts and networks work. Address
+    /// literals are returned as is.
+    pub fn netapp_resolve(name: &str) -> Result<IpAddr, Error> {
+        if let Some(ip) = IpAddr::parse(name) {
+            return Ok(ip);
+        }
+        match SimpleLink::netapp_get_host_by_name(name) {
+            Ok(ip) => Ok(IpAddr::V4(Ipv4Addr(ip))),
+            Err(err) => {
+                match SimpleLink::netapp_get_host_by_name_v6(name) {
+                    Ok(ip) => Ok(IpAddr::V6(ip)),
+                    Err(_) => Err(err),
+                }
+            }
+        }
+    }
+
+    pub fn netapp_mdns_unregister_service(name: &str) -> Result<(), Error> {
         let name_len = name.len() as u8;
         let name_ptr = {
             if name_len > 0 {
@@ -129,57 +649,164 @@ impl SimpleLink {
                 ptr::null()
             }
         };
-        try_wlan!(sl_NetAppMDNSUnRegisterService(name_ptr, name_len));
+        try_netapp!(sl_NetAppMDNSUnRegisterService(name_ptr, name_len));
         Ok(())
     }
 
     pub fn netapp_ping_start(ping_params: &SlPingStartCommand,
                              family: SocketFamily)
-                             -> Result<(), SimpleLinkError> {
+                             -> Result<(), Error> {
         let params_ptr = ping_params as *const SlPingStartCommand;
 
         // Since we're provinding a callback, the ping_report parameter is ignored.
-        try_wlan!(sl_NetAppPingStart(params_ptr,
-                                     family as u8,
-                                     ptr::null_mut(),
-                                     Some(SimpleLinkPingReport)));
+        try_netapp!(sl_NetAppPingStart(params_ptr,
+                                       family as u8,
+                                       ptr::null_mut(),
+                                       Some(SimpleLinkPingReport)));
         Ok(())
     }
 
+    // Net Util
+
+    /// Fills `buf` from the NWP true random number generator and returns the
+    /// number of bytes written. This is 0 when the SDK has no TRNG access.
+    pub fn netutil_get_true_random(buf: &mut [u8]) -> Result<usize, Error> {
+        let len = cmp::min(buf.len(), u16::max_value() as usize) as u16;
+        let rc = try_device!(simplelink_get_true_random(buf.as_mut_ptr(), len));
+        Ok(rc as usize)
+    }
+
     // Net Config
 
-    pub fn netcfg_set(config: NetConfigSet, val: &[u8]) -> Result<(), SimpleLinkError> {
+    pub fn netcfg_set(config: NetConfigSet, val: &[u8]) -> Result<(), Error> {
         let config_id = ((config as u32 & 0xff00) >> 8) as u8;
         let config_opt = (config as u32 & 0x00ff) as u8;
-        try_wlan!(sl_NetCfgSet(config_id, config_opt, val.len() as u8, val.as_ptr()) as i16);
+        try_device!(sl_NetCfgSet(config_id, config_opt, val.len() as u8, val.as_ptr()));
         Ok(())
     }
 
     pub fn netcfg_get(config: NetConfigGet,
                       config_opt: Option<*mut u8>,
                       result: &mut [u8])
-                      -> Result<&mut [u8], SimpleLinkError> {
+                      -> Result<&mut [u8], Error> {
         let config_opt_ptr = if let Some(p) = config_opt {
             p
         } else {
             ptr::null_mut()
         };
         let mut result_len = result.len() as u8;
-        try_wlan!(sl_NetCfgGet(config as u8,
-                               config_opt_ptr,
-                               &mut result_len,
-                               result.as_mut_ptr()) as i16);
+        try_device!(sl_NetCfgGet(config as u8,
+                                 config_opt_ptr,
+                                 &mut result_len,
+                                 result.as_mut_ptr()));
         Ok(&mut result[0..result_len as usize])
     }
 
     pub fn netcfg_get_mac_addr(mac_addr: &mut [u8; self::SL_MAC_ADDR_LEN])
-                               -> Result<&mut [u8], SimpleLinkError> {
+                               -> Result<&mut [u8], Error> {
         SimpleLink::netcfg_get(NetConfigGet::MacAddress, None, mac_addr)
     }
 
+    /// Uses a static IPv4 configuration for the station interface. The
+    /// configuration is stored on the NWP and takes effect after a restart.
+    pub fn netcfg_set_ipv4_static(config: &Ipv4Config) -> Result<(), Error> {
+        let args = config.to_args();
+        let val = unsafe {
+            slice::from_raw_parts(&args as *const _ as *const u8,
+                                  mem::size_of::<SlNetCfgIpV4Args>())
+        };
+        SimpleLink::netcfg_set(NetConfigSet::Ipv4StaP2pClientStaticEnable, val)
+    }
+
+    /// Enables the DHCP client on the station interface. Takes effect after
+    /// a restart of the NWP.
+    pub fn netcfg_set_ipv4_dhcp() -> Result<(), Error> {
+        SimpleLink::netcfg_set(NetConfigSet::Ipv4StaP2pClientDhcpEnable, &[1])
+    }
+
+    /// Reads the current addressing of the station interface.
+    pub fn netcfg_get_ipv4_info() -> Result<Ipv4Info, Error> {
+        let mut dhcp: u8 = 0;
+        let args = try!(SimpleLink::netcfg_get_ipv4_args(NetConfigGet::Ipv4StaP2pClientGetInfo,
+                                                         Some(&mut dhcp as *mut u8)));
+        Ok(Ipv4Info {
+            config: Ipv4Config::from_args(&args),
+            dhcp: dhcp != 0,
+        })
+    }
+
+    /// Returns the IPv6 addressing of the station interface. The SDK has no
+    /// netcfg options for IPv6: the NWP autoconfigures the interface and
+    /// reports it through the IPv6 acquired event, so this is None until
+    /// that event was received.
+    pub fn netcfg_get_ipv6_info() -> Option<Ipv6Info> {
+        let mut ip: [u32; 4] = [0; 4];
+        let mut gateway: [u32; 4] = [0; 4];
+        let mut dns: [u32; 4] = [0; 4];
+        let acquired = unsafe {
+            simplelink_get_ipv6_info(ip.as_mut_ptr(), gateway.as_mut_ptr(), dns.as_mut_ptr())
+        };
+        if !acquired {
+            return None;
+        }
+        Some(Ipv6Info {
+            ip: Ipv6Addr::from_words(&ip),
+            gateway: Ipv6Addr::from_words(&gateway),
+            dns: Ipv6Addr::from_words(&dns),
+        })
+    }
+
+    /// Reads the addressing of the AP (or P2P group owner) interface.
+    pub fn netcfg_get_ipv4_ap_info() -> Result<Ipv4Config, Error> {
+        let args = try!(SimpleLink::netcfg_get_ipv4_args(NetConfigGet::Ipv4ApP2pGoGetInfo, None));
+        Ok(Ipv4Config::from_args(&args))
+    }
+
+    fn netcfg_get_ipv4_args(config: NetConfigGet,
+                            config_opt: Option<*mut u8>)
+                            -> Result<SlNetCfgIpV4Args, Error> {
+        let mut args: SlNetCfgIpV4Args = Default::default();
+        {
+            let val = unsafe {
+                slice::from_raw_parts_mut(&mut args as *mut _ as *mut u8,
+                                          mem::size_of::<SlNetCfgIpV4Args>())
+            };
+            try!(SimpleLink::netcfg_get(config, config_opt, val));
+        }
+        Ok(args)
+    }
+
     // WLAN
 
-    pub fn wlan_delete_profile(index: i16) -> Result<(), SimpleLinkError> {
+    /// Stores a profile on the NWP and returns its index. Profiles are used
+    /// by the NWP to reconnect automatically when the auto connection policy
+    /// is set.
+    pub fn wlan_add_profile(ssid: &str,
+                            mac_addr: &[u8],
+                            sec_params: Option<SlSecParams>,
+                            sec_params_ext: Option<SlSecParamsExt>,
+                            priority: u32)
+                            -> Result<i16, Error> {
+        let mac_addr_ptr = if mac_addr.len() > 0 {
+            mac_addr.as_ptr()
+        } else {
+            ptr::null()
+        };
+        let sec_params_ptr =
+            sec_params.as_ref().map(|r| r as *const SlSecParams).unwrap_or(ptr::null());
+        let sec_params_ext_ptr =
+            sec_params_ext.as_ref().map(|r| r as *const SlSecParamsExt).unwrap_or(ptr::null());
+        let index = try_wlan!(sl_WlanProfileAdd(ssid.as_ptr(),
+                                                ssid.len() as i16,
+                                                mac_addr_ptr,
+                                                sec_params_ptr,
+                                                sec_params_ext_ptr,
+                                                priority,
+                                                0));
+        Ok(index)
+    }
+
+    pub fn wlan_delete_profile(index: i16) -> Result<(), Error> {
         try_wlan!(sl_WlanProfileDel(index));
         Ok(())
     }
@@ -188,7 +815,17 @@ impl SimpleLink {
                         mac_addr: &[u8],
                         sec_params: Option<SlSecParams>,
                         sec_params_ext: Option<SlSecParamsExt>)
-                        -> Result<(), SimpleLinkError> {
+                        -> Result<(), Error> {
+        SimpleLink::wlan_connect_bytes(ssid.as_bytes(), mac_addr, sec_params, sec_params_ext)
+    }
+
+    /// Like `wlan_connect`, for SSIDs and P2P device names which aren't
+    /// UTF-8.
+    pub fn wlan_connect_bytes(ssid: &[u8],
+                              mac_addr: &[u8],
+                              sec_params: Option<SlSecParams>,
+                              sec_params_ext: Option<SlSecParamsExt>)
+                              -> Result<(), Error> {
         let ssid_ptr = ssid.as_ptr();
         let ssid_len = ssid.len() as i16;
         let mac_addr_len = mac_addr.len();
@@ -209,38 +846,271 @@ impl SimpleLink {
         Ok(())
     }
 
-    pub fn wlan_disconnect() -> Result<(), SimpleLinkError> {
+    /// Starts a WPS enrollment with the access point named `ssid` and waits
+    /// up to `timeout_ms` for the connection to be established.
+    ///
+    /// The NWP stores the credentials obtained through WPS as a profile; once
+    /// connected, the connection policy is switched to auto so this profile
+    /// is used again after a reset.
+    pub fn wlan_wps_connect(ssid: &str,
+                            method: WpsMethod,
+                            timeout_ms: u32)
+                            -> Result<(), Error> {
+        let sec_params = match method {
+            WpsMethod::PushButton => SlSecParams::wps_push_button(),
+            WpsMethod::Pin(pin) => {
+                let valid_len = pin.len() == 4 || pin.len() == 8;
+                if !valid_len || !pin.bytes().all(|c| c >= b'0' && c <= b'9') {
+                    let err = SimpleLinkError::Wlan(WlanError::WPS_NO_PIN_OR_WRONG_PIN_LEN);
+                    return Err(err.into());
+                }
+                SlSecParams::wps_pin(pin)
+            }
+        };
+
+        try!(SimpleLink::wlan_connect(ssid, &[], Some(sec_params), None));
+
+        if !SimpleLink::wait_for(|| SimpleLink::is_connected(), timeout_ms) {
+            // Abort the enrollment so that the NWP doesn't keep trying in the
+            // background.
+            let _ = SimpleLink::wlan_disconnect();
+            return Err(SimpleLinkError::Osi(OsiError::OSI_TIMEOUT).into());
+        }
+
+        try!(SimpleLink::wlan_set_policy(WlanPolicy::Connection(ConnectionPolicy::default())));
+        Ok(())
+    }
+
+    pub fn wlan_disconnect() -> Result<(), Error> {
         try_wlan!(sl_WlanDisconnect());
         Ok(())
     }
 
-    pub fn wlan_set(config: WlanConfig, val: &[u8]) -> Result<(), SimpleLinkError> {
+    pub fn wlan_set(config: WlanConfig, val: &[u8]) -> Result<(), Error> {
         let config_id = ((config as u32 & 0xff00) >> 8) as u16;
         let config_opt = (config as u32 & 0x00ff) as u16;
         try_wlan!(sl_WlanSet(config_id, config_opt, val.len() as u16, val.as_ptr()));
         Ok(())
     }
 
-    pub fn wlan_set_mode(mode: WlanMode) -> Result<WlanMode, SimpleLinkError> {
+    pub fn wlan_get(config: WlanConfig, val: &mut [u8]) -> Result<&mut [u8], Error> {
+        let config_id = ((config as u32 & 0xff00) >> 8) as u16;
+        let mut config_opt = (config as u32 & 0x00ff) as u16;
+        let mut len = val.len() as u16;
+        try_wlan!(sl_WlanGet(config_id, &mut config_opt, &mut len, val.as_mut_ptr()));
+        Ok(&mut val[0..len as usize])
+    }
+
+    pub fn wlan_set_mode(mode: WlanMode) -> Result<WlanMode, Error> {
         let rc = try_wlan!(sl_WlanSetMode(mode as u8));
         Ok(try!(WlanMode::try_from(rc)))
     }
 
-    pub fn wlan_set_policy(policy: Policy, val: &[u8]) -> Result<(), SimpleLinkError> {
-        let policy_type = ((policy as u32 & 0xff00) >> 8) as u8;
-        let policy_policy = (policy as u32 & 0x00ff) as u8;
-        try_wlan!(sl_WlanPolicySet(policy_type, policy_policy, val.as_ptr(), val.len() as u8));
+    pub fn wlan_set_policy(policy: WlanPolicy) -> Result<(), Error> {
+        let mut options = [0; POLICY_OPTIONS_LEN_MAX];
+        let (value, len) = policy.encode(&mut options);
+        let options_ptr = if len > 0 {
+            options.as_ptr()
+        } else {
+            ptr::null()
+        };
+        try_wlan!(sl_WlanPolicySet(policy.policy_type() as u8, value, options_ptr, len as u8));
         Ok(())
     }
 
+    /// Reads the raw value of a policy: the policy value, or its options
+    /// when it has some.
+    pub fn wlan_get_policy(policy_type: PolicyType,
+                           val: &mut [u8])
+                           -> Result<&mut [u8], Error> {
+        let mut len = val.len() as u8;
+        try_wlan!(sl_WlanPolicyGet(policy_type as u8, 0, val.as_mut_ptr(), &mut len));
+        let len = cmp::min(len as usize, val.len());
+        Ok(&mut val[0..len])
+    }
+
+    pub fn wlan_get_connection_policy() -> Result<ConnectionPolicy, Error> {
+        let mut val = [0; POLICY_OPTIONS_LEN_MAX];
+        let val = try!(SimpleLink::wlan_get_policy(PolicyType::Connection, &mut val));
+        match val.first() {
+            Some(bits) => Ok(ConnectionPolicy::from_bits_truncate(*bits)),
+            None => Err(SimpleLinkError::ValueError("ConnectionPolicy length", 0).into()),
+        }
+    }
+
+    pub fn wlan_get_power_policy() -> Result<PowerPolicy, Error> {
+        let mut val = [0; POLICY_OPTIONS_LEN_MAX];
+        let val = try!(SimpleLink::wlan_get_policy(PolicyType::Power, &mut val));
+        PowerPolicy::decode(val)
+    }
+
+    /// Fills `entries` with the access points found by the last scans. The
+    /// scan policy has to be enabled, and the NWP in station mode.
+    pub fn wlan_get_network_list(entries: &mut [SlWlanNetworkEntry])
+                                 -> Result<&mut [SlWlanNetworkEntry], Error> {
+        let count = cmp::min(entries.len(), u8::max_value() as usize) as u8;
+        let found = try_wlan!(sl_WlanGetNetworkList(0, count, entries.as_mut_ptr()));
+        Ok(&mut entries[..found as usize])
+    }
+
+    // WLAN Rx Statistics
+
+    pub fn wlan_rx_stat_start() -> Result<(), Error> {
+        try_wlan!(sl_WlanRxStatStart());
+        Ok(())
+    }
+
+    pub fn wlan_rx_stat_stop() -> Result<(), Error> {
+        try_wlan!(sl_WlanRxStatStop());
+        Ok(())
+    }
+
+    /// Reads the statistics collected since the start or the previous read;
+    /// the NWP clears them on each read.
+    pub fn wlan_rx_stat_get() -> Result<SlGetRxStatResponse, Error> {
+        let mut rx_stat: SlGetRxStatResponse = Default::default();
+        try_wlan!(sl_WlanRxStatGet(&mut rx_stat, 0));
+        Ok(rx_stat)
+    }
+
     // WLAN Rx Filter
 
     pub fn wlan_rx_filter(op: WlanRxFilterOp,
                           buf: &WlanRxFilterOpBuf)
-                          -> Result<(), SimpleLinkError> {
+                          -> Result<(), Error> {
         let buf_size = mem::size_of::<WlanRxFilterOpBuf>() as u16;
         let buf_ptr: *const u8 = buf as *const _ as *const u8;
-        try_wlan!(sl_WlanRxFilterSet(op as u8, buf_ptr, buf_size));
+        try_rx_filter!(sl_WlanRxFilterSet(op as u8, buf_ptr, buf_size));
+        Ok(())
+    }
+
+    /// Adds an RX filter and returns its ID. See `rx_filter::RxFilter` for
+    /// a typed way of building the rule.
+    pub fn wlan_rx_filter_add(rule_type: RxFilterRuleType,
+                              flags: u8,
+                              rule: &SlRxFilterRule,
+                              trigger: &SlRxFilterTrigger,
+                              action: &SlRxFilterAction)
+                              -> Result<RxFilterId, Error> {
+        let mut filter_id: i8 = 0;
+        try_rx_filter!(sl_WlanRxFilterAdd(rule_type as u8,
+                                          flags,
+                                          rule,
+                                          trigger,
+                                          action,
+                                          &mut filter_id));
+        Ok(RxFilterId(filter_id))
+    }
+}
+
+/// Keeps the NWP running: returned by `SimpleLinkSession::start`, it stops
+/// the NWP when dropped. Only one session can be open at a time, and its
+/// starts, restarts and stops are serialised with the other tasks.
+pub struct SimpleLinkSession {
+    mode: WlanMode,
+}
+
+impl SimpleLinkSession {
+    /// Starts the NWP in the role it was left in.
+    pub fn start() -> Result<SimpleLinkSession, Error> {
+        let mut open = try!(SimpleLinkSession::lock());
+        if *open {
+            return Err(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED).into());
+        }
+        try!(SimpleLinkSession::stop_leftover());
+        SimpleLink::init_app_variables();
+        let mode = try!(SimpleLink::start());
+        *open = true;
+        Ok(SimpleLinkSession { mode: mode })
+    }
+
+    /// Starts the NWP in `mode`, restarting it if it came up in another
+    /// role.
+    pub fn start_in_mode(mode: WlanMode) -> Result<SimpleLinkSession, Error> {
+        let mut open = try!(SimpleLinkSession::lock());
+        if *open {
+            return Err(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED).into());
+        }
+        try!(SimpleLinkSession::stop_leftover());
+        if let Err(e) = SimpleLink::start_in_mode(mode) {
+            // Switching the role may fail after the NWP was started.
+            if SimpleLink::is_started() {
+                let _ = SimpleLink::stop(SL_STOP_TIMEOUT);
+            }
+            return Err(e);
+        }
+        *open = true;
+        Ok(SimpleLinkSession { mode: mode })
+    }
+
+    /// The role the NWP is running in.
+    pub fn mode(&self) -> WlanMode {
+        self.mode
+    }
+
+    /// Switches the NWP to `mode`. The NWP is restarted for the new role to
+    /// take effect.
+    pub fn switch_mode(&mut self, mode: WlanMode) -> Result<(), Error> {
+        let _lock = try!(SimpleLinkSession::lock());
+        if mode == self.mode {
+            return Ok(());
+        }
+        try!(SimpleLink::wlan_set_mode(mode));
+        try!(self.restart_locked());
+        if self.mode != mode {
+            return Err(SimpleLinkError::Wlan(WlanError::INVALID_ROLE).into());
+        }
         Ok(())
     }
+
+    /// Restarts the NWP, eg. for settings applied on start to take effect.
+    pub fn restart(&mut self) -> Result<(), Error> {
+        let _lock = try!(SimpleLinkSession::lock());
+        self.restart_locked()
+    }
+
+    /// Stops the NWP and reports the errors dropping the session ignores.
+    /// If the NWP doesn't stop, the session is dropped, which tries again.
+    pub fn stop(self) -> Result<(), Error> {
+        {
+            let mut open = try!(SimpleLinkSession::lock());
+            try!(SimpleLink::stop(SL_STOP_TIMEOUT));
+            *open = false;
+        }
+        // Already stopped.
+        mem::forget(self);
+        Ok(())
+    }
+
+    // Stops the NWP left running by a session that failed to stop it, as
+    // sl_Start can't be called again while it runs.
+    fn stop_leftover() -> Result<(), Error> {
+        if SimpleLink::is_started() {
+            try!(SimpleLink::stop(SL_STOP_TIMEOUT));
+        }
+        Ok(())
+    }
+
+    fn restart_locked(&mut self) -> Result<(), Error> {
+        try!(SimpleLink::stop(SL_STOP_TIMEOUT));
+        SimpleLink::init_app_variables();
+        self.mode = try!(SimpleLink::start());
+        Ok(())
+    }
+
+    fn lock() -> Result<MutexGuard<'static, bool>, Error> {
+        SESSION.lock(Duration::infinite())
+            .map_err(|_| Error::from(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED)))
+    }
+}
+
+impl Drop for SimpleLinkSession {
+    fn drop(&mut self) {
+        if let Ok(mut open) = SimpleLinkSession::lock() {
+            *open = false;
+            if SimpleLink::is_started() {
+                let _ = SimpleLink::stop(SL_STOP_TIMEOUT);
+            }
+        }
+    }
 }
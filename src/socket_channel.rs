@@ -2,48 +2,87 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use core::mem;
-use cc3200_sys::socket::{Family, Protocol, SocketError, SocketType, RawSocket, sl_Socket,
-                         sl_Close, sl_Connect, sl_Send, sl_Recv, SlSockAddrIn_t, SlSockAddr_t,
-                         sl_Htonl, sl_Htons};
-use simplelink::SimpleLink;
+// Sockets: `SocketChannel` for TCP, used as a smallhttp channel or through
+// the io traits, and `UdpSocket` for datagrams. Both are closed when
+// dropped.
+
+use cc3200_sys::socket::{Family, OptionLevel, OptionName, Protocol, SlSockAddrStorage_t,
+                         SlSocklen_t, SlTimeval_t, SocketError, SocketType, RawSocket, sl_Socket,
+                         sl_Accept, sl_Bind, sl_Close, sl_Connect, sl_Listen, sl_Send, sl_Recv,
+                         sl_RecvFrom, sl_SendTo, sl_SetSockOpt};
+use core::{cmp, mem, ptr};
+use error::{Error, ErrorKind};
+use io::{Read, Write};
+use net::{IpAddr, Ipv4Addr, SocketAddr};
+use simplelink::{SimpleLink, SimpleLinkError};
 use smallhttp::traits::{Channel, ChannelError};
 
 #[derive(Clone)]
 pub struct SocketChannel {
-    inner: RawSocket,
+    // None until `open` created the socket in the family of the destination.
+    inner: Option<RawSocket>,
 }
 
 impl SocketChannel {
-    pub fn new() -> Option<Self> {
-        let socket = unsafe {
-            sl_Socket(Family::AF_INET,
-                      SocketType::SOCK_STREAM,
-                      Protocol::IPPROTO_TCP)
-        };
-        if socket >= 0 {
-            Some(SocketChannel { inner: socket })
-        } else {
-            None
+    /// A channel for `Channel::open`, which creates the socket once the
+    /// family of the destination address is known.
+    pub fn new() -> Result<Self, Error> {
+        try!(SimpleLink::check_started());
+        Ok(SocketChannel { inner: None })
+    }
+
+    /// Opens a TCP socket bound to `port` on all the IPv4 interfaces, and
+    /// listening for `backlog` connections.
+    pub fn listen(port: u16, backlog: i16) -> Result<Self, Error> {
+        try!(SimpleLink::check_started());
+        let channel = SocketChannel { inner: Some(try!(tcp_socket(Family::AF_INET))) };
+        let socket = try!(channel.raw());
+        try!(bind(socket, port));
+        try!(check(unsafe { sl_Listen(socket, backlog) }));
+        Ok(channel)
+    }
+
+    /// Waits for a connection on a listening socket.
+    pub fn accept(&self) -> Result<SocketChannel, Error> {
+        let listener = try!(self.raw());
+        let socket = unsafe { sl_Accept(listener, ptr::null_mut(), ptr::null_mut()) };
+        if socket < 0 {
+            return Err(SimpleLinkError::from_socket_code(socket as i32).into());
         }
+        Ok(SocketChannel { inner: Some(socket) })
+    }
+
+    /// Makes the reads and `accept` fail after `secs` seconds without data.
+    pub fn set_recv_timeout(&self, secs: u32) -> Result<(), Error> {
+        set_recv_timeout(try!(self.raw()), secs)
+    }
+
+    fn raw(&self) -> Result<RawSocket, Error> {
+        self.inner.ok_or(SocketError::EBADF.into())
+    }
 
+    fn close(&mut self) {
+        if let Some(socket) = self.inner.take() {
+            unsafe {
+                sl_Close(socket);
+            }
+        }
     }
 }
 
 impl Drop for SocketChannel {
     fn drop(&mut self) {
-        unsafe {
-            sl_Close(self.inner);
-        }
+        self.close();
     }
 }
 
 impl Channel for SocketChannel {
     // Opens a channel to the given host:port destination, with TLS support is needed.
     fn open(&mut self, host: &str, port: u16, tls: bool) -> Result<(), ChannelError> {
-        // Convert the host name into a socket address.
-        let addr = match SimpleLink::netapp_get_host_by_name(host) {
-            Ok(addr) => addr,
+        // Convert the host name into a socket address. IPv6 is used when the
+        // host only has an AAAA record.
+        let ip = match SimpleLink::netapp_resolve(host) {
+            Ok(ip) => ip,
             Err(err) => {
                 error!("Unable to resolve {} : {}", host, err);
                 return Err(ChannelError::InvalidHostName);
@@ -55,22 +94,25 @@ impl Channel for SocketChannel {
             return Err(ChannelError::TlsUnsupported);
         }
 
-        let inaddr = SlSockAddrIn_t {
-            sin_family: Family::AF_INET,
-            sin_port: unsafe { sl_Htons(port) },
-            sin_addr: unsafe { sl_Htonl(addr) },
-            sin_zero: [0; 8],
-        };
-        let sockaddr =
-            unsafe { ::core::intrinsics::transmute::<SlSockAddrIn_t, SlSockAddr_t>(inaddr) };
-        let ret = unsafe {
-            sl_Connect(self.inner,
-                       &sockaddr as *const SlSockAddr_t,
-                       mem::size_of::<SlSockAddrIn_t>() as i16)
+        // A reopened channel gets a new socket.
+        self.close();
+        let socket = match tcp_socket(ip.family()) {
+            Ok(socket) => socket,
+            Err(err) => {
+                debug!("Unable to create a {:?} socket: {}", ip.family(), err);
+                return Err(ChannelError::SomethingWentWrong);
+            }
         };
+        self.inner = Some(socket);
+
+        let addr = SocketAddr::new(ip, port);
+        let (sockaddr, len) = addr.to_raw();
+        let ret = unsafe { sl_Connect(socket, sockaddr.as_ptr(), len) };
 
-        if ret != SocketError::SOC_OK {
-            debug!("Unable to connect to {:?} : {:?}", sockaddr, ret);
+        if ret < 0 {
+            debug!("Unable to connect to {} : {}",
+                   addr,
+                   SimpleLinkError::from_socket_code(ret as i32));
             Err(ChannelError::UnableToConnect)
         } else {
             Ok(())
@@ -81,8 +123,9 @@ impl Channel for SocketChannel {
     // or an error.
     fn send(&mut self, data: &[u8], len: usize) -> Result<usize, ChannelError> {
         assert!(len < i16::max_value() as usize);
+        let socket = try!(self.inner.ok_or(ChannelError::SomethingWentWrong));
         let ret = unsafe {
-            sl_Send(self.inner, data.as_ptr(), len as i16, 0 /* flags */)
+            sl_Send(socket, data.as_ptr(), len as i16, 0 /* flags */)
         };
         // Rustc doesn't seem to return a SizeOrError but just a i16...
         if ret >= 0 {
@@ -97,8 +140,9 @@ impl Channel for SocketChannel {
     // or an error.
     fn recv(&mut self, data: &mut [u8], max_len: usize) -> Result<usize, ChannelError> {
         assert!(max_len < i16::max_value() as usize);
+        let socket = try!(self.inner.ok_or(ChannelError::SomethingWentWrong));
         let ret = unsafe {
-            sl_Recv(self.inner,
+            sl_Recv(socket,
                     data.as_ptr() as *mut u8,
                     max_len as i16,
                     0 /* flags */)
@@ -113,3 +157,155 @@ impl Channel for SocketChannel {
         }
     }
 }
+
+// Lets the sockets be used with the io helpers, eg. io::copy from a file.
+// The end of the stream reads as 0 bytes.
+impl Read for SocketChannel {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = cmp::min(buf.len(), i16::max_value() as usize);
+        let socket = try!(self.raw());
+        let ret = unsafe { sl_Recv(socket, buf.as_mut_ptr(), len as i16, 0 /* flags */) };
+        if ret < 0 {
+            Err(SimpleLinkError::from_socket_code(ret as i32).into())
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
+
+impl Write for SocketChannel {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let len = cmp::min(buf.len(), i16::max_value() as usize);
+        let socket = try!(self.raw());
+        let ret = unsafe { sl_Send(socket, buf.as_ptr(), len as i16, 0 /* flags */) };
+        if ret < 0 {
+            Err(SimpleLinkError::from_socket_code(ret as i32).into())
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
+
+pub struct UdpSocket {
+    inner: RawSocket,
+}
+
+impl UdpSocket {
+    /// Opens a UDP socket bound to `port` on all the IPv4 interfaces.
+    pub fn bind(port: u16) -> Result<Self, Error> {
+        try!(SimpleLink::check_started());
+        let socket = try!(open_socket(Family::AF_INET,
+                                      SocketType::SOCK_DGRAM,
+                                      Protocol::IPPROTO_UDP));
+        let udp = UdpSocket { inner: socket };
+        try!(bind(udp.inner, port));
+        Ok(udp)
+    }
+
+    /// Makes `recv_from` fail after `secs` seconds without a datagram.
+    pub fn set_recv_timeout(&self, secs: u32) -> Result<(), Error> {
+        set_recv_timeout(self.inner, secs)
+    }
+
+    /// Receives a datagram, and returns its length and sender.
+    pub fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddr), Error> {
+        let mut from = SlSockAddrStorage_t::default();
+        let mut from_len = mem::size_of::<SlSockAddrStorage_t>() as SlSocklen_t;
+        let len = cmp::min(buf.len(), i16::max_value() as usize);
+        let ret = unsafe {
+            sl_RecvFrom(self.inner,
+                        buf.as_mut_ptr(),
+                        len as i16,
+                        0 /* flags */,
+                        from.as_mut_ptr(),
+                        &mut from_len)
+        };
+        if ret < 0 {
+            return Err(SimpleLinkError::from_socket_code(ret as i32).into());
+        }
+        match SocketAddr::from_raw(&from) {
+            Some(from) => Ok((ret as usize, from)),
+            None => Err(SocketError::SOC_ERROR.into()),
+        }
+    }
+
+    pub fn send_to(&self, buf: &[u8], to: &SocketAddr) -> Result<usize, Error> {
+        let (sockaddr, addr_len) = to.to_raw();
+        let len = cmp::min(buf.len(), i16::max_value() as usize);
+        let ret = unsafe {
+            sl_SendTo(self.inner,
+                      buf.as_ptr(),
+                      len as i16,
+                      0 /* flags */,
+                      sockaddr.as_ptr(),
+                      addr_len)
+        };
+        if ret < 0 {
+            Err(SimpleLinkError::from_socket_code(ret as i32).into())
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
+
+impl Drop for UdpSocket {
+    fn drop(&mut self) {
+        unsafe {
+            sl_Close(self.inner);
+        }
+    }
+}
+
+fn open_socket(family: Family, kind: SocketType, protocol: Protocol) -> Result<RawSocket, Error> {
+    let socket = unsafe { sl_Socket(family, kind, protocol) };
+    if socket < 0 {
+        Err(SimpleLinkError::from_socket_code(socket as i32).into())
+    } else {
+        Ok(socket)
+    }
+}
+
+fn tcp_socket(family: Family) -> Result<RawSocket, Error> {
+    open_socket(family, SocketType::SOCK_STREAM, Protocol::IPPROTO_TCP)
+}
+
+fn bind(socket: RawSocket, port: u16) -> Result<(), Error> {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::unspecified()), port);
+    let (sockaddr, len) = addr.to_raw();
+    check(unsafe { sl_Bind(socket, sockaddr.as_ptr(), len) })
+}
+
+fn set_recv_timeout(socket: RawSocket, secs: u32) -> Result<(), Error> {
+    let timeout = SlTimeval_t {
+        tv_sec: secs,
+        tv_usec: 0,
+    };
+    check(unsafe {
+        sl_SetSockOpt(socket,
+                      OptionLevel::SOL_SOCKET,
+                      OptionName::SO_RCVTIMEO,
+                      &timeout as *const _ as *const u8,
+                      mem::size_of::<SlTimeval_t>() as SlSocklen_t)
+    })
+}
+
+fn check(ret: i16) -> Result<(), Error> {
+    if ret < 0 { Err(SimpleLinkError::from_socket_code(ret as i32).into()) } else { Ok(()) }
+}
+
+// The `Channel` trait is the one of smallhttp, and keeps its errors; this
+// lets the code using both the channel and the rest of the crate return an
+// `Error`.
+impl From<ChannelError> for Error {
+    fn from(err: ChannelError) -> Error {
+        match err {
+            ChannelError::InvalidHostName => Error::new(ErrorKind::NotFound, "invalid host name"),
+            ChannelError::TlsUnsupported => {
+                Error::new(ErrorKind::InvalidInput, "TLS is not supported")
+            }
+            ChannelError::UnableToConnect => Error::new(ErrorKind::Network, "unable to connect"),
+            ChannelError::EndOfStream => Error::new(ErrorKind::Network, "end of stream"),
+            ChannelError::SomethingWentWrong => Error::new(ErrorKind::Network, "channel error"),
+        }
+    }
+}
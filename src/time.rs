@@ -3,6 +3,7 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use collections::string::String;
+use freertos_rs::FreeRtosUtils;
 use numeric_utils::format_int_into;
 
 pub type Seconds = i64;
@@ -178,6 +179,12 @@ impl Tm {
     }
 }
 
+/// The milliseconds since the scheduler started, from the tick count. It
+/// wraps around, so durations are measured with `wrapping_sub`.
+pub fn now_ms() -> u32 {
+    FreeRtosUtils::get_tick_count_duration().to_ms()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -0,0 +1,220 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Image updates.
+//
+// The boot info file tells the bootloader which image to run, and whether
+// it is being tested. Only the file system and the FLC library are used, so
+// this module also builds on the host where the tests run it against the
+// simulated file system of cc3200_sys::host.
+
+extern crate cc3200_sys;
+
+use core::result::Result;
+
+use self::cc3200_sys::simplelink::*;
+use error::{Error, ErrorKind};
+use io::{Catalog, File, FileFlags, Read, Write};
+
+macro_rules! ignore {
+    ($e:expr) => ({
+        match $e {
+            _ => { },
+        }
+    })
+}
+
+#[derive(Copy, Clone)]
+pub enum ImageStatus {
+    TESTING,
+    TESTREADY,
+    NOTEST
+}
+
+impl ImageStatus {
+    pub fn from_u32(value: u32) -> Result<ImageStatus, Error> {
+        let image_status = match value {
+            IMG_STATUS_TESTING   => ImageStatus::TESTING,
+            IMG_STATUS_TESTREADY => ImageStatus::TESTREADY,
+            IMG_STATUS_NOTEST    => ImageStatus::NOTEST,
+            value => {
+                println!("unknwon image status {}", value);
+                return Err(Error::new(ErrorKind::InvalidInput, "unknown image status"));
+            }
+        };
+        Ok(image_status)
+    }
+    pub fn to_u32(image_status: ImageStatus) -> Result<u32, Error> {
+        let value = match image_status {
+            ImageStatus::NOTEST    => IMG_STATUS_NOTEST,
+            ImageStatus::TESTREADY => IMG_STATUS_TESTREADY,
+            ImageStatus::TESTING   => IMG_STATUS_TESTING
+        };
+        Ok(value)
+    }
+}
+
+pub struct BootInfo {
+    pub active_image: u8,
+    pub image_status: ImageStatus,
+}
+
+impl BootInfo {
+
+    /// Create a BootInfo structure
+    pub fn new(active_image: u8, image_status: ImageStatus) -> BootInfo {
+        BootInfo { active_image: active_image,
+                   image_status: image_status }
+    }
+
+    /// Create a BootInfo structure the represents the factory reset
+    pub fn factory_reset() -> BootInfo {
+        BootInfo::new(0, ImageStatus::NOTEST)
+    }
+
+    /// Returns the currently active image's filename
+    pub fn image_filename(&self) -> &'static str {
+        match self.active_image {
+            0 => IMG_FACTORY_DEFAULT,
+            1 => IMG_USER_1,
+            2 => IMG_USER_2,
+            _ => IMG_FACTORY_DEFAULT
+        }
+    }
+
+    /// Returns the filename of the image to update
+    pub fn next_image_filename(&self) -> &'static str {
+        // If we booted from image 1, we update image 2; in any other case
+        // we update image 1. This mirrors the behavior of the TI SDK.
+        match self.active_image {
+            1 => IMG_USER_2,
+            _ => IMG_USER_1
+        }
+    }
+}
+
+pub struct Update { }
+
+impl Update {
+
+    /// Sets the application to test mode
+    pub fn test() -> bool {
+        let res = unsafe {
+            sl_extlib_FlcTest(FLC_TEST_RESET_MCU |
+                              FLC_TEST_RESET_MCU_WITH_APP)
+        };
+        Update::reset_is_required(res)
+    }
+
+    /// Returns true if the application runs in testing mode, false otherwise
+    pub fn is_testing() -> bool {
+        let res = unsafe {
+            sl_extlib_FlcIsPendingCommit()
+        };
+        res != 0
+    }
+
+    /// Commits all changes, moving the application state from 'testing' to 'stable'
+    pub fn commit() -> bool {
+        let res = unsafe {
+            sl_extlib_FlcCommit(FLC_COMMITED)
+        };
+        Update::reset_is_required(res)
+    }
+
+    /// Aborts the update
+    pub fn abort() -> bool {
+        let res = unsafe {
+            sl_extlib_FlcCommit(FLC_NOT_COMMITED)
+        };
+        Update::reset_is_required(res)
+    }
+
+    /// Reads or creates the device's boot-info structure
+    pub fn get_boot_info() -> Result<BootInfo, Error> {
+        let boot_info = match Update::read_boot_info() {
+            Ok(boot_info) => boot_info,
+            Err(_) => {
+                let boot_info = BootInfo::factory_reset();
+                Update::write_boot_info(&boot_info)?;
+                boot_info
+            }
+        };
+        Ok(boot_info)
+    }
+
+    /// Opens the next image for updating; the filename is selected automatically
+    /// and recorded in the default catalog. Closing the image with
+    /// `Catalog::close` records its length.
+    pub fn next_image(max_len: usize) -> Result<File, Error> {
+        // The TI filesystem doesn't resize files dynamically. Hence, if
+        // the image file already exists and is too small, we cannot use
+        // it for the update.
+        //
+        // Below, we read the boot-info file to get next image's filename and
+        // its size. We remove the existing file if it's too small. The latter
+        // call to File::create() will create a new file.
+        let boot_info = Update::get_boot_info()?;
+        let file_name = boot_info.next_image_filename();
+        Catalog::with_default(|catalog| {
+            match File::get_info(file_name) {
+                Ok(file_info) => {
+                    let allocated_length = file_info.allocated_length as usize;
+                    if allocated_length < max_len {
+                        catalog.remove(file_name)?;
+                    }
+                },
+                Err(_) => {
+                    // No file info available; unconditionally remove
+                    // file if it exists.
+                    ignore!(catalog.remove(file_name));
+                }
+            };
+            catalog.create(file_name, max_len, FileFlags::default(), None)
+        })
+    }
+
+    fn reset_is_required(flags: i32) -> bool {
+        (flags & (FLC_TEST_RESET_MCU | FLC_TEST_RESET_NWP)) != 0
+    }
+
+    fn encode_boot_info(boot_info: &BootInfo) -> Result<[u8; 8], Error> {
+        let active_image = boot_info.active_image;
+        let image_status = ImageStatus::to_u32(boot_info.image_status)?;
+        let mut buf: [u8; 8] = [0; 8];
+        buf[0] = active_image;
+        buf[4] = 0xff & (image_status) as u8;
+        buf[5] = 0xff & (image_status >> 8) as u8;
+        buf[6] = 0xff & (image_status >> 16) as u8;
+        buf[7] = 0xff & (image_status >> 24) as u8;
+        Ok(buf)
+    }
+
+    fn decode_boot_info(buf: [u8; 8]) -> Result<BootInfo, Error> {
+        let active_image = buf[0];
+        let image_status = ((buf[4] as u32)) |
+                           ((buf[5] as u32) << 8) |
+                           ((buf[6] as u32) << 16) |
+                           ((buf[7] as u32) << 24);
+        Ok(BootInfo::new(active_image, ImageStatus::from_u32(image_status)?))
+    }
+
+    fn write_boot_info(boot_info: &BootInfo) -> Result<(), Error> {
+
+        let buf = Update::encode_boot_info(boot_info)?;
+
+        Catalog::with_default(|catalog| {
+            let mut file = catalog.create(IMG_BOOT_INFO, 8, FileFlags::default(), None)?;
+            file.write_all(&buf[..])?;
+            catalog.close(file)
+        })
+    }
+
+    fn read_boot_info() -> Result<BootInfo, Error> {
+        let mut buf: [u8; 8] = [0; 8];
+        let mut file = File::open(IMG_BOOT_INFO)?;
+        file.read_exact(&mut buf)?;
+        Update::decode_boot_info(buf)
+    }
+}
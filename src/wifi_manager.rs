@@ -0,0 +1,315 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// A task owning the SimpleLink lifecycle in station mode: it starts the NWP,
+// connects to the configured access point and reconnects with an exponential
+// backoff whenever the connection is lost.
+
+use collections::String;
+
+use core::cmp;
+use core::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+
+use freertos_rs::{CurrentTask, Duration, Mutex, Task};
+
+use error::{Error, ErrorKind};
+use events::{Event, EventQueue, NetAppEvent, WlanEvent};
+use simplelink::{OsiError, SecurityType, SimpleLink, SimpleLinkError, SimpleLinkSession,
+                 SlPingStartCommand, SlSecParams, SocketFamily, WlanMode};
+use time::now_ms;
+
+const EVENT_QUEUE_LEN: usize = 8;
+const POLL_INTERVAL_MS: u32 = 100;
+
+const PING_INTERVAL: u32 = 1000;    // msecs
+const PING_TIMEOUT: u16 = 3000;     // msecs
+const PING_PKT_SIZE: u16 = 20;      // bytes
+const PING_ATTEMPTS: u32 = 3;
+// How long to wait for the ping-done event beyond the time the pings take.
+const PING_MARGIN_MS: u32 = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WifiState {
+    /// The manager isn't running.
+    Idle,
+    /// A connection attempt is in progress.
+    Connecting,
+    /// Associated with the access point, waiting for an IP address.
+    Connected,
+    /// Associated and an IP address was acquired.
+    GotIp,
+    /// All the connection attempts failed.
+    Failed,
+}
+
+/// What can be reached through the current connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    None,
+    Lan,
+    Internet,
+}
+
+pub struct WifiConfig {
+    pub ssid: String,
+    pub security: SecurityType,
+    pub key: String,
+
+    /// How long to wait for the connection and the IP address on each attempt.
+    pub connect_timeout_ms: u32,
+    /// Delay before the first retry; it doubles after each failure.
+    pub initial_backoff_ms: u32,
+    pub max_backoff_ms: u32,
+    /// Number of consecutive failures before giving up. `None` retries forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl WifiConfig {
+    pub fn new(ssid: &str, security: SecurityType, key: &str) -> Self {
+        WifiConfig {
+            ssid: String::from(ssid),
+            security: security,
+            key: String::from(key),
+            connect_timeout_ms: 15000,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 60000,
+            max_attempts: None,
+        }
+    }
+
+    fn security_params(&self) -> Option<SlSecParams> {
+        match self.security {
+            SecurityType::Open => None,
+            sec_type => {
+                Some(SlSecParams {
+                    sec_type: sec_type as u8,
+                    key: self.key.as_ptr(),
+                    key_len: self.key.len() as u8,
+                })
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref STATE: Mutex<WifiState> = Mutex::new(WifiState::Idle).unwrap();
+}
+
+static RUNNING: AtomicBool = ATOMIC_BOOL_INIT;
+static STOP_REQUESTED: AtomicBool = ATOMIC_BOOL_INIT;
+
+fn set_state(state: WifiState) {
+    if let Ok(mut current) = STATE.lock(Duration::infinite()) {
+        if *current != state {
+            debug!("WifiManager: {:?} -> {:?}", *current, state);
+            *current = state;
+        }
+    }
+}
+
+pub struct WifiManager { }
+
+impl WifiManager {
+    /// Starts the SimpleLink spawn task and the manager task. Only one manager
+    /// can run at a time.
+    pub fn start(config: WifiConfig) -> Result<(), Error> {
+        if RUNNING.swap(true, Ordering::SeqCst) {
+            return Err(SimpleLinkError::Osi(OsiError::OSI_OPERATION_FAILED).into());
+        }
+        STOP_REQUESTED.store(false, Ordering::SeqCst);
+
+        if let Err(e) = SimpleLink::start_spawn_task() {
+            RUNNING.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+
+        let task = Task::new()
+            .name("wifi")
+            .stack_size(2048) // 32-bit words
+            .start(move || {
+                if let Err(e) = WifiManager::run(&config) {
+                    error!("WifiManager stopped: {}", e);
+                    set_state(WifiState::Failed);
+                }
+                RUNNING.store(false, Ordering::SeqCst);
+            });
+        if task.is_err() {
+            RUNNING.store(false, Ordering::SeqCst);
+            return Err(SimpleLinkError::Osi(OsiError::OSI_MEMORY_ALLOCATION_FAILURE).into());
+        }
+        Ok(())
+    }
+
+    /// Asks the manager to disconnect and stop the NWP.
+    pub fn stop() {
+        STOP_REQUESTED.store(true, Ordering::SeqCst);
+    }
+
+    pub fn state() -> WifiState {
+        match STATE.lock(Duration::infinite()) {
+            Ok(state) => *state,
+            Err(_) => WifiState::Idle,
+        }
+    }
+
+    /// Waits up to `timeout_ms` for the manager to reach `state`. Returns
+    /// false on timeout.
+    pub fn wait_for(state: WifiState, timeout_ms: u32) -> bool {
+        let mut elapsed_ms = 0;
+        while WifiManager::state() != state {
+            if elapsed_ms >= timeout_ms {
+                return false;
+            }
+            CurrentTask::delay(Duration::ms(POLL_INTERVAL_MS));
+            elapsed_ms += POLL_INTERVAL_MS;
+        }
+        true
+    }
+
+    /// Checks the connection by pinging the gateway, and then `host` if the
+    /// gateway answered.
+    pub fn check_connection(host: &str) -> Result<Connectivity, Error> {
+        if WifiManager::state() != WifiState::GotIp {
+            return Ok(Connectivity::None);
+        }
+        if !try!(WifiManager::ping(SimpleLink::gateway_ip())) {
+            return Ok(Connectivity::None);
+        }
+        let inet_ip = try!(SimpleLink::netapp_get_host_by_name(host));
+        if try!(WifiManager::ping(inet_ip)) {
+            Ok(Connectivity::Internet)
+        } else {
+            Ok(Connectivity::Lan)
+        }
+    }
+
+    // Returns true if at least one reply was received.
+    fn ping(ip: u32) -> Result<bool, Error> {
+        let ping_params = SlPingStartCommand {
+            ping_interval_time: PING_INTERVAL,
+            ping_size: PING_PKT_SIZE,
+            ping_request_timeout: PING_TIMEOUT,
+            total_number_of_attempts: PING_ATTEMPTS,
+            flags: 0,
+            ip: ip,
+            ip1_or_padding: 0,
+            ip2_or_padding: 0,
+            ip3_or_padding: 0,
+        };
+
+        SimpleLink::clear_ping_done();
+        try!(SimpleLink::netapp_ping_start(&ping_params, SocketFamily::AF_INET));
+        let timeout_ms = PING_ATTEMPTS * (PING_INTERVAL + PING_TIMEOUT as u32) + PING_MARGIN_MS;
+        let start_ms = now_ms();
+        while !SimpleLink::is_ping_done() {
+            if now_ms().wrapping_sub(start_ms) >= timeout_ms {
+                return Err(Error::new(ErrorKind::TimedOut, "no ping report"));
+            }
+            CurrentTask::delay(Duration::ms(POLL_INTERVAL_MS));
+        }
+        Ok(SimpleLink::ping_packets_received() > 0)
+    }
+
+    fn run(config: &WifiConfig) -> Result<(), Error> {
+        let events = try!(EventQueue::subscribe(EVENT_QUEUE_LEN));
+        let session = try!(SimpleLinkSession::start_in_mode(WlanMode::ROLE_STA));
+
+        WifiManager::reconnect_loop(config, &events);
+
+        let _ = SimpleLink::wlan_disconnect();
+        try!(session.stop());
+        if WifiManager::state() != WifiState::Failed {
+            set_state(WifiState::Idle);
+        }
+        Ok(())
+    }
+
+    // Connects and reconnects until a stop is requested or the attempts are
+    // exhausted. Failures are handled here, so that `run` always stops the
+    // NWP it started.
+    fn reconnect_loop(config: &WifiConfig, events: &EventQueue) {
+        let mut backoff_ms = config.initial_backoff_ms;
+        let mut failures = 0;
+
+        while !STOP_REQUESTED.load(Ordering::SeqCst) {
+            set_state(WifiState::Connecting);
+            match WifiManager::connect(config, events) {
+                Ok(true) => {
+                    backoff_ms = config.initial_backoff_ms;
+                    failures = 0;
+                    WifiManager::monitor(events);
+                    continue;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("WifiManager: connection to {} failed: {}", config.ssid, e),
+            }
+
+            failures += 1;
+            if let Some(max_attempts) = config.max_attempts {
+                if failures >= max_attempts {
+                    set_state(WifiState::Failed);
+                    break;
+                }
+            }
+            info!("WifiManager: retrying in {} ms", backoff_ms);
+            WifiManager::sleep(backoff_ms);
+            backoff_ms = cmp::min(backoff_ms.saturating_mul(2), config.max_backoff_ms);
+        }
+    }
+
+    // Sleeps for `duration_ms`, returning early if a stop was requested.
+    fn sleep(duration_ms: u32) {
+        let mut elapsed_ms = 0;
+        while elapsed_ms < duration_ms && !STOP_REQUESTED.load(Ordering::SeqCst) {
+            CurrentTask::delay(Duration::ms(POLL_INTERVAL_MS));
+            elapsed_ms += POLL_INTERVAL_MS;
+        }
+    }
+
+    // Performs one connection attempt. Returns true once an IP is acquired,
+    // false on timeout.
+    fn connect(config: &WifiConfig, events: &EventQueue) -> Result<bool, Error> {
+        // Drop stale events from a previous connection.
+        while events.try_receive().is_some() {}
+
+        try!(SimpleLink::wlan_connect(&config.ssid, &[], config.security_params(), None));
+
+        // The receive returns early on events, so the elapsed time is read
+        // from the tick count.
+        let start_ms = now_ms();
+        while now_ms().wrapping_sub(start_ms) < config.connect_timeout_ms {
+            if STOP_REQUESTED.load(Ordering::SeqCst) {
+                return Ok(false);
+            }
+            match events.receive(POLL_INTERVAL_MS) {
+                Some(Event::Wlan(WlanEvent::Connected { .. })) => {
+                    set_state(WifiState::Connected);
+                }
+                Some(Event::Wlan(WlanEvent::Disconnected { reason })) => {
+                    debug!("WifiManager: disconnected, reason {}", reason);
+                    set_state(WifiState::Connecting);
+                }
+                Some(Event::NetApp(NetAppEvent::IpAcquired { .. })) => {
+                    set_state(WifiState::GotIp);
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+
+        let _ = SimpleLink::wlan_disconnect();
+        Ok(false)
+    }
+
+    // Waits until the connection is lost or a stop is requested.
+    fn monitor(events: &EventQueue) {
+        while !STOP_REQUESTED.load(Ordering::SeqCst) {
+            if let Some(Event::Wlan(WlanEvent::Disconnected { reason })) =
+                   events.receive(POLL_INTERVAL_MS) {
+                info!("WifiManager: connection lost, reason {}", reason);
+                return;
+            }
+        }
+    }
+}